@@ -0,0 +1,353 @@
+use std::path::Path;
+use serde::Deserialize;
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::service_level::ServiceLevel;
+use crate::package_input::PackageInput;
+
+/**
+ * DatasetFormat
+ *
+ * The file formats a dataset of packages can be written in:
+ * - CSV, with a header naming the columns
+ * - newline delimited JSON, with one object per package
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum DatasetFormat {
+    Csv, Ndjson
+}
+
+/**
+ * DatasetError
+ *
+ * Documents why a dataset of packages could not be loaded. Invalid
+ * packages report the line they were found on.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum DatasetError {
+    Unreadable(String),
+    UnknownFormat(String),
+    Invalid { line: usize, message: String }
+}
+
+/**
+ * Dataset
+ *
+ * Packages to analyse, each with an id. Both formats hold the same
+ * fields: the `width`, `height`, `length` and `mass` of a package, and
+ * optionally its `id`, its `attributes`, the `country` and `postcode`
+ * of its destination and its `service_level`. A package without an id
+ * is named after the line it is found on. CSV files separate the
+ * attributes of a package with ';'.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    packages: Vec<(String, PackageInput)>
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Record {
+    id: Option<String>,
+    width: usize,
+    height: usize,
+    length: usize,
+    mass: usize,
+    #[serde(default)]
+    attributes: Vec<Attribute>,
+    country: Option<String>,
+    postcode: Option<String>,
+    service_level: Option<ServiceLevel>
+}
+
+const COLUMNS: [&str; 9] = ["id", "width", "height", "length", "mass", "attributes", "country", "postcode", "service_level"];
+
+impl DatasetFormat {
+    pub fn from_path(path: &Path) -> Option<DatasetFormat> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(DatasetFormat::Csv),
+            "ndjson" | "jsonl" => Some(DatasetFormat::Ndjson),
+            _ => None
+        }
+    }
+}
+
+impl Dataset {
+    /**
+     * Loads a dataset from a CSV or NDJSON file, the format being
+     * derived from the extension of the file
+     */
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Dataset, DatasetError> {
+        let path = path.as_ref();
+        let format = DatasetFormat::from_path(path)
+            .ok_or_else(|| DatasetError::UnknownFormat(path.display().to_string()))?;
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| DatasetError::Unreadable(format!("{}: {}", path.display(), e)))?;
+
+        Dataset::from_source(&source, format)
+    }
+
+    /**
+     * Loads a dataset from its textual representation. Blank lines and
+     * a leading byte order mark, as written by spreadsheets, are skipped.
+     */
+    pub fn from_source(source: &str, format: DatasetFormat) -> Result<Dataset, DatasetError> {
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+        let packages = match format {
+            DatasetFormat::Csv => csv_packages(source)?,
+            DatasetFormat::Ndjson => source.lines().enumerate()
+                .map(|(index, line)| (index + 1, line))
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(line, text)| {
+                    serde_json::from_str::<Record>(text).map_err(|e| without_location(&e.to_string()))
+                        .and_then(|record| record.into_package(line))
+                        .map_err(|message| DatasetError::Invalid { line, message })
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(Dataset { packages })
+    }
+
+    pub fn packages(&self) -> &[(String, PackageInput)] {
+        &self.packages
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+// values may be quoted, so that they can hold commas, quotes and line breaks
+fn csv_packages(source: &str) -> Result<Vec<(String, PackageInput)>, DatasetError> {
+    let mut rows = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(source.as_bytes())
+        .into_records()
+        .filter(|row| !matches!(row, Ok(row) if row.iter().all(str::is_empty)))
+        .map(|row| row.map_err(|e| DatasetError::Invalid { line: line_at(source, e.position()), message: e.to_string() }));
+
+    let columns = match rows.next() {
+        Some(header) => {
+            let header = header?;
+            csv_columns(line_at(source, header.position()), &header)?
+        },
+        None => return Ok(vec![])
+    };
+
+    rows.map(|row| {
+        let row = row?;
+        let line = line_at(source, row.position());
+        csv_record(&columns, &row)
+            .and_then(|record| record.into_package(line))
+            .map_err(|message| DatasetError::Invalid { line, message })
+    }).collect()
+}
+
+// derived from the offset, as the parser doesn't count the blank lines
+// it skips, and places a row at the start of the blank lines before it
+fn line_at(source: &str, position: Option<&csv::Position>) -> usize {
+    let bytes = source.as_bytes();
+    let mut offset = position.map_or(0, |position| position.byte() as usize).min(bytes.len());
+    while offset < bytes.len() && (bytes[offset] == b'\n' || bytes[offset] == b'\r') {
+        offset += 1;
+    }
+    bytes[..offset].iter().filter(|byte| **byte == b'\n').count() + 1
+}
+
+fn csv_columns(line: usize, header: &csv::StringRecord) -> Result<Vec<String>, DatasetError> {
+    let columns: Vec<String> = header.iter().map(str::to_string).collect();
+    if let Some(unknown) = columns.iter().find(|column| !COLUMNS.contains(&column.as_str())) {
+        return Err(DatasetError::Invalid { line, message: format!("Unknown column: expecting one of {}, but got '{}'", COLUMNS.join(", "), unknown) });
+    }
+    if let Some(missing) = COLUMNS[1..5].iter().find(|required| !columns.iter().any(|column| column == *required)) {
+        return Err(DatasetError::Invalid { line, message: format!("Missing column: expecting a {} column", missing) });
+    }
+
+    Ok(columns)
+}
+
+fn csv_record(columns: &[String], row: &csv::StringRecord) -> Result<Record, String> {
+    if row.len() != columns.len() {
+        return Err(format!("Invalid row: expecting {} values, but got {}", columns.len(), row.len()));
+    }
+
+    let mut record = Record::default();
+    for (column, value) in columns.iter().zip(row.iter()) {
+        let number = || value.parse::<usize>().map_err(|_| format!("Invalid {}: expecting a whole number, but got {}", column, value));
+        let text = || Some(value.to_string()).filter(|value| !value.is_empty());
+        match column.as_str() {
+            "id" => record.id = text(),
+            "width" => record.width = number()?,
+            "height" => record.height = number()?,
+            "length" => record.length = number()?,
+            "mass" => record.mass = number()?,
+            "attributes" => record.attributes = value.split(';')
+                .map(str::trim)
+                .filter(|attribute| !attribute.is_empty())
+                .map(|attribute| attribute.parse::<Attribute>().map_err(|e| e.to_string()))
+                .collect::<Result<_, _>>()?,
+            "country" => record.country = text(),
+            "postcode" => record.postcode = text(),
+            _ => record.service_level = text().map(|level| level.parse::<ServiceLevel>().map_err(|e| e.to_string())).transpose()?
+        }
+    }
+
+    Ok(record)
+}
+
+// the JSON messages end with their own rendering of the location
+fn without_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message.to_string()
+    }
+}
+
+impl Record {
+    fn into_package(self, line: usize) -> Result<(String, PackageInput), String> {
+        let mut input = self.attributes.into_iter().fold(
+            PackageInput::new(self.width, self.height, self.length, self.mass),
+            PackageInput::with_attribute
+        );
+        match (self.country, self.postcode) {
+            (Some(country), postcode) => {
+                let mut destination = Destination::new(country).map_err(|e| e.to_string())?;
+                if let Some(postcode) = postcode {
+                    destination = destination.with_postcode(postcode).map_err(|e| e.to_string())?;
+                }
+                input = input.with_destination(destination);
+            },
+            (None, Some(_)) => return Err("Missing country: expecting a country along with the postcode".to_string()),
+            (None, None) => ()
+        }
+        if let Some(level) = self.service_level {
+            input = input.with_service_level(level);
+        }
+
+        Ok((self.id.unwrap_or_else(|| format!("line {}", line)), input))
+    }
+}
+
+impl std::fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DatasetError::Unreadable(reason) => write!(f, "Unreadable dataset: {}", reason),
+            DatasetError::UnknownFormat(path) => write!(f, "Unknown dataset format: expecting a .csv, .ndjson or .jsonl file, but got {}", path),
+            DatasetError::Invalid { line, message } => write!(f, "Invalid dataset at line {}: {}", line, message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn express_to_london() -> PackageInput {
+        PackageInput::new(10, 20, 30, 4)
+            .with_attribute(Attribute::Fragile)
+            .with_attribute(Attribute::Liquid)
+            .with_destination(Destination::new("GB").unwrap().with_postcode("EC1A 1BB").unwrap())
+            .with_service_level(ServiceLevel::Express)
+    }
+
+    #[test]
+    fn csv_datasets_are_loaded() {
+        let source = "\
+id,width,height,length,mass,attributes,country,postcode,service_level
+PKG-1,10,20,30,4,fragile;liquid,GB,EC1A 1BB,express
+  
+,100,40,30,25,,,,
+";
+        let dataset = Dataset::from_source(source, DatasetFormat::Csv).unwrap();
+        assert_eq!(dataset.packages(), &[
+            ("PKG-1".to_string(), express_to_london()),
+            ("line 4".to_string(), PackageInput::new(100, 40, 30, 25))
+        ]);
+    }
+
+    #[test]
+    fn ndjson_datasets_are_loaded() {
+        let source = r#"
+{"id": "PKG-1", "width": 10, "height": 20, "length": 30, "mass": 4, "attributes": ["fragile", "liquid"], "country": "GB", "postcode": "EC1A 1BB", "service_level": "express"}
+{"width": 100, "height": 40, "length": 30, "mass": 25}
+"#;
+        let dataset = Dataset::from_source(source, DatasetFormat::Ndjson).unwrap();
+        assert_eq!(dataset.packages(), &[
+            ("PKG-1".to_string(), express_to_london()),
+            ("line 3".to_string(), PackageInput::new(100, 40, 30, 25))
+        ]);
+    }
+
+    #[test]
+    fn csv_values_can_be_quoted() {
+        let source = "\
+id,width,height,length,mass,country,postcode
+\"PKG-1, \"\"fragile\"\" crate\",10,20,30,4,GB,\"EC1A 1BB\"
+";
+        let dataset = Dataset::from_source(source, DatasetFormat::Csv).unwrap();
+        assert_eq!(dataset.packages(), &[(
+            "PKG-1, \"fragile\" crate".to_string(),
+            PackageInput::new(10, 20, 30, 4).with_destination(Destination::new("GB").unwrap().with_postcode("EC1A 1BB").unwrap())
+        )]);
+    }
+
+    #[test]
+    fn a_leading_byte_order_mark_is_skipped() {
+        let dataset = Dataset::from_source("\u{feff}id,width,height,length,mass\r\nPKG-1,10,20,30,4\r\n", DatasetFormat::Csv).unwrap();
+        assert_eq!(dataset.packages(), &[("PKG-1".to_string(), PackageInput::new(10, 20, 30, 4))]);
+        let dataset = Dataset::from_source("\u{feff}{\"width\": 10, \"height\": 20, \"length\": 30, \"mass\": 4}", DatasetFormat::Ndjson).unwrap();
+        assert_eq!(dataset.len(), 1);
+    }
+
+    #[test]
+    fn columns_are_validated() {
+        assert_eq!(
+            Dataset::from_source("width,height,length\n", DatasetFormat::Csv),
+            Err(DatasetError::Invalid { line: 1, message: "Missing column: expecting a mass column".to_string() })
+        );
+        assert_eq!(
+            Dataset::from_source("width,height,length,mass,colour\n", DatasetFormat::Csv),
+            Err(DatasetError::Invalid {
+                line: 1,
+                message: "Unknown column: expecting one of id, width, height, length, mass, attributes, country, postcode, service_level, but got 'colour'".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_packages_are_located() {
+        let invalid = |source, format| match Dataset::from_source(source, format) {
+            Err(DatasetError::Invalid { line, message }) => (line, message),
+            other => panic!("expected an invalid dataset, but got {:?}", other)
+        };
+        assert_eq!(
+            invalid("width,height,length,mass\n1,2,3,4\n1,2,three,4\n", DatasetFormat::Csv),
+            (3, "Invalid length: expecting a whole number, but got three".to_string())
+        );
+        assert_eq!(invalid("width,height,length,mass\n1,2,3\n", DatasetFormat::Csv), (2, "Invalid row: expecting 4 values, but got 3".to_string()));
+        assert_eq!(
+            invalid("width,height,length,mass,attributes\n1,2,3,4,irregular\n", DatasetFormat::Csv).1,
+            "Unknown attribute: expecting one of fragile, hazmat, liquid, temperature_controlled, lithium_batteries, but got 'irregular'"
+        );
+        assert_eq!(
+            invalid("{\"width\": 1, \"height\": 2, \"length\": 3, \"mass\": 4, \"postcode\": \"10115\"}", DatasetFormat::Ndjson),
+            (1, "Missing country: expecting a country along with the postcode".to_string())
+        );
+        assert!(invalid("{\"width\": 1}", DatasetFormat::Ndjson).1.starts_with("missing field"));
+    }
+
+    #[test]
+    fn the_format_is_derived_from_the_extension() {
+        assert_eq!(DatasetFormat::from_path(Path::new("week-42.csv")), Some(DatasetFormat::Csv));
+        assert_eq!(DatasetFormat::from_path(Path::new("week-42.ndjson")), Some(DatasetFormat::Ndjson));
+        assert_eq!(DatasetFormat::from_path(Path::new("week-42.jsonl")), Some(DatasetFormat::Ndjson));
+        assert_eq!(DatasetFormat::from_path(Path::new("week-42.xlsx")), None);
+    }
+}
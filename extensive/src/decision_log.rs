@@ -0,0 +1,553 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::service_level::ServiceLevel;
+use crate::package_input::PackageInput;
+use crate::sort_decision::SortDecision;
+use crate::sort_error::SortError;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::SortResult;
+
+// the first line of every decision log, naming the version of its format
+const HEADER: &str = "# otta decision log v1";
+
+/**
+ * DecisionLog
+ *
+ * An append-only file recording the input and the decision of every
+ * sorted package. Every entry is written as a line holding the CRC-32
+ * checksum of its JSON contents followed by the contents, and is
+ * synced to disk before it is acknowledged. An entry that was only
+ * partly written, as when the sorter crashed, is discarded.
+ */
+pub struct DecisionLog {
+    file: File
+}
+
+/**
+ * LogEntry
+ *
+ * The input of a sorted package along with the decision it got: the
+ * version of the policy, the outcome, and the stack it was sent to.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    package: String,
+    at: DateTime<Utc>,
+    input: PackageInput,
+    policy_version: String,
+    outcome: String,
+    stack: Option<SortResult>
+}
+
+/**
+ * DecisionLogError
+ *
+ * Documents why a decision log could not be used:
+ * - the file could not be read or written
+ * - the file isn't a decision log
+ * - the entry on a line doesn't match its checksum
+ * - the entry on a line matches its checksum, but can't be parsed
+ * - a logged input can no longer be sorted
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecisionLogError {
+    Unreadable(String),
+    Unwritable(String),
+    NotALog(String),
+    ChecksumMismatch(usize),
+    Unparsable(usize, String),
+    InvalidEntry(usize, SortError)
+}
+
+/**
+ * StackChange
+ *
+ * A logged package that would be sent to another stack when replayed.
+ * Packages that must be remeasured have no stack.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackChange {
+    package: String,
+    before: Option<SortResult>,
+    after: Option<SortResult>
+}
+
+/**
+ * ReplayReport
+ *
+ * The packages of a log that would have changed stack under another
+ * policy, out of every replayed package.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayReport {
+    replayed: usize,
+    changes: Vec<StackChange>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoggedEntry {
+    package: String,
+    at: DateTime<Utc>,
+    input: LoggedInput,
+    policy_version: String,
+    outcome: String,
+    stack: Option<SortResult>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoggedInput {
+    width: usize,
+    height: usize,
+    length: usize,
+    mass: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attributes: Vec<Attribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    destination: Option<LoggedDestination>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    service_level: Option<ServiceLevel>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoggedDestination {
+    country: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    postcode: Option<String>
+}
+
+impl DecisionLog {
+    /**
+     * Opens a decision log for appending, creating it when it doesn't exist
+     */
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DecisionLogError> {
+        let path = path.as_ref();
+        let unwritable = |e: std::io::Error| DecisionLogError::Unwritable(format!("{}: {}", path.display(), e));
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path).map_err(unwritable)?;
+
+        let mut contents = vec![];
+        file.read_to_end(&mut contents).map_err(unwritable)?;
+        if !contents.is_empty() && !has_header(&contents) {
+            return Err(DecisionLogError::NotALog(path.display().to_string()));
+        }
+
+        // discards the partly written entry, which was never acknowledged
+        let complete = complete_lines(&contents).len();
+        if complete < contents.len() {
+            file.set_len(complete as u64).map_err(unwritable)?;
+        }
+        if complete == 0 {
+            writeln!(file, "{}", HEADER).map_err(unwritable)?;
+            file.sync_data().map_err(unwritable)?;
+        }
+
+        Ok(DecisionLog { file })
+    }
+
+    /**
+     * Appends an entry, returning once it is synced to disk
+     */
+    pub fn append(&mut self, entry: &LogEntry) -> Result<(), DecisionLogError> {
+        let unwritable = |e: std::io::Error| DecisionLogError::Unwritable(e.to_string());
+        let json = serde_json::to_string(&LoggedEntry::from(entry)).map_err(|e| DecisionLogError::Unwritable(e.to_string()))?;
+
+        writeln!(self.file, "{:08x} {}", crc32(json.as_bytes()), json).map_err(unwritable)?;
+        self.file.sync_data().map_err(unwritable)
+    }
+
+    /**
+     * Reads every entry of a decision log, verifying their checksums
+     */
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<LogEntry>, DecisionLogError> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .map_err(|e| DecisionLogError::Unreadable(format!("{}: {}", path.display(), e)))?;
+        if !has_header(&contents) {
+            return Err(DecisionLogError::NotALog(path.display().to_string()));
+        }
+
+        complete_lines(&contents).split_inclusive(|byte| *byte == b'\n')
+            .enumerate()
+            .skip(1)
+            .map(|(index, line)| parse_entry(&line[..line.len() - 1], index + 1))
+            .collect()
+    }
+}
+
+// whether the first line of a file is the header, whether or not it is complete
+fn has_header(contents: &[u8]) -> bool {
+    contents.split(|byte| *byte == b'\n').next().map(<[u8]>::trim_ascii_end) == Some(HEADER.as_bytes())
+}
+
+// the contents up to the last line break, leaving out a partly written entry
+fn complete_lines(contents: &[u8]) -> &[u8] {
+    let complete = contents.iter().rposition(|byte| *byte == b'\n').map(|index| index + 1).unwrap_or(0);
+    &contents[..complete]
+}
+
+fn parse_entry(line: &[u8], number: usize) -> Result<LogEntry, DecisionLogError> {
+    let (checksum, json) = match line.iter().position(|byte| *byte == b' ') {
+        Some(index) => (&line[..index], &line[index + 1..]),
+        None => return Err(DecisionLogError::ChecksumMismatch(number))
+    };
+    let checksum = std::str::from_utf8(checksum).ok().and_then(|checksum| u32::from_str_radix(checksum, 16).ok());
+    if checksum != Some(crc32(json)) {
+        return Err(DecisionLogError::ChecksumMismatch(number));
+    }
+
+    let unparsable = |reason: String| DecisionLogError::Unparsable(number, reason);
+    let logged = serde_json::from_slice::<LoggedEntry>(json).map_err(|e| unparsable(e.to_string()))?;
+    LogEntry::try_from(logged).map_err(|e| unparsable(e.to_string()))
+}
+
+// CRC-32 as used by zip and PNG files
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xedb8_8320,
+            _ => crc >> 1
+        })
+    })
+}
+
+impl LogEntry {
+    pub fn new<T: Into<String>>(package: T, at: DateTime<Utc>, input: PackageInput, decision: &SortDecision) -> Self {
+        LogEntry {
+            package: package.into(),
+            at,
+            input,
+            policy_version: decision.policy_version().to_string(),
+            outcome: decision.outcome().to_string(),
+            stack: decision.outcome().stack().cloned()
+        }
+    }
+
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    pub fn at(&self) -> &DateTime<Utc> {
+        &self.at
+    }
+
+    pub fn input(&self) -> &PackageInput {
+        &self.input
+    }
+
+    pub fn policy_version(&self) -> &str {
+        &self.policy_version
+    }
+
+    pub fn outcome(&self) -> &str {
+        &self.outcome
+    }
+
+    pub fn stack(&self) -> Option<&SortResult> {
+        self.stack.as_ref()
+    }
+}
+
+impl StackChange {
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    pub fn before(&self) -> Option<&SortResult> {
+        self.before.as_ref()
+    }
+
+    pub fn after(&self) -> Option<&SortResult> {
+        self.after.as_ref()
+    }
+}
+
+impl ReplayReport {
+    pub fn replayed(&self) -> usize {
+        self.replayed
+    }
+
+    pub fn changes(&self) -> &[StackChange] {
+        &self.changes
+    }
+}
+
+/**
+ * Sorts every logged input again under the given policy, reporting
+ * the packages that would be sent to another stack
+ */
+pub(crate) fn replay(entries: &[LogEntry], policy: &SortPolicy) -> Result<ReplayReport, DecisionLogError> {
+    let mut changes = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        let decision = crate::sort_package(&entry.input, policy)
+            .map_err(|error| DecisionLogError::InvalidEntry(index + 1, error))?;
+        let after = decision.outcome().stack();
+        if after != entry.stack() {
+            changes.push(StackChange {
+                package: entry.package.clone(),
+                before: entry.stack.clone(),
+                after: after.cloned()
+            });
+        }
+    }
+
+    Ok(ReplayReport { replayed: entries.len(), changes })
+}
+
+impl From<&LogEntry> for LoggedEntry {
+    fn from(entry: &LogEntry) -> Self {
+        let input = &entry.input;
+        LoggedEntry {
+            package: entry.package.clone(),
+            at: entry.at,
+            input: LoggedInput {
+                width: input.width(),
+                height: input.height(),
+                length: input.length(),
+                mass: input.mass(),
+                attributes: input.attributes().iter().cloned().collect(),
+                destination: input.destination().map(|destination| LoggedDestination {
+                    country: destination.country().to_string(),
+                    postcode: destination.postcode().map(str::to_string)
+                }),
+                service_level: input.service_level().cloned()
+            },
+            policy_version: entry.policy_version.clone(),
+            outcome: entry.outcome.clone(),
+            stack: entry.stack.clone()
+        }
+    }
+}
+
+impl TryFrom<LoggedEntry> for LogEntry {
+    type Error = crate::package::destination::DestinationError;
+
+    fn try_from(logged: LoggedEntry) -> Result<Self, Self::Error> {
+        let fields = logged.input;
+        let mut input = fields.attributes.into_iter().fold(
+            PackageInput::new(fields.width, fields.height, fields.length, fields.mass),
+            PackageInput::with_attribute
+        );
+        if let Some(destination) = fields.destination {
+            let mut parsed = Destination::new(destination.country)?;
+            if let Some(postcode) = destination.postcode {
+                parsed = parsed.with_postcode(postcode)?;
+            }
+            input = input.with_destination(parsed);
+        }
+        if let Some(level) = fields.service_level {
+            input = input.with_service_level(level);
+        }
+
+        Ok(LogEntry {
+            package: logged.package,
+            at: logged.at,
+            input,
+            policy_version: logged.policy_version,
+            outcome: logged.outcome,
+            stack: logged.stack
+        })
+    }
+}
+
+impl std::fmt::Display for StackChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stack = |stack: &Option<SortResult>| stack.as_ref().map(SortResult::to_string).unwrap_or("remeasure".to_string());
+        write!(f, "{}: {} -> {}", self.package, stack(&self.before), stack(&self.after))
+    }
+}
+
+impl std::fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let noun = if self.replayed == 1 { "package" } else { "packages" };
+        write!(f, "{} of {} {} would change stack", self.changes.len(), self.replayed, noun)?;
+        for change in &self.changes {
+            write!(f, "\n{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DecisionLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecisionLogError::Unreadable(reason) => write!(f, "Unreadable decision log: {}", reason),
+            DecisionLogError::Unwritable(reason) => write!(f, "Unwritable decision log: {}", reason),
+            DecisionLogError::NotALog(path) => write!(f, "Not a decision log: expecting a file starting with '{}', but got {}", HEADER, path),
+            DecisionLogError::ChecksumMismatch(line) => write!(f, "Corrupt decision log: the entry at line {} doesn't match its checksum", line),
+            DecisionLogError::Unparsable(line, reason) => write!(f, "Corrupt decision log: the entry at line {} can't be parsed: {}", line, reason),
+            DecisionLogError::InvalidEntry(entry, error) => write!(f, "Invalid entry {}: {}", entry, error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurements::cm::Cm;
+    use crate::measurements::kg::Kg;
+    use crate::test_dependencies::scratch::ScratchFile;
+
+    fn log_file(name: &str) -> ScratchFile {
+        ScratchFile::new(name)
+    }
+
+    fn at() -> DateTime<Utc> {
+        "2026-10-19T08:00:00Z".parse().unwrap()
+    }
+
+    fn entry(package: &str, input: PackageInput) -> LogEntry {
+        let decision = crate::sort_package(&input, &SortPolicy::default()).unwrap();
+        LogEntry::new(package, at(), input, &decision)
+    }
+
+    fn entries() -> Vec<LogEntry> {
+        vec![
+            entry("PKG-1", PackageInput::new(10, 10, 10, 19)),
+            entry("PKG-2", PackageInput::new(10, 10, 10, 1)
+                .with_attribute(Attribute::Fragile)
+                .with_destination(Destination::new("GB").unwrap().with_postcode("EC1A 1BB").unwrap())
+                .with_service_level(ServiceLevel::Express)),
+            entry("PKG-3", PackageInput::new(100, 40, 30, 25))
+        ]
+    }
+
+    #[test]
+    fn the_checksum_is_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn appended_entries_are_read_back() {
+        let path = log_file("appended.log");
+        let mut log = DecisionLog::open(&path).unwrap();
+        for entry in &entries()[..2] {
+            log.append(entry).unwrap();
+        }
+        drop(log);
+        DecisionLog::open(&path).unwrap().append(&entries()[2]).unwrap();
+
+        assert_eq!(DecisionLog::read(&path), Ok(entries()));
+        assert_eq!(entries()[2].stack(), Some(&SortResult::Rejected));
+        assert_eq!(entries()[2].outcome(), "rejected");
+    }
+
+    #[test]
+    fn tampered_entries_are_detected() {
+        let path = log_file("tampered.log");
+        let mut log = DecisionLog::open(&path).unwrap();
+        for entry in &entries() {
+            log.append(entry).unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap().replacen("\"mass\":1,", "\"mass\":2,", 1);
+        std::fs::write(&path, contents).unwrap();
+        assert_eq!(DecisionLog::read(&path), Err(DecisionLogError::ChecksumMismatch(3)));
+    }
+
+    #[test]
+    fn partly_written_entries_are_discarded() {
+        let path = log_file("torn.log");
+        DecisionLog::open(&path).unwrap().append(&entries()[0]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "0badc0de {{\"package\":").unwrap();
+
+        assert_eq!(DecisionLog::read(&path), Ok(entries()[..1].to_vec()));
+        DecisionLog::open(&path).unwrap().append(&entries()[1]).unwrap();
+        assert_eq!(DecisionLog::read(&path), Ok(entries()[..2].to_vec()));
+    }
+
+    #[test]
+    fn entries_torn_within_a_character_are_discarded() {
+        let path = log_file("torn-utf8.log");
+        DecisionLog::open(&path).unwrap().append(&entries()[0]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&"0badc0de {\"package\":\"PKG-€".as_bytes()[..26]).unwrap();
+
+        assert_eq!(DecisionLog::read(&path), Ok(entries()[..1].to_vec()));
+        DecisionLog::open(&path).unwrap().append(&entries()[1]).unwrap();
+        assert_eq!(DecisionLog::read(&path), Ok(entries()[..2].to_vec()));
+    }
+
+    #[test]
+    fn a_torn_header_is_written_again() {
+        let path = log_file("torn-header.log");
+        std::fs::write(&path, HEADER).unwrap();
+        DecisionLog::open(&path).unwrap().append(&entries()[0]).unwrap();
+        assert_eq!(DecisionLog::read(&path), Ok(entries()[..1].to_vec()));
+    }
+
+    #[test]
+    fn entries_matching_their_checksum_must_parse() {
+        let path = log_file("unparsable.log");
+        let json = "{\"package\":\"PKG-1\"}";
+        std::fs::write(&path, format!("{}\n{:08x} {}\n", HEADER, crc32(json.as_bytes()), json)).unwrap();
+        assert!(matches!(DecisionLog::read(&path), Err(DecisionLogError::Unparsable(2, _))));
+    }
+
+    #[test]
+    fn other_files_are_not_logs() {
+        let path = log_file("policy.toml");
+        std::fs::write(&path, "version = \"default\"\n").unwrap();
+        let expected = DecisionLogError::NotALog(path.display().to_string());
+        assert_eq!(DecisionLog::read(&path), Err(expected.clone()));
+        assert_eq!(DecisionLog::open(&path).err(), Some(expected));
+
+        let path = log_file("longer-header.log");
+        std::fs::write(&path, format!("{}0\n", HEADER)).unwrap();
+        let expected = DecisionLogError::NotALog(path.display().to_string());
+        assert_eq!(DecisionLog::read(&path), Err(expected.clone()));
+        assert_eq!(DecisionLog::open(&path).err(), Some(expected));
+    }
+
+    #[test]
+    fn replays_report_the_packages_changing_stack() {
+        let policy = SortPolicy::default().with_thresholds(Cm::new(150).unwrap(), Kg::new(18).unwrap());
+        let report = replay(&entries(), &policy).unwrap();
+
+        assert_eq!(report.replayed(), 3);
+        assert_eq!(report.changes(), &[StackChange {
+            package: "PKG-1".to_string(),
+            before: Some(SortResult::Standard),
+            after: Some(SortResult::Special)
+        }]);
+        assert_eq!(replay(&entries(), &SortPolicy::default()).unwrap().changes(), &[]);
+    }
+
+    #[test]
+    fn invalid_entries_can_not_be_replayed() {
+        let invalid = LogEntry { input: PackageInput::new(10, 10, 10, 0), ..entries()[0].clone() };
+        assert_eq!(
+            replay(&[entries()[1].clone(), invalid], &SortPolicy::default()),
+            Err(DecisionLogError::InvalidEntry(2, SortError::InvalidMass(0)))
+        );
+    }
+
+    #[test]
+    fn display_replay_report() {
+        let report = ReplayReport {
+            replayed: 3,
+            changes: vec![
+                StackChange { package: "PKG-1".to_string(), before: Some(SortResult::Standard), after: Some(SortResult::Special) },
+                StackChange { package: "PKG-3".to_string(), before: Some(SortResult::Rejected), after: None }
+            ]
+        };
+        assert_eq!(report.to_string(), "2 of 3 packages would change stack\nPKG-1: standard -> special\nPKG-3: rejected -> remeasure");
+    }
+
+    #[test]
+    fn display_corrupt_log() {
+        assert_eq!(DecisionLogError::ChecksumMismatch(3).to_string(), "Corrupt decision log: the entry at line 3 doesn't match its checksum");
+        assert_eq!(
+            DecisionLogError::Unparsable(3, "missing field `at`".to_string()).to_string(),
+            "Corrupt decision log: the entry at line 3 can't be parsed: missing field `at`"
+        );
+    }
+}
@@ -0,0 +1,516 @@
+use std::path::Path;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::service_level::ServiceLevel;
+use crate::package_input::PackageInput;
+use crate::sort_decision::SortDecision;
+use crate::sort_error::SortError;
+use crate::sort_policy::schedule::format_timestamp;
+use crate::sort_result::SortResult;
+
+// every version of the schema, migrating from the previous one; the
+// version of a database is the number of migrations applied to it
+const MIGRATIONS: [&str; 2] = [
+    "CREATE TABLE measurements (
+        id INTEGER PRIMARY KEY,
+        package TEXT NOT NULL,
+        measured_at TEXT NOT NULL,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        length INTEGER NOT NULL,
+        mass INTEGER NOT NULL,
+        attributes TEXT NOT NULL,
+        country TEXT,
+        postcode TEXT,
+        service_level TEXT
+    );
+    CREATE TABLE decisions (
+        measurement INTEGER PRIMARY KEY REFERENCES measurements (id),
+        policy_version TEXT,
+        outcome TEXT NOT NULL,
+        stack TEXT,
+        routing_key TEXT,
+        error TEXT
+    );",
+    "CREATE INDEX measurements_by_package ON measurements (package);
+    CREATE INDEX measurements_by_day ON measurements (substr(measured_at, 1, 10));"
+];
+
+/**
+ * DecisionStore
+ *
+ * A local SQLite database recording the measurements of every package
+ * along with the decision it got, or the error it was refused with.
+ * The schema is migrated to its latest version when the database is
+ * opened.
+ */
+pub struct DecisionStore {
+    connection: Connection
+}
+
+/**
+ * StoredSort
+ *
+ * A recorded measurement and its outcome: the decision as displayed and
+ * the stack it was sent to, or the message and kind of the error.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredSort {
+    package: String,
+    at: DateTime<Utc>,
+    input: PackageInput,
+    policy_version: Option<String>,
+    outcome: String,
+    stack: Option<SortResult>,
+    routing_key: Option<String>,
+    error: Option<String>
+}
+
+/**
+ * DailyVolume
+ *
+ * The number of packages sent onto a stack on a day.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyVolume {
+    day: NaiveDate,
+    stack: SortResult,
+    count: usize
+}
+
+/**
+ * ErrorRate
+ *
+ * The number of sorts that failed with a kind of error, and their share
+ * of every recorded sort.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorRate {
+    error: String,
+    count: usize,
+    rate: f64
+}
+
+/**
+ * StoreError
+ *
+ * Documents why the decision store could not be used:
+ * - the database could not be opened or migrated
+ * - the database has a schema newer than this version supports
+ * - a statement failed, or a stored row could not be read back
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoreError {
+    Unavailable(String),
+    UnsupportedSchema(usize),
+    Failed(String)
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        StoreError::Failed(error.to_string())
+    }
+}
+
+impl DecisionStore {
+    /**
+     * Opens the database at a path, creating it when it doesn't exist
+     */
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .map_err(|e| StoreError::Unavailable(format!("{}: {}", path.display(), e)))?;
+
+        DecisionStore::migrated(connection)
+    }
+
+    /**
+     * Opens a database living in memory only, as used in tests
+     */
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        let connection = Connection::open_in_memory().map_err(|e| StoreError::Unavailable(e.to_string()))?;
+
+        DecisionStore::migrated(connection)
+    }
+
+    fn migrated(mut connection: Connection) -> Result<Self, StoreError> {
+        let version = schema_version(&connection)?;
+        if version > MIGRATIONS.len() {
+            return Err(StoreError::UnsupportedSchema(version));
+        }
+
+        // every migration is applied along with its version, or not at all
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction().map_err(|e| StoreError::Unavailable(e.to_string()))?;
+            transaction.execute_batch(migration)
+                .and_then(|_| transaction.pragma_update(None, "user_version", index + 1))
+                .and_then(|_| transaction.commit())
+                .map_err(|e| StoreError::Unavailable(format!("migrating to version {}: {}", index + 1, e)))?;
+        }
+
+        Ok(DecisionStore { connection })
+    }
+
+    pub fn schema_version(&self) -> Result<usize, StoreError> {
+        schema_version(&self.connection)
+    }
+
+    /**
+     * Records the measurements of a package along with how it was sorted
+     */
+    pub fn record(&mut self, package: &str, at: &DateTime<Utc>, input: &PackageInput, sorted: &Result<SortDecision, SortError>) -> Result<(), StoreError> {
+        let attributes: Vec<String> = input.attributes().iter().map(Attribute::to_string).collect();
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO measurements (package, measured_at, width, height, length, mass, attributes, country, postcode, service_level)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                package,
+                format_timestamp(at),
+                input.width(),
+                input.height(),
+                input.length(),
+                input.mass(),
+                attributes.join(","),
+                input.destination().map(Destination::country),
+                input.destination().and_then(Destination::postcode),
+                input.service_level().map(ServiceLevel::to_string)
+            ]
+        )?;
+        let measurement = transaction.last_insert_rowid();
+        match sorted {
+            Ok(decision) => transaction.execute(
+                "INSERT INTO decisions (measurement, policy_version, outcome, stack, routing_key) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    measurement,
+                    decision.policy_version(),
+                    decision.outcome().to_string(),
+                    decision.outcome().stack().map(SortResult::to_string),
+                    decision.routing_key()
+                ]
+            )?,
+            Err(error) => transaction.execute(
+                "INSERT INTO decisions (measurement, outcome, error) VALUES (?1, ?2, ?3)",
+                params![measurement, error.to_string(), error.kind()]
+            )?
+        };
+
+        Ok(transaction.commit()?)
+    }
+
+    /**
+     * Every recorded sort of a package, from the earliest to the latest
+     */
+    pub fn lookup(&self, package: &str) -> Result<Vec<StoredSort>, StoreError> {
+        let mut statement = self.connection.prepare(
+            "SELECT package, measured_at, width, height, length, mass, attributes, country, postcode, service_level,
+                policy_version, outcome, stack, routing_key, error
+            FROM measurements JOIN decisions ON decisions.measurement = measurements.id
+            WHERE package = ?1
+            ORDER BY measured_at, id"
+        )?;
+        let rows = statement.query_map([package], |row| Ok(stored_sort(row)))?;
+
+        rows.map(|row| row?).collect()
+    }
+
+    /**
+     * The number of packages sent onto every stack, per day. Packages
+     * that must be remeasured or could not be sorted are left out.
+     */
+    pub fn daily_volumes(&self) -> Result<Vec<DailyVolume>, StoreError> {
+        let mut statement = self.connection.prepare(
+            "SELECT substr(measured_at, 1, 10) AS day, stack, COUNT(*)
+            FROM measurements JOIN decisions ON decisions.measurement = measurements.id
+            WHERE stack IS NOT NULL
+            GROUP BY day, stack
+            ORDER BY day, stack"
+        )?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, usize>(2)?)))?;
+
+        rows.map(|row| {
+            let (day, stack, count) = row?;
+            Ok(DailyVolume {
+                day: day.parse().map_err(|e| unreadable("day", &day, e))?,
+                stack: stack.parse().map_err(|e| unreadable("stack", &stack, e))?,
+                count
+            })
+        }).collect()
+    }
+
+    /**
+     * The number and share of sorts that failed, per kind of error,
+     * from the most to the least frequent
+     */
+    pub fn error_rates(&self) -> Result<Vec<ErrorRate>, StoreError> {
+        let total: usize = self.connection.query_row("SELECT COUNT(*) FROM decisions", [], |row| row.get(0))?;
+        let mut statement = self.connection.prepare(
+            "SELECT error, COUNT(*) AS count FROM decisions WHERE error IS NOT NULL GROUP BY error ORDER BY count DESC, error"
+        )?;
+        let rows = statement.query_map([], |row| {
+            let count: usize = row.get(1)?;
+            Ok(ErrorRate { error: row.get(0)?, count, rate: count as f64 / total as f64 })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn schema_version(connection: &Connection) -> Result<usize, StoreError> {
+    connection.pragma_query_value(None, "user_version", |row| row.get(0))
+        .optional()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| StoreError::Unavailable(e.to_string()))
+}
+
+fn unreadable<E: std::fmt::Display>(column: &str, value: &str, error: E) -> StoreError {
+    StoreError::Failed(format!("unreadable {} '{}': {}", column, value, error))
+}
+
+fn stored_sort(row: &Row) -> Result<StoredSort, StoreError> {
+    let mut input = PackageInput::new(row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?);
+    let attributes: String = row.get(6)?;
+    for attribute in attributes.split(',').filter(|attribute| !attribute.is_empty()) {
+        input = input.with_attribute(attribute.parse().map_err(|e| unreadable("attribute", attribute, e))?);
+    }
+    if let Some(country) = row.get::<_, Option<String>>(7)? {
+        let mut destination = Destination::new(country.as_str()).map_err(|e| unreadable("country", &country, e))?;
+        if let Some(postcode) = row.get::<_, Option<String>>(8)? {
+            destination = destination.with_postcode(postcode.as_str()).map_err(|e| unreadable("postcode", &postcode, e))?;
+        }
+        input = input.with_destination(destination);
+    }
+    if let Some(level) = row.get::<_, Option<String>>(9)? {
+        input = input.with_service_level(level.parse().map_err(|e| unreadable("service level", &level, e))?);
+    }
+
+    let at: String = row.get(1)?;
+    let stack: Option<String> = row.get(12)?;
+    Ok(StoredSort {
+        package: row.get(0)?,
+        at: DateTime::parse_from_rfc3339(&at).map_err(|e| unreadable("timestamp", &at, e))?.with_timezone(&Utc),
+        input,
+        policy_version: row.get(10)?,
+        outcome: row.get(11)?,
+        stack: stack.map(|stack| stack.parse().map_err(|e| unreadable("stack", &stack, e))).transpose()?,
+        routing_key: row.get(13)?,
+        error: row.get(14)?
+    })
+}
+
+impl StoredSort {
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    pub fn at(&self) -> &DateTime<Utc> {
+        &self.at
+    }
+
+    pub fn input(&self) -> &PackageInput {
+        &self.input
+    }
+
+    /**
+     * The version of the policy the package was sorted with, unless the sort failed
+     */
+    pub fn policy_version(&self) -> Option<&str> {
+        self.policy_version.as_deref()
+    }
+
+    /**
+     * The decision as displayed, or the message of the error the sort failed with
+     */
+    pub fn outcome(&self) -> &str {
+        &self.outcome
+    }
+
+    pub fn stack(&self) -> Option<&SortResult> {
+        self.stack.as_ref()
+    }
+
+    pub fn routing_key(&self) -> Option<&str> {
+        self.routing_key.as_deref()
+    }
+
+    /**
+     * The kind of error the sort failed with, if it failed
+     */
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+impl DailyVolume {
+    pub fn day(&self) -> &NaiveDate {
+        &self.day
+    }
+
+    pub fn stack(&self) -> &SortResult {
+        &self.stack
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl ErrorRate {
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StoreError::Unavailable(reason) => write!(f, "Unavailable decision store: {}", reason),
+            StoreError::UnsupportedSchema(version) => write!(f, "Unsupported schema: expecting version {} or lower, but got {}", MIGRATIONS.len(), version),
+            StoreError::Failed(reason) => write!(f, "Failed store query: {}", reason)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort_policy::SortPolicy;
+    use crate::test_dependencies::scratch::ScratchFile;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    fn record(store: &mut DecisionStore, package: &str, timestamp: &str, input: PackageInput) {
+        let sorted = crate::sort_package(&input, &SortPolicy::default());
+        store.record(package, &at(timestamp), &input, &sorted).unwrap();
+    }
+
+    fn day(day: &str) -> NaiveDate {
+        day.parse().unwrap()
+    }
+
+    #[test]
+    fn new_stores_are_migrated_to_the_latest_version() {
+        assert_eq!(DecisionStore::open_in_memory().unwrap().schema_version(), Ok(2));
+    }
+
+    #[test]
+    fn older_stores_are_migrated_keeping_their_records() {
+        let path = ScratchFile::new("migrated.sqlite");
+        let connection = Connection::open(&path).unwrap();
+        connection.execute_batch(MIGRATIONS[0]).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        connection.execute(
+            "INSERT INTO measurements (package, measured_at, width, height, length, mass, attributes) VALUES ('PKG-1', '2026-10-19T08:00:00Z', 10, 10, 10, 1, '')",
+            []
+        ).unwrap();
+        connection.execute("INSERT INTO decisions (measurement, policy_version, outcome, stack) VALUES (1, 'default', 'standard', 'standard')", []).unwrap();
+        drop(connection);
+
+        let store = DecisionStore::open(&path).unwrap();
+        assert_eq!(store.schema_version(), Ok(2));
+        assert_eq!(store.lookup("PKG-1").unwrap().len(), 1);
+        drop(store);
+        assert_eq!(DecisionStore::open(&path).unwrap().schema_version(), Ok(2));
+    }
+
+    #[test]
+    fn newer_stores_are_refused() {
+        let path = ScratchFile::new("newer.sqlite");
+        Connection::open(&path).unwrap().pragma_update(None, "user_version", 3).unwrap();
+
+        assert_eq!(DecisionStore::open(&path).err(), Some(StoreError::UnsupportedSchema(3)));
+    }
+
+    #[test]
+    fn recorded_sorts_are_looked_up_by_package() {
+        let mut store = DecisionStore::open_in_memory().unwrap();
+        let input = PackageInput::new(10, 10, 10, 4)
+            .with_attribute(Attribute::Fragile)
+            .with_attribute(Attribute::Liquid)
+            .with_destination(Destination::new("DE").unwrap().with_postcode("10115").unwrap())
+            .with_service_level(ServiceLevel::Express);
+        record(&mut store, "PKG-1", "2026-10-19T08:00:00Z", input.clone());
+        record(&mut store, "PKG-2", "2026-10-19T08:01:00Z", PackageInput::new(10, 10, 10, 1));
+        record(&mut store, "PKG-1", "2026-10-19T09:00:00Z", PackageInput::new(10, 0, 10, 4));
+
+        assert_eq!(store.lookup("PKG-1").unwrap(), vec![
+            StoredSort {
+                package: "PKG-1".to_string(),
+                at: at("2026-10-19T08:00:00Z"),
+                input,
+                policy_version: Some("default".to_string()),
+                outcome: "standard".to_string(),
+                stack: Some(SortResult::Standard),
+                routing_key: None,
+                error: None
+            },
+            StoredSort {
+                package: "PKG-1".to_string(),
+                at: at("2026-10-19T09:00:00Z"),
+                input: PackageInput::new(10, 0, 10, 4),
+                policy_version: None,
+                outcome: "Invalid height: expecting a value of 1 or more, but got 0".to_string(),
+                stack: None,
+                routing_key: None,
+                error: Some("invalid_height".to_string())
+            }
+        ]);
+        assert_eq!(store.lookup("PKG-3"), Ok(vec![]));
+    }
+
+    #[test]
+    fn daily_volumes_are_counted_per_stack() {
+        let mut store = DecisionStore::open_in_memory().unwrap();
+        record(&mut store, "PKG-1", "2026-10-18T23:59:59Z", PackageInput::new(10, 10, 10, 1));
+        record(&mut store, "PKG-2", "2026-10-19T08:00:00Z", PackageInput::new(10, 10, 10, 1));
+        record(&mut store, "PKG-3", "2026-10-19T08:01:00Z", PackageInput::new(10, 10, 10, 25));
+        record(&mut store, "PKG-4", "2026-10-19T08:02:00Z", PackageInput::new(10, 10, 10, 2));
+        record(&mut store, "PKG-5", "2026-10-19T08:03:00Z", PackageInput::new(10, 10, 0, 2));
+
+        let volumes: Vec<(NaiveDate, SortResult, usize)> = store.daily_volumes().unwrap().into_iter()
+            .map(|volume| (*volume.day(), volume.stack().clone(), volume.count()))
+            .collect();
+        assert_eq!(volumes, vec![
+            (day("2026-10-18"), SortResult::Standard, 1),
+            (day("2026-10-19"), SortResult::Special, 1),
+            (day("2026-10-19"), SortResult::Standard, 2)
+        ]);
+    }
+
+    #[test]
+    fn error_rates_are_computed_per_kind_of_error() {
+        let mut store = DecisionStore::open_in_memory().unwrap();
+        record(&mut store, "PKG-1", "2026-10-19T08:00:00Z", PackageInput::new(10, 10, 10, 1));
+        record(&mut store, "PKG-2", "2026-10-19T08:01:00Z", PackageInput::new(10, 0, 10, 1));
+        record(&mut store, "PKG-3", "2026-10-19T08:02:00Z", PackageInput::new(10, 10, 10, 0));
+        record(&mut store, "PKG-4", "2026-10-19T08:03:00Z", PackageInput::new(10, 10, 10, 0));
+
+        let rates: Vec<(String, usize, f64)> = store.error_rates().unwrap().into_iter()
+            .map(|rate| (rate.error().to_string(), rate.count(), rate.rate()))
+            .collect();
+        assert_eq!(rates, vec![("invalid_mass".to_string(), 2, 0.5), ("invalid_height".to_string(), 1, 0.25)]);
+    }
+
+    #[test]
+    fn empty_stores_have_no_error_rates() {
+        assert_eq!(DecisionStore::open_in_memory().unwrap().error_rates(), Ok(vec![]));
+    }
+
+    #[test]
+    fn display_store_errors() {
+        assert_eq!(StoreError::UnsupportedSchema(3).to_string(), "Unsupported schema: expecting version 2 or lower, but got 3");
+        assert_eq!(StoreError::Failed("disk I/O error".to_string()).to_string(), "Failed store query: disk I/O error");
+    }
+}
@@ -0,0 +1,277 @@
+use crate::dataset::Dataset;
+use crate::sort_error::SortError;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::SortResult;
+
+// the number of packages kept as examples of every transition
+const MAX_EXAMPLES: usize = 3;
+
+/**
+ * Transition
+ *
+ * The packages sent from one stack under the current policy to a
+ * stack under the proposed policy, along with a few examples of them.
+ * Packages that must be remeasured have no stack.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    before: Option<SortResult>,
+    after: Option<SortResult>,
+    count: usize,
+    examples: Vec<String>
+}
+
+/**
+ * ImpactAnalysis
+ *
+ * The effect of replacing the current policy by a proposed one on a
+ * dataset of packages: how many packages go from every stack to every
+ * other stack. Uncertain packages are compared on their conservative
+ * stack, as they are routed.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImpactAnalysis {
+    total: usize,
+    transitions: Vec<Transition>
+}
+
+impl Transition {
+    pub fn before(&self) -> Option<&SortResult> {
+        self.before.as_ref()
+    }
+
+    pub fn after(&self) -> Option<&SortResult> {
+        self.after.as_ref()
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn examples(&self) -> &[String] {
+        &self.examples
+    }
+
+    pub fn is_change(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+impl ImpactAnalysis {
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /**
+     * Every transition that occurred, ordered by the stack before and
+     * the stack after
+     */
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /**
+     * The number of packages going from one stack to another
+     */
+    pub fn count(&self, before: Option<&SortResult>, after: Option<&SortResult>) -> usize {
+        self.transitions.iter()
+            .find(|transition| transition.before() == before && transition.after() == after)
+            .map_or(0, Transition::count)
+    }
+
+    pub fn changed(&self) -> usize {
+        self.transitions.iter()
+            .filter(|transition| transition.is_change())
+            .map(Transition::count)
+            .sum()
+    }
+
+    /**
+     * The share of the dataset a number of packages represents, in percent
+     */
+    pub fn percentage(&self, count: usize) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => count as f64 * 100.0 / total as f64
+        }
+    }
+
+    // every stack occurring before or after, in the order of the transitions
+    fn stacks(&self) -> Vec<Option<SortResult>> {
+        let mut stacks: Vec<Option<SortResult>> = vec![];
+        for stack in self.transitions.iter().flat_map(|transition| [&transition.before, &transition.after]) {
+            if !stacks.contains(stack) {
+                stacks.push(stack.clone());
+            }
+        }
+        stacks.sort_by_key(order);
+        stacks
+    }
+}
+
+/**
+ * Sorts every package of the dataset under both policies, using the
+ * same classification as production, and counts the transitions
+ */
+pub(crate) fn analyse(dataset: &Dataset, current: &SortPolicy, proposed: &SortPolicy) -> Result<ImpactAnalysis, SortError> {
+    let mut transitions: Vec<Transition> = vec![];
+    for (id, input) in dataset.packages() {
+        let sort = |policy| crate::sort_package(input, policy)
+            .map(|decision| decision.outcome().stack().cloned())
+            .map_err(|error| SortError::InvalidPackage(id.clone(), Box::new(error)));
+        let (before, after) = (sort(current)?, sort(proposed)?);
+
+        match transitions.iter_mut().find(|transition| transition.before == before && transition.after == after) {
+            Some(transition) => {
+                transition.count += 1;
+                if transition.examples.len() < MAX_EXAMPLES {
+                    transition.examples.push(id.clone());
+                }
+            },
+            None => transitions.push(Transition { before, after, count: 1, examples: vec![id.clone()] })
+        }
+    }
+    transitions.sort_by_key(|transition| (order(&transition.before), order(&transition.after)));
+
+    Ok(ImpactAnalysis { total: dataset.len(), transitions })
+}
+
+// the built-in stacks come first, then the custom ones by name, then remeasurement
+fn order(stack: &Option<SortResult>) -> (u8, String) {
+    match stack {
+        Some(SortResult::Standard) => (0, String::new()),
+        Some(SortResult::Special) => (1, String::new()),
+        Some(SortResult::Rejected) => (2, String::new()),
+        Some(SortResult::Custom(id)) => (3, id.name().to_string()),
+        None => (4, String::new())
+    }
+}
+
+fn label(stack: &Option<SortResult>) -> String {
+    stack.as_ref().map_or("remeasure".to_string(), SortResult::to_string)
+}
+
+impl std::fmt::Display for ImpactAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let noun = if self.total == 1 { "package" } else { "packages" };
+        writeln!(f, "{} {}, {} changed stack ({:.1}%)", self.total, noun, self.changed(), self.percentage(self.changed()))?;
+
+        let stacks = self.stacks();
+        let labels: Vec<String> = stacks.iter().map(label).collect();
+        let width = labels.iter().map(String::len).chain(["before \\ after".len()]).max().unwrap_or(0);
+        write!(f, "\n{:<width$}", "before \\ after")?;
+        for label in &labels {
+            write!(f, "  {:>w$}", label, w = label.len().max(5))?;
+        }
+        for (before, row) in stacks.iter().zip(&labels) {
+            write!(f, "\n{:<width$}", row)?;
+            for (after, label) in stacks.iter().zip(&labels) {
+                write!(f, "  {:>w$}", self.count(before.as_ref(), after.as_ref()), w = label.len().max(5))?;
+            }
+        }
+        writeln!(f)?;
+
+        for transition in &self.transitions {
+            write!(
+                f,
+                "\n{} -> {}: {} ({:.1}%), e.g. {}",
+                label(&transition.before),
+                label(&transition.after),
+                transition.count,
+                self.percentage(transition.count),
+                transition.examples.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::DatasetFormat;
+    use crate::measurements::cm::Cm;
+    use crate::measurements::kg::Kg;
+
+    fn dataset() -> Dataset {
+        Dataset::from_source("\
+id,width,height,length,mass
+PKG-1,10,10,10,19
+PKG-2,10,10,10,18
+PKG-3,10,10,10,1
+PKG-4,100,40,30,25
+PKG-5,10,10,10,19
+PKG-6,10,10,10,19
+PKG-7,10,10,10,19
+", DatasetFormat::Csv).unwrap()
+    }
+
+    fn lowered_heavy_threshold() -> SortPolicy {
+        SortPolicy::default().with_thresholds(Cm::new(150).unwrap(), Kg::new(18).unwrap())
+    }
+
+    #[test]
+    fn transitions_are_counted() {
+        let analysis = analyse(&dataset(), &SortPolicy::default(), &lowered_heavy_threshold()).unwrap();
+
+        assert_eq!(analysis.total(), 7);
+        assert_eq!(analysis.changed(), 5);
+        assert_eq!(analysis.count(Some(&SortResult::Standard), Some(&SortResult::Special)), 5);
+        assert_eq!(analysis.count(Some(&SortResult::Standard), Some(&SortResult::Standard)), 1);
+        assert_eq!(analysis.count(Some(&SortResult::Rejected), Some(&SortResult::Rejected)), 1);
+        assert_eq!(analysis.count(Some(&SortResult::Special), Some(&SortResult::Standard)), 0);
+    }
+
+    #[test]
+    fn transitions_are_ordered_and_hold_a_few_examples() {
+        let analysis = analyse(&dataset(), &SortPolicy::default(), &lowered_heavy_threshold()).unwrap();
+        assert_eq!(analysis.transitions(), &[
+            Transition { before: Some(SortResult::Standard), after: Some(SortResult::Standard), count: 1, examples: vec!["PKG-3".to_string()] },
+            Transition {
+                before: Some(SortResult::Standard),
+                after: Some(SortResult::Special),
+                count: 5,
+                examples: vec!["PKG-1".to_string(), "PKG-2".to_string(), "PKG-5".to_string()]
+            },
+            Transition { before: Some(SortResult::Rejected), after: Some(SortResult::Rejected), count: 1, examples: vec!["PKG-4".to_string()] }
+        ]);
+    }
+
+    #[test]
+    fn an_unchanged_policy_changes_nothing() {
+        let analysis = analyse(&dataset(), &SortPolicy::default(), &SortPolicy::default()).unwrap();
+        assert_eq!(analysis.changed(), 0);
+        assert!(analysis.transitions().iter().all(|transition| !transition.is_change()));
+    }
+
+    #[test]
+    fn invalid_packages_are_named() {
+        let dataset = Dataset::from_source("id,width,height,length,mass\nPKG-1,10,0,10,1\n", DatasetFormat::Csv).unwrap();
+        assert_eq!(
+            analyse(&dataset, &SortPolicy::default(), &SortPolicy::default()),
+            Err(SortError::InvalidPackage("PKG-1".to_string(), Box::new(SortError::InvalidHeight(0))))
+        );
+    }
+
+    #[test]
+    fn empty_datasets_have_no_transitions() {
+        let analysis = analyse(&Dataset::from_source("", DatasetFormat::Ndjson).unwrap(), &SortPolicy::default(), &SortPolicy::default()).unwrap();
+        assert_eq!((analysis.total(), analysis.changed(), analysis.percentage(0)), (0, 0, 0.0));
+    }
+
+    #[test]
+    fn display_impact_analysis() {
+        let analysis = analyse(&dataset(), &SortPolicy::default(), &lowered_heavy_threshold()).unwrap();
+        assert_eq!(analysis.to_string(), "\
+7 packages, 5 changed stack (71.4%)
+
+before \\ after  standard  special  rejected
+standard               1        5         0
+special                0        0         0
+rejected               0        0         1
+
+standard -> standard: 1 (14.3%), e.g. PKG-3
+standard -> special: 5 (71.4%), e.g. PKG-1, PKG-2, PKG-5
+rejected -> rejected: 1 (14.3%), e.g. PKG-4");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use crate::sort_result::SortResult;
+
+/**
+ * LaneMap
+ *
+ * Maps every stack onto the physical lanes, or chutes, that collect
+ * its packages. A stack can be served by several lanes, which are
+ * filled in the order they were added. Packages for which every lane
+ * is full, or whose stack has no lane, are sent to the overflow.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LaneMap {
+    stacks: Vec<(SortResult, Vec<String>)>,
+    capacities: BTreeMap<String, usize>,
+    overflow: Overflow
+}
+
+/**
+ * Overflow
+ *
+ * Documents what happens to packages that can't be sent to one of
+ * the lanes of their stack:
+ * - they are sent to an overflow lane, which is never considered full
+ * - they are held for an operator
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Overflow {
+    Lane(String),
+    Hold
+}
+
+/**
+ * Routing
+ *
+ * Documents where a package was sent by a lane router.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Routing {
+    Lane(String),
+    Overflow(String),
+    Held
+}
+
+/**
+ * LaneEvent
+ *
+ * Documents changes in the state of the lanes that operators should
+ * know about.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum LaneEvent {
+    LaneFull(String)
+}
+
+/**
+ * FillLevel
+ *
+ * The number of packages currently collected by a lane, out of the
+ * number it can hold.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillLevel {
+    lane: String,
+    count: usize,
+    capacity: usize
+}
+
+/**
+ * LaneRouter
+ *
+ * Routes packages onto the lanes of a lane map, keeping track of how
+ * many packages every lane holds until it is emptied.
+ */
+pub struct LaneRouter {
+    map: LaneMap,
+    counts: BTreeMap<String, usize>,
+    listener: Box<dyn FnMut(LaneEvent) + Send>
+}
+
+impl LaneMap {
+    pub fn new(overflow: Overflow) -> Self {
+        LaneMap {
+            stacks: vec![],
+            capacities: BTreeMap::new(),
+            overflow
+        }
+    }
+
+    /**
+     * Adds a lane serving the given stack, after the lanes already
+     * serving it
+     *
+     * A lane holds up to `capacity` packages. Adding a lane again
+     * replaces its capacity.
+     */
+    pub fn with_lane<T: Into<String>>(mut self, result: SortResult, lane: T, capacity: usize) -> Self {
+        let lane = lane.into();
+        self.capacities.insert(lane.clone(), capacity);
+        match self.stacks.iter_mut().find(|(stack, _)| stack == &result) {
+            Some((_, lanes)) if lanes.contains(&lane) => {},
+            Some((_, lanes)) => lanes.push(lane),
+            None => self.stacks.push((result, vec![lane]))
+        }
+        self
+    }
+
+    pub fn lanes_for(&self, result: &SortResult) -> &[String] {
+        self.stacks.iter()
+            .find(|(stack, _)| stack == result)
+            .map(|(_, lanes)| lanes.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn overflow(&self) -> &Overflow {
+        &self.overflow
+    }
+}
+
+impl FillLevel {
+    pub fn lane(&self) -> &str {
+        &self.lane
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count >= self.capacity
+    }
+}
+
+impl LaneRouter {
+    pub fn new(map: LaneMap) -> Self {
+        LaneRouter {
+            map,
+            counts: BTreeMap::new(),
+            listener: Box::new(|_| {})
+        }
+    }
+
+    /**
+     * Reports every lane that fills up to the listener
+     */
+    pub fn with_listener<F>(mut self, listener: F) -> Self
+    where
+        F: FnMut(LaneEvent) + Send + 'static
+    {
+        self.listener = Box::new(listener);
+        self
+    }
+
+    /**
+     * Sends a package of the given stack to the first of its lanes that
+     * isn't full, or to the overflow
+     */
+    pub fn route(&mut self, result: &SortResult) -> Routing {
+        let available = self.map.lanes_for(result).iter()
+            .find(|lane| self.count(lane) < self.map.capacities[*lane])
+            .cloned();
+
+        match available {
+            Some(lane) => {
+                let count = self.counts.entry(lane.clone()).or_insert(0);
+                *count += 1;
+                if *count >= self.map.capacities[&lane] {
+                    (self.listener)(LaneEvent::LaneFull(lane.clone()));
+                }
+                Routing::Lane(lane)
+            },
+            None => match &self.map.overflow {
+                Overflow::Lane(lane) => Routing::Overflow(lane.clone()),
+                Overflow::Hold => Routing::Held
+            }
+        }
+    }
+
+    /**
+     * Records that a lane was emptied, so that it can collect packages again
+     */
+    pub fn empty(&mut self, lane: &str) {
+        self.counts.remove(lane);
+    }
+
+    /**
+     * The fill level of every lane of the map, ordered by lane
+     */
+    pub fn fill_levels(&self) -> Vec<FillLevel> {
+        self.map.capacities.iter()
+            .map(|(lane, capacity)| FillLevel {
+                lane: lane.clone(),
+                count: self.count(lane),
+                capacity: *capacity
+            })
+            .collect()
+    }
+
+    fn count(&self, lane: &str) -> usize {
+        self.counts.get(lane).copied().unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for Routing {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Routing::Lane(lane) => write!(f, "lane {}", lane),
+            Routing::Overflow(lane) => write!(f, "overflow lane {}", lane),
+            Routing::Held => write!(f, "held")
+        }
+    }
+}
+
+impl std::fmt::Display for LaneEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LaneEvent::LaneFull(lane) => write!(f, "Lane {} is full", lane)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use crate::sort_result::StackId;
+
+    fn map(overflow: Overflow) -> LaneMap {
+        LaneMap::new(overflow)
+            .with_lane(SortResult::Standard, "s1", 2)
+            .with_lane(SortResult::Standard, "s2", 1)
+            .with_lane(SortResult::Special, "x1", 1)
+    }
+
+    #[test]
+    fn stacks_are_served_by_their_lanes_in_order() {
+        let map = map(Overflow::Hold);
+        assert_eq!(map.lanes_for(&SortResult::Standard), ["s1".to_string(), "s2".to_string()]);
+        assert_eq!(map.lanes_for(&SortResult::Rejected), [] as [String; 0]);
+    }
+
+    #[test]
+    fn lanes_are_filled_in_order() {
+        let mut router = LaneRouter::new(map(Overflow::Hold));
+        assert_eq!(router.route(&SortResult::Standard), Routing::Lane("s1".to_string()));
+        assert_eq!(router.route(&SortResult::Standard), Routing::Lane("s1".to_string()));
+        assert_eq!(router.route(&SortResult::Standard), Routing::Lane("s2".to_string()));
+    }
+
+    #[test]
+    fn packages_overflow_when_every_lane_is_full() {
+        let mut router = LaneRouter::new(map(Overflow::Lane("o1".to_string())));
+        assert_eq!(router.route(&SortResult::Special), Routing::Lane("x1".to_string()));
+        assert_eq!(router.route(&SortResult::Special), Routing::Overflow("o1".to_string()));
+        assert_eq!(router.route(&SortResult::Special), Routing::Overflow("o1".to_string()));
+    }
+
+    #[test]
+    fn packages_are_held_when_there_is_no_overflow_lane() {
+        let mut router = LaneRouter::new(map(Overflow::Hold));
+        router.route(&SortResult::Special);
+        assert_eq!(router.route(&SortResult::Special), Routing::Held);
+    }
+
+    #[test]
+    fn stacks_without_lanes_overflow() {
+        let mut router = LaneRouter::new(map(Overflow::Lane("o1".to_string())));
+        let lift = SortResult::Custom(StackId::new("two-person-lift").unwrap());
+        assert_eq!(router.route(&SortResult::Rejected), Routing::Overflow("o1".to_string()));
+        assert_eq!(router.route(&lift), Routing::Overflow("o1".to_string()));
+    }
+
+    #[test]
+    fn emptied_lanes_collect_packages_again() {
+        let mut router = LaneRouter::new(map(Overflow::Hold));
+        router.route(&SortResult::Special);
+        router.empty("x1");
+        assert_eq!(router.route(&SortResult::Special), Routing::Lane("x1".to_string()));
+    }
+
+    #[test]
+    fn fill_levels_are_exposed() {
+        let mut router = LaneRouter::new(map(Overflow::Hold));
+        router.route(&SortResult::Standard);
+        router.route(&SortResult::Special);
+
+        let levels = router.fill_levels();
+        let summary: Vec<_> = levels.iter().map(|level| (level.lane(), level.count(), level.capacity(), level.is_full())).collect();
+        assert_eq!(summary, vec![("s1", 1, 2, false), ("s2", 0, 1, false), ("x1", 1, 1, true)]);
+    }
+
+    #[test]
+    fn full_lanes_are_reported_once() {
+        let (sender, receiver) = mpsc::channel();
+        let mut router = LaneRouter::new(map(Overflow::Hold))
+            .with_listener(move |event| { let _ = sender.send(event); });
+
+        for _ in 0..4 {
+            router.route(&SortResult::Standard);
+        }
+
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![
+            LaneEvent::LaneFull("s1".to_string()),
+            LaneEvent::LaneFull("s2".to_string())
+        ]);
+    }
+
+    #[test]
+    fn lanes_without_capacity_are_always_full() {
+        let mut router = LaneRouter::new(LaneMap::new(Overflow::Hold).with_lane(SortResult::Standard, "s1", 0));
+        assert_eq!(router.route(&SortResult::Standard), Routing::Held);
+    }
+
+    #[test]
+    fn display_routings_and_events() {
+        assert_eq!(Routing::Lane("s1".to_string()).to_string(), "lane s1");
+        assert_eq!(Routing::Overflow("o1".to_string()).to_string(), "overflow lane o1");
+        assert_eq!(Routing::Held.to_string(), "held");
+        assert_eq!(LaneEvent::LaneFull("s1".to_string()).to_string(), "Lane s1 is full");
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::upper_case_acronyms)]
+
 #[cfg(test)]
 #[macro_use(quickcheck)]
 extern crate quickcheck_macros;
@@ -5,15 +7,63 @@ extern crate quickcheck_macros;
 use crate::measurements::cm::Cm;
 use crate::measurements::kg::Kg;
 use crate::package::dimensions::PackageDimensions;
+use crate::package::fusion;
 use crate::package::mass::Mass;
 use crate::package::Package;
-use crate::sort_error::SortError;
-use crate::sort_result::SortResult;
-
+pub use crate::dataset::{Dataset, DatasetError, DatasetFormat};
+pub use crate::decision_log::{DecisionLog, DecisionLogError, LogEntry, ReplayReport, StackChange};
+#[cfg(feature = "sqlite")]
+pub use crate::decision_store::{DailyVolume, DecisionStore, ErrorRate, StoreError, StoredSort};
+pub use crate::impact_analysis::{ImpactAnalysis, Transition};
+pub use crate::lane_map::{FillLevel, LaneEvent, LaneMap, LaneRouter, Overflow, Routing};
+pub use crate::loader::{Container, ContainerError, LoadingPlan, Placement, Utilisation};
+pub use crate::metrics::Metrics;
+pub use crate::package::attributes::{Attribute, AttributeError};
+pub use crate::package::destination::{Destination, DestinationError};
+pub use crate::package::dimensional_weight::DimensionalDivisor;
+pub use crate::package::dimensions::DimensionClass;
+pub use crate::package::fusion::{Disagreement, Fusion, FusionReport, FusionStrategy};
+pub use crate::package::mass::MassClass;
+pub use crate::package::service_level::{ServiceLevel, ServiceLevelError};
+pub use crate::package::sorted_dimensions::Side;
+pub use crate::package_input::PackageInput;
+pub use crate::shipment::{RollUp, Shipment, ShipmentDecision};
+pub use crate::packaging::{BoxSize, BoxSizeError, Recommendation};
+pub use crate::split_planner::{Divisibility, SplitPlan};
+pub use crate::policy_holder::{PolicyEvent, PolicyHolder, PolicyWatcher};
+pub use crate::reclassification::{AuditSink, JsonLinesAuditSink, OverriddenDecision, Reclassification, ReclassificationError};
+pub use crate::sort_decision::{SortDecision, SortOutcome, TraceStep};
+pub use crate::sort_error::SortError;
+pub use crate::sort_policy::bulky_strategy::BulkyStrategy;
+pub use crate::sort_policy::plausibility::{Implausibility, PlausibilityError, PlausibilityLimits};
+pub use crate::sort_policy::policy_file::{PolicyFileError, PolicyFormat};
+pub use crate::sort_policy::registry::{PolicyRegistry, PolicyRegistryError, ThresholdOverrides};
+pub use crate::sort_policy::rules::{CustomStack, EvaluationMode, Predicate, Rule, RuleSet, RuleSetError};
+pub use crate::sort_policy::schedule::{PolicySchedule, PolicyScheduleError};
+pub use crate::sort_policy::tolerance::Tolerance;
+pub use crate::sort_policy::SortPolicy;
+pub use crate::sort_result::{SortResult, StackId, StackIdError};
+
+mod dataset;
+mod decision_log;
+#[cfg(feature = "sqlite")]
+mod decision_store;
+mod impact_analysis;
+mod lane_map;
+mod loader;
+mod metrics;
 pub(crate) mod measurements;
 mod package;
+mod package_input;
+mod packaging;
+mod policy_holder;
+mod reclassification;
+mod shipment;
 mod sort_result;
 pub(crate)  mod sort_error;
+mod sort_decision;
+mod sort_policy;
+mod split_planner;
 
 /**
  * Safe sort
@@ -23,22 +73,232 @@ pub(crate)  mod sort_error;
  *
  * All input values should be positive integers and non-zero.
  */
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err(Display), fields(result = tracing::field::Empty)))]
 pub fn safe_sort(width: usize, height: usize, length: usize, mass: usize) -> Result<SortResult, SortError> {
+    let p = package(&PackageInput::new(width, height, length, mass))?;
+
+    // performs the actual sorting
+    let result = p.stack(&SortPolicy::default());
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("result", tracing::field::display(&result));
+
+    Ok(result)
+}
+
+/**
+ * Safe sort with policy
+ *
+ * Sorts a package using the thresholds of the given policy. When the
+ * policy holds plausibility limits, implausible measurements are
+ * flagged for remeasurement instead of being sorted. Measurements within
+ * the tolerance of the policy around a threshold are reported as uncertain.
+ *
+ * All input values should be positive integers and non-zero.
+ */
+pub fn safe_sort_with_policy(width: usize, height: usize, length: usize, mass: usize, policy: &SortPolicy) -> Result<SortDecision, SortError> {
+    sort_package(&PackageInput::new(width, height, length, mass), policy)
+}
+
+/**
+ * Sort package
+ *
+ * Sorts a package described by its measurements and attributes using
+ * the given policy. The rules of the policy can consult the attributes.
+ *
+ * All measurements should be positive integers and non-zero.
+ */
+#[cfg_attr(feature = "tracing", tracing::instrument(
+    level = "debug",
+    skip_all,
+    err(Display),
+    fields(width = input.width(), height = input.height(), length = input.length(), mass = input.mass(), result = tracing::field::Empty)
+))]
+pub fn sort_package(input: &PackageInput, policy: &SortPolicy) -> Result<SortDecision, SortError> {
+    let p = package(input)?;
+
+    let decision = p.sort(policy);
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("result", tracing::field::display(decision.outcome()));
+
+    Ok(decision)
+}
+
+/**
+ * Safe sort for a site
+ *
+ * Sorts a package using the policy of the given site or tenant, as
+ * registered with the registry.
+ *
+ * All input values should be positive integers and non-zero.
+ */
+pub fn safe_sort_for(site: &str, width: usize, height: usize, length: usize, mass: usize, registry: &PolicyRegistry) -> Result<SortDecision, SortError> {
+    let policy = registry.policy_for(site)
+        .ok_or_else(|| SortError::UnknownSite(site.to_string()))?;
+
+    safe_sort_with_policy(width, height, length, mass, policy)
+}
+
+/**
+ * Recommend packaging
+ *
+ * Recommends how to pack a package so that it is sorted as standard
+ * under the given policy: the smallest box of the catalogue holding
+ * it, or else a split of its contents by mass over several boxes.
+ * Returns nothing when no box of the catalogue keeps it standard.
+ *
+ * All measurements should be positive integers and non-zero.
+ */
+pub fn recommend_packaging(input: &PackageInput, catalogue: &[BoxSize], policy: &SortPolicy) -> Result<Option<Recommendation>, SortError> {
+    packaging::recommend(input, catalogue, policy)
+}
+
+/**
+ * Plan split
+ *
+ * Plans how to split a load, typically one that would be rejected,
+ * into the fewest packages that are each sorted as standard or
+ * special under the given policy, with as few special ones as
+ * possible. Every planned package is sorted to validate the plan.
+ * Returns nothing when the load can't be split that way.
+ *
+ * All measurements should be positive integers and non-zero.
+ */
+pub fn plan_split(load: &PackageInput, divisibility: &Divisibility, policy: &SortPolicy) -> Result<Option<SplitPlan>, SortError> {
+    split_planner::plan(load, divisibility, policy)
+}
+
+/**
+ * Load stack
+ *
+ * Plans how the packages sorted onto a stack are loaded into the
+ * given containers, such as roll cages or pallets, within their inner
+ * dimensions and weight limits. The plan reports the utilisation of
+ * every container and the packages that could not be placed.
+ *
+ * All measurements should be positive integers and non-zero.
+ */
+pub fn load_stack(packages: &[PackageInput], containers: &[Container]) -> Result<LoadingPlan, SortError> {
+    let items = packages.iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let dimensions = package_dimensions(input.width(), input.height(), input.length());
+            dimensions.and_then(|dimensions| Ok((dimensions, package_mass(input.mass())?)))
+                .map_err(|error| SortError::InvalidPiece(index + 1, Box::new(error)))
+        })
+        .collect::<Result<Vec<_>, SortError>>()?;
+
+    Ok(loader::load(&items, containers))
+}
+
+/**
+ * Sort shipment
+ *
+ * Sorts every piece of a shipment using the given policy, and rolls
+ * their results up into the result of the shipment as configured.
+ * A shipment is rejected as soon as one of its pieces is rejected.
+ *
+ * Every piece should hold positive, non-zero measurements.
+ */
+pub fn sort_shipment(shipment: &Shipment, policy: &SortPolicy, roll_up: &RollUp) -> Result<ShipmentDecision, SortError> {
+    shipment.sort(policy, roll_up)
+}
+
+/**
+ * Override decision
+ *
+ * Overrules the automatic decision for a package, as a supervisor does
+ * after inspecting it. The override is recorded with the audit sink
+ * before it takes effect, and the returned decision keeps both the
+ * automatic and the final result.
+ */
+pub fn override_decision(decision: &SortDecision, reclassification: Reclassification, policy: &SortPolicy, sink: &mut dyn AuditSink) -> Result<OverriddenDecision, ReclassificationError> {
+    reclassification.apply(decision, policy, sink)
+}
+
+/**
+ * Replay
+ *
+ * Sorts the inputs of a decision log again under another policy, and
+ * reports the packages that would have been sent to another stack.
+ * Uncertain packages are compared on their conservative stack.
+ */
+pub fn replay(entries: &[LogEntry], policy: &SortPolicy) -> Result<ReplayReport, DecisionLogError> {
+    decision_log::replay(entries, policy)
+}
+
+/**
+ * Analyse impact
+ *
+ * Sorts every package of a dataset under the current and a proposed
+ * policy, and reports how many packages, and which ones, would move
+ * from every stack to every other stack.
+ *
+ * Every package should hold positive, non-zero measurements.
+ */
+pub fn analyse_impact(dataset: &Dataset, current: &SortPolicy, proposed: &SortPolicy) -> Result<ImpactAnalysis, SortError> {
+    impact_analysis::analyse(dataset, current, proposed)
+}
+
+/**
+ * Safe sort readings
+ *
+ * Sorts a package that was measured several times. The readings are
+ * fused into a single measurement as configured by the policy, and the
+ * returned report documents rejected outliers and readings that
+ * disagree beyond the tolerance of the policy. A package whose readings
+ * of a measurement are all outliers is flagged for remeasurement.
+ *
+ * Every reading should hold positive, non-zero integers, and at least
+ * one dimension and one mass reading are expected.
+ */
+pub fn safe_sort_readings(dimension_readings: &[(usize, usize, usize)], mass_readings: &[usize], policy: &SortPolicy) -> Result<(SortDecision, FusionReport), SortError> {
+    if dimension_readings.is_empty() {
+        return Err(SortError::MissingDimensionReadings);
+    }
+    if mass_readings.is_empty() {
+        return Err(SortError::MissingMassReadings);
+    }
+
+    let dimensions = dimension_readings.iter()
+        .map(|(width, height, length)| package_dimensions(*width, *height, *length))
+        .collect::<Result<Vec<_>, _>>()?;
+    let masses = mass_readings.iter()
+        .map(|mass| package_mass(*mass))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (p, report) = fusion::fuse(&dimensions, &masses, policy);
+    let decision = match p {
+        Ok(p) => p.sort(policy),
+        Err(reason) => SortDecision::new(SortOutcome::NeedsRemeasure(reason), vec![], policy.version().to_string())
+    };
+
+    Ok((decision, report))
+}
 
-    // type conversions to impose domain invariants
+fn package(input: &PackageInput) -> Result<Package, SortError> {
+    // composes the Package aggregate root
+    Ok(
+        Package::new(
+            package_dimensions(input.width(), input.height(), input.length())?,
+            package_mass(input.mass())?
+        )
+        .with_attributes(input.attributes().clone())
+        .with_destination(input.destination().cloned())
+        .with_service_level(input.service_level().cloned())
+    )
+}
+
+// type conversions to impose domain invariants
+fn package_dimensions(width: usize, height: usize, length: usize) -> Result<PackageDimensions, SortError> {
     let width_cm = Cm::new(width).map_err(|e| SortError::InvalidWidth(e.original_value()))?;
     let height_cm = Cm::new(height).map_err(|e| SortError::InvalidHeight(e.original_value()))?;
     let length_cm = Cm::new(length).map_err(|e| SortError::InvalidLength(e.original_value()))?;
-    let mass_kg = Kg::new(mass)?;
 
-    // composes the Package aggregate root
-    let p = Package::new(
-        PackageDimensions::new(width_cm, height_cm, length_cm),
-        Mass::new(mass_kg)
-    );
+    Ok(PackageDimensions::new(width_cm, height_cm, length_cm))
+}
 
-    // performs the actual sorting
-    Ok(p.sort())
+fn package_mass(mass: usize) -> Result<Mass, SortError> {
+    Ok(Mass::new(Kg::new(mass)?))
 }
 
 /**
@@ -119,6 +379,44 @@ pub(crate) mod test_dependencies {
         super::safe_sort(width.into(), height.into(), length.into(), mass.into())
     }
 
+    pub(crate) mod scratch {
+        use std::path::{Path, PathBuf};
+
+        /**
+         * A file in the temporary directory, named after the process so
+         * that concurrent test runs don't collide, and deleted once dropped
+         */
+        pub(crate) struct ScratchFile { path: PathBuf }
+
+        impl ScratchFile {
+            pub(crate) fn new(name: &str) -> Self {
+                let path = std::env::temp_dir().join(format!("otta-{}-{}", std::process::id(), name));
+                let _ = std::fs::remove_file(&path);
+                ScratchFile { path }
+            }
+        }
+
+        impl std::ops::Deref for ScratchFile {
+            type Target = Path;
+
+            fn deref(&self) -> &Path {
+                &self.path
+            }
+        }
+
+        impl AsRef<Path> for ScratchFile {
+            fn as_ref(&self) -> &Path {
+                &self.path
+            }
+        }
+
+        impl Drop for ScratchFile {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_file(&self.path);
+            }
+        }
+    }
+
     pub(crate) mod length {
         use crate::test_dependencies::{generate_outside_of_range, generate_within_range};
         use quickcheck::Arbitrary;
@@ -135,9 +433,9 @@ pub(crate) mod test_dependencies {
             }
         }
 
-        impl Into<usize> for InvalidLength {
-            fn into(self) -> usize {
-                self.value
+        impl From<InvalidLength> for usize {
+            fn from(input: InvalidLength) -> Self {
+                input.value
             }
         }
 
@@ -153,9 +451,9 @@ pub(crate) mod test_dependencies {
             }
         }
 
-        impl Into<usize> for ValidLength {
-            fn into(self) -> usize {
-                self.value
+        impl From<ValidLength> for usize {
+            fn from(input: ValidLength) -> Self {
+                input.value
             }
         }
     }
@@ -182,9 +480,9 @@ pub(crate) mod test_dependencies {
             }
         }
 
-        impl Into<usize> for InvalidMass {
-            fn into(self) -> usize {
-                self.value
+        impl From<InvalidMass> for usize {
+            fn from(input: InvalidMass) -> Self {
+                input.value
             }
         }
 
@@ -200,9 +498,9 @@ pub(crate) mod test_dependencies {
             }
         }
 
-        impl Into<usize> for ValidMass {
-            fn into(self) -> usize {
-                self.value
+        impl From<ValidMass> for usize {
+            fn from(input: ValidMass) -> Self {
+                input.value
             }
         }
     }
@@ -217,6 +515,7 @@ mod tests {
         use crate::test_dependencies::length::{InvalidLength, ValidLength};
         use crate::test_dependencies::mass::{InvalidMass, ValidMass};
         use crate::test_dependencies::sort_helper;
+        use crate::safe_sort;
 
         #[quickcheck]
         fn sorting_packages_returns_one_of_4_strings(width: ValidLength, height: ValidLength, length: ValidLength, mass: ValidMass) {
@@ -225,6 +524,15 @@ mod tests {
             assert!(requirement);
         }
 
+        #[quickcheck]
+        fn permuting_the_sides_never_changes_the_result(width: ValidLength, height: ValidLength, length: ValidLength, mass: ValidMass) {
+            let (width, height, length, mass): (usize, usize, usize, usize) = (width.into(), height.into(), length.into(), mass.into());
+            let expected = safe_sort(width, height, length, mass);
+            for (w, h, l) in [(width, length, height), (height, width, length), (height, length, width), (length, width, height), (length, height, width)] {
+                assert_eq!(safe_sort(w, h, l, mass), expected);
+            }
+        }
+
         #[quickcheck]
         fn sorting_with_an_invalid_width_produces_an_error(width: InvalidLength, height: ValidLength, length: ValidLength, mass: ValidMass) {
             let expected = Err(SortError::InvalidWidth(width.clone().into()));
@@ -254,6 +562,318 @@ mod tests {
         }
     }
 
+    mod safe_sort_with_policy {
+        use crate::sort_error::SortError;
+        use crate::sort_result::SortResult;
+        use crate::test_dependencies::length::ValidLength;
+        use crate::test_dependencies::mass::{InvalidMass, ValidMass};
+        use crate::{safe_sort, safe_sort_with_policy, Implausibility, PlausibilityLimits, SortOutcome, SortPolicy};
+
+        #[quickcheck]
+        fn the_default_policy_sorts_like_safe_sort(width: ValidLength, height: ValidLength, length: ValidLength, mass: ValidMass) -> bool {
+            let (width, height, length, mass) = (width.into(), height.into(), length.into(), mass.into());
+            safe_sort_with_policy(width, height, length, mass, &SortPolicy::default()).map(|decision| decision.outcome().clone())
+                == safe_sort(width, height, length, mass).map(SortOutcome::Sorted)
+        }
+
+        #[quickcheck]
+        fn invalid_inputs_are_still_errors(width: ValidLength, height: ValidLength, length: ValidLength, mass: InvalidMass) {
+            let expected = Err(SortError::InvalidMass(mass.clone().into()));
+            let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default());
+            assert_eq!(safe_sort_with_policy(width.into(), height.into(), length.into(), mass.into(), &policy), expected)
+        }
+
+        #[test]
+        fn test_plausible_sort() {
+            let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default());
+            assert_eq!(safe_sort_with_policy(100, 40, 30, 10, &policy).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Special))
+        }
+
+        #[test]
+        fn test_glitching_dimensioner() {
+            let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default());
+            assert_eq!(
+                safe_sort_with_policy(9_000_000, 40, 30, 10, &policy).unwrap().outcome(),
+                &SortOutcome::NeedsRemeasure(Implausibility::SideTooLong(9_000_000, 500))
+            )
+        }
+    }
+
+    mod uncertain_sort {
+        use crate::sort_result::SortResult;
+        use crate::{safe_sort_with_policy, SortOutcome, SortPolicy, Tolerance};
+
+        #[test]
+        fn test_nearly_heavy_sort() {
+            let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_mass(1));
+            assert_eq!(
+                safe_sort_with_policy(10, 10, 10, 19, &policy).unwrap().outcome(),
+                &SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special }
+            )
+        }
+
+        #[test]
+        fn test_nearly_bulky_and_heavy_sort() {
+            let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1).with_mass(1));
+            assert_eq!(
+                safe_sort_with_policy(100, 30, 19, 40, &policy).unwrap().outcome(),
+                &SortOutcome::Uncertain { lenient: SortResult::Special, conservative: SortResult::Rejected }
+            )
+        }
+    }
+
+    mod safe_sort_readings {
+        use crate::sort_error::SortError;
+        use crate::sort_result::SortResult;
+        use crate::test_dependencies::length::ValidLength;
+        use crate::test_dependencies::mass::ValidMass;
+        use crate::{safe_sort, safe_sort_readings, Disagreement, Implausibility, Side, SortOutcome, SortPolicy, Tolerance};
+
+        #[quickcheck]
+        fn a_single_reading_sorts_like_safe_sort(width: ValidLength, height: ValidLength, length: ValidLength, mass: ValidMass) -> bool {
+            let (width, height, length, mass) = (width.into(), height.into(), length.into(), mass.into());
+            let (decision, report) = safe_sort_readings(&[(width, height, length)], &[mass], &SortPolicy::default()).unwrap();
+            Ok(decision.outcome().clone()) == safe_sort(width, height, length, mass).map(SortOutcome::Sorted) && report.is_consistent()
+        }
+
+        #[test]
+        fn test_fused_sort_ignores_a_glitching_reading() {
+            let (decision, report) = safe_sort_readings(&[(50, 40, 30), (51, 40, 30), (50, 400, 30)], &[19, 19], &SortPolicy::default()).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+            assert_eq!(report.rejected_readings(), 1);
+            assert_eq!(report.disagreements(), &[Disagreement::Side(Side::Longest, 350, 0), Disagreement::Side(Side::Middle, 10, 0)]);
+        }
+
+        #[test]
+        fn test_fused_sort_within_tolerance() {
+            let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1).with_mass(1));
+            let (decision, report) = safe_sort_readings(&[(50, 40, 30), (51, 40, 30)], &[10, 11, 10], &policy).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+            assert!(report.is_consistent());
+        }
+
+        #[test]
+        fn test_fused_sort_of_readings_that_disagree() {
+            let (decision, report) = safe_sort_readings(&[(50, 40, 30), (400, 40, 30)], &[19], &SortPolicy::default()).unwrap();
+            assert_eq!(
+                decision.outcome(),
+                &SortOutcome::NeedsRemeasure(Implausibility::Irreconcilable(Disagreement::Side(Side::Longest, 350, 20)))
+            );
+            assert_eq!(report.disagreements(), &[Disagreement::Side(Side::Longest, 350, 0)]);
+        }
+
+        #[test]
+        fn test_fused_sort_of_readings_in_other_orientations() {
+            let (decision, report) = safe_sort_readings(&[(140, 5, 5), (5, 5, 140), (5, 140, 5)], &[5], &SortPolicy::default()).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+            assert!(report.is_consistent());
+
+            let (decision, report) = safe_sort_readings(&[(100, 10, 10), (10, 10, 100)], &[5], &SortPolicy::default()).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+            assert!(report.is_consistent());
+        }
+
+        #[test]
+        fn test_missing_readings() {
+            assert_eq!(safe_sort_readings(&[], &[10], &SortPolicy::default()), Err(SortError::MissingDimensionReadings));
+            assert_eq!(safe_sort_readings(&[(1, 1, 1)], &[], &SortPolicy::default()), Err(SortError::MissingMassReadings));
+        }
+
+        #[test]
+        fn test_invalid_reading() {
+            assert_eq!(safe_sort_readings(&[(1, 1, 1), (1, 0, 1)], &[10], &SortPolicy::default()), Err(SortError::InvalidHeight(0)));
+        }
+    }
+
+    mod sort_package {
+        use crate::sort_error::SortError;
+        use crate::sort_result::SortResult;
+        use crate::{sort_package, Attribute, Destination, EvaluationMode, PackageInput, Predicate, Rule, RuleSet, ServiceLevel, SortOutcome, SortPolicy, TraceStep};
+
+        fn hazmat_policy() -> SortPolicy {
+            let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+                Rule::new("hazmat", SortResult::Special).when(Predicate::HasAttribute(Attribute::Hazmat)),
+                Rule::new("standard", SortResult::Standard)
+            ]).unwrap();
+            SortPolicy::default().with_rules(rules)
+        }
+
+        #[test]
+        fn test_attribute_driven_sort() {
+            let decision = sort_package(&PackageInput::new(10, 10, 10, 1).with_attribute(Attribute::Hazmat), &hazmat_policy()).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+            assert!(decision.trace().contains(&TraceStep::AttributeConsulted(Attribute::Hazmat)));
+            assert!(decision.trace().contains(&TraceStep::RuleMatched("hazmat".to_string(), SortResult::Special)));
+        }
+
+        #[test]
+        fn test_destination_driven_sort() {
+            let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+                Rule::new("express to germany", SortResult::Special)
+                    .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                    .when(Predicate::DestinationCountry("DE".to_string()))
+                    .with_routing_key("germany"),
+                Rule::new("standard", SortResult::Standard)
+            ]).unwrap();
+            let input = PackageInput::new(10, 10, 10, 1)
+                .with_destination(Destination::new("de").unwrap().with_postcode("10115").unwrap())
+                .with_service_level(ServiceLevel::Express);
+
+            let decision = sort_package(&input, &SortPolicy::default().with_rules(rules)).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+            assert_eq!(decision.routing_key(), Some("germany"));
+        }
+
+        #[test]
+        fn test_attributes_are_ignored_by_default() {
+            let decision = sort_package(&PackageInput::new(10, 10, 10, 1).with_attribute(Attribute::Hazmat), &SortPolicy::default()).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+        }
+
+        #[test]
+        fn test_invalid_input() {
+            assert_eq!(sort_package(&PackageInput::new(10, 10, 10, 0), &hazmat_policy()), Err(SortError::InvalidMass(0)));
+        }
+    }
+
+    mod recommend_packaging {
+        use crate::sort_error::SortError;
+        use crate::{recommend_packaging, BoxSize, PackageInput, Recommendation, SortPolicy};
+
+        #[test]
+        fn test_bulky_package() {
+            let catalogue = vec![BoxSize::new("small", 30, 20, 10).unwrap(), BoxSize::new("medium", 40, 30, 30).unwrap()];
+            let recommendation = recommend_packaging(&PackageInput::new(35, 100, 20, 5), &catalogue, &SortPolicy::default()).unwrap();
+            assert_eq!(recommendation, Some(Recommendation::Split(BoxSize::new("medium", 40, 30, 30).unwrap(), 2)));
+        }
+
+        #[test]
+        fn test_invalid_input() {
+            assert_eq!(recommend_packaging(&PackageInput::new(0, 10, 10, 1), &[], &SortPolicy::default()), Err(SortError::InvalidWidth(0)));
+        }
+    }
+
+    mod plan_split {
+        use crate::sort_result::SortResult;
+        use crate::{plan_split, Divisibility, PackageInput, SortOutcome, SortPolicy};
+
+        #[test]
+        fn test_rejected_load() {
+            let load = PackageInput::new(100, 100, 40, 30);
+            let plan = plan_split(&load, &Divisibility::Units(2), &SortPolicy::default()).unwrap().unwrap();
+
+            assert_eq!(plan.len(), 2);
+            assert!(plan.pieces().iter().all(|(_, decision)| decision.outcome() == &SortOutcome::Sorted(SortResult::Special)));
+        }
+    }
+
+    mod load_stack {
+        use crate::sort_error::SortError;
+        use crate::{load_stack, Container, PackageInput};
+
+        #[test]
+        fn test_loading() {
+            let cage = Container::new("cage", 80, 160, 70, 250).unwrap();
+            let plan = load_stack(&[PackageInput::new(40, 30, 70, 10), PackageInput::new(200, 10, 10, 1)], &[cage]).unwrap();
+
+            assert_eq!(plan.placements().len(), 1);
+            assert_eq!(plan.unplaceable(), &[1]);
+        }
+
+        #[test]
+        fn test_invalid_input() {
+            assert_eq!(
+                load_stack(&[PackageInput::new(40, 30, 70, 10), PackageInput::new(40, 30, 70, 0)], &[]),
+                Err(SortError::InvalidPiece(2, Box::new(SortError::InvalidMass(0))))
+            );
+        }
+    }
+
+    mod sort_shipment {
+        use crate::sort_result::SortResult;
+        use crate::{sort_shipment, PackageInput, RollUp, Shipment, SortOutcome, SortPolicy};
+
+        #[test]
+        fn test_rejected_piece() {
+            let shipment = Shipment::new()
+                .with_piece(PackageInput::new(10, 10, 10, 1))
+                .with_piece(PackageInput::new(100, 100, 100, 30));
+            let decision = sort_shipment(&shipment, &SortPolicy::default(), &RollUp::default()).unwrap();
+
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Rejected));
+            assert_eq!(decision.pieces()[0].outcome(), &SortOutcome::Sorted(SortResult::Standard));
+            assert_eq!(decision.total_mass(), 31);
+        }
+
+        #[test]
+        fn test_total_mass() {
+            let shipment = Shipment::new()
+                .with_piece(PackageInput::new(10, 10, 10, 12))
+                .with_piece(PackageInput::new(10, 10, 10, 12));
+            let roll_up = RollUp::default().with_total_mass_at_least(20, SortResult::Special);
+
+            let decision = sort_shipment(&shipment, &SortPolicy::default(), &roll_up).unwrap();
+            assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+        }
+    }
+
+    mod override_decision {
+        use chrono::{DateTime, Utc};
+        use crate::sort_result::SortResult;
+        use crate::{override_decision, safe_sort_with_policy, OverriddenDecision, Reclassification, SortOutcome, SortPolicy};
+
+        #[test]
+        fn test_rejected_package_forced_to_special() {
+            let decision = safe_sort_with_policy(100, 100, 100, 30, &SortPolicy::default()).unwrap();
+            let at = DateTime::parse_from_rfc3339("2026-10-19T14:30:00Z").unwrap().with_timezone(&Utc);
+            let mut audit: Vec<OverriddenDecision> = vec![];
+
+            let overridden = override_decision(
+                &decision,
+                Reclassification::new("PKG-1", SortResult::Special, "alex", "inspected", at),
+                &SortPolicy::default(),
+                &mut audit
+            ).unwrap();
+
+            assert_eq!(overridden.automatic().outcome(), &SortOutcome::Sorted(SortResult::Rejected));
+            assert_eq!(overridden.result(), &SortResult::Special);
+            assert_eq!(audit, vec![overridden]);
+        }
+    }
+
+    mod safe_sort_for {
+        use crate::sort_error::SortError;
+        use crate::sort_result::SortResult;
+        use crate::{safe_sort_for, PolicyRegistry, SortOutcome, SortPolicy, ThresholdOverrides};
+
+        fn registry() -> PolicyRegistry {
+            PolicyRegistry::new(SortPolicy::default())
+                .with_site("ams", ThresholdOverrides::default()).unwrap()
+                .with_site("lhr", ThresholdOverrides::default().with_heavy_threshold(18)).unwrap()
+        }
+
+        #[test]
+        fn test_site_thresholds() {
+            let ams = safe_sort_for("ams", 10, 10, 10, 19, &registry()).unwrap();
+            assert_eq!(ams.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+            assert_eq!(ams.policy_version(), "default/ams");
+
+            let lhr = safe_sort_for("lhr", 10, 10, 10, 19, &registry()).unwrap();
+            assert_eq!(lhr.outcome(), &SortOutcome::Sorted(SortResult::Special));
+            assert_eq!(lhr.policy_version(), "default/lhr");
+        }
+
+        #[test]
+        fn test_unknown_site() {
+            assert_eq!(safe_sort_for("cdg", 10, 10, 10, 0, &registry()), Err(SortError::UnknownSite("cdg".to_string())));
+        }
+
+        #[test]
+        fn test_invalid_input() {
+            assert_eq!(safe_sort_for("ams", 0, 10, 10, 1, &registry()), Err(SortError::InvalidWidth(0)));
+        }
+    }
+
     mod safe_sort_2 {
         use crate::safe_sort_2;
 
@@ -283,6 +903,71 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "tracing")]
+    mod tracing_spans {
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+        use serde_json::Value;
+        use crate::safe_sort;
+
+        #[derive(Clone, Default)]
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Captured {
+            fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(bytes)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // the closed spans, as written to JSON logs
+        fn closed_spans<F: FnOnce()>(sort: F) -> Vec<Value> {
+            let captured = Captured::default();
+            let writer = captured.clone();
+            let subscriber = tracing_subscriber::fmt()
+                .json()
+                .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+                .with_max_level(tracing::Level::DEBUG)
+                .with_writer(move || writer.clone())
+                .finish();
+            tracing::subscriber::with_default(subscriber, sort);
+
+            let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+            logs.lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .filter(|event| event["fields"]["message"] == "close")
+                .map(|event| event["span"].clone())
+                .collect()
+        }
+
+        fn span<'a>(spans: &'a [Value], name: &str) -> &'a Value {
+            spans.iter().find(|span| span["name"] == name).unwrap_or_else(|| panic!("no {} span in {:?}", name, spans))
+        }
+
+        #[test]
+        fn sorts_are_followed_from_their_input_to_their_result() {
+            let spans = closed_spans(|| assert_eq!(safe_sort(10, 20, 30, 19), Ok(crate::SortResult::Standard)));
+
+            let sort = span(&spans, "safe_sort");
+            assert_eq!((&sort["width"], &sort["height"], &sort["length"], &sort["mass"]), (&Value::from(10), &Value::from(20), &Value::from(30), &Value::from(19)));
+            assert_eq!(sort["result"], "standard");
+            let dimensions = spans.iter().find(|span| span.get("dimension_class").is_some()).unwrap();
+            assert_eq!((&dimensions["width"], &dimensions["dimension_class"]), (&Value::from(10), &Value::from("standard")));
+            let mass = spans.iter().find(|span| span.get("mass_class").is_some()).unwrap();
+            assert_eq!((&mass["mass"], &mass["billable"], &mass["mass_class"]), (&Value::from(19), &Value::from(19), &Value::from("standard")));
+        }
+
+        #[test]
+        fn failed_sorts_leave_the_result_out() {
+            let spans = closed_spans(|| assert!(safe_sort(10, 0, 30, 19).is_err()));
+            assert_eq!(spans.len(), 1);
+            assert_eq!(span(&spans, "safe_sort").get("result"), None);
+        }
+    }
+
     mod panicking_sort {
         use crate::sort;
 
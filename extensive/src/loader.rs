@@ -0,0 +1,427 @@
+use crate::measurements::cm::Cm;
+use crate::measurements::kg::Kg;
+use crate::package::dimensions::PackageDimensions;
+use crate::package::mass::Mass;
+
+/**
+ * Container
+ *
+ * A roll cage, pallet or other container a stack is loaded into,
+ * described by its inner dimensions in cm and the mass it can carry.
+ * Its width and length span the floor, its height is vertical.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Container {
+    name: String,
+    dimensions: PackageDimensions,
+    max_mass: Kg
+}
+
+/**
+ * ContainerError
+ *
+ * Documents why a container could not be described: one of its inner
+ * dimensions or its weight limit is invalid.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContainerError {
+    InvalidDimension(String, usize),
+    InvalidWeightLimit(String, usize)
+}
+
+/**
+ * Placement
+ *
+ * Where a package was placed: the container it was loaded into, the
+ * corner of the package closest to the floor-level origin of the
+ * container, and the extent of the package along the width, height
+ * and length of the container, as it was turned to fit.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    item: usize,
+    container: usize,
+    position: (usize, usize, usize),
+    extent: (usize, usize, usize)
+}
+
+/**
+ * Utilisation
+ *
+ * How much of the volume and of the weight limit of a container is
+ * taken up by the packages loaded into it.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Utilisation {
+    container: String,
+    used_volume: u128,
+    volume: u128,
+    used_mass: usize,
+    max_mass: usize
+}
+
+/**
+ * LoadingPlan
+ *
+ * How the packages of a stack are loaded into containers, the
+ * utilisation of every container, and the packages that could not
+ * be placed in any of them. Packages are referred to by their
+ * position in the loaded stack.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadingPlan {
+    placements: Vec<Placement>,
+    utilisation: Vec<Utilisation>,
+    unplaceable: Vec<usize>
+}
+
+impl Container {
+    pub fn new<T: Into<String>>(name: T, width: usize, height: usize, length: usize, max_mass: usize) -> Result<Self, ContainerError> {
+        let name = name.into();
+        let cm = |value| Cm::new(value).map_err(|_| ContainerError::InvalidDimension(name.clone(), value));
+        let dimensions = PackageDimensions::new(cm(width)?, cm(height)?, cm(length)?);
+        let max_mass = Kg::new(max_mass).map_err(|_| ContainerError::InvalidWeightLimit(name.clone(), max_mass))?;
+
+        Ok(Container {
+            name,
+            dimensions,
+            max_mass
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extent(&self) -> (usize, usize, usize) {
+        (self.dimensions.width().value(), self.dimensions.height().value(), self.dimensions.length().value())
+    }
+}
+
+impl Placement {
+    pub fn item(&self) -> usize {
+        self.item
+    }
+
+    pub fn container(&self) -> usize {
+        self.container
+    }
+
+    /**
+     * The offsets along the width, height and length of the container
+     */
+    pub fn position(&self) -> (usize, usize, usize) {
+        self.position
+    }
+
+    /**
+     * The extent along the width, height and length of the container
+     */
+    pub fn extent(&self) -> (usize, usize, usize) {
+        self.extent
+    }
+
+    fn overlaps(&self, other: &Placement) -> bool {
+        let overlap = |from: usize, size: usize, other_from: usize, other_size: usize| {
+            from < other_from + other_size && other_from < from + size
+        };
+        self.container == other.container
+            && overlap(self.position.0, self.extent.0, other.position.0, other.extent.0)
+            && overlap(self.position.1, self.extent.1, other.position.1, other.extent.1)
+            && overlap(self.position.2, self.extent.2, other.position.2, other.extent.2)
+    }
+}
+
+impl Utilisation {
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+
+    pub fn used_volume(&self) -> u128 {
+        self.used_volume
+    }
+
+    pub fn used_mass(&self) -> usize {
+        self.used_mass
+    }
+
+    /**
+     * The share of the volume of the container in use, between 0 and 1
+     */
+    pub fn volume_ratio(&self) -> f64 {
+        self.used_volume as f64 / self.volume as f64
+    }
+
+    /**
+     * The share of the weight limit of the container in use, between 0 and 1
+     */
+    pub fn mass_ratio(&self) -> f64 {
+        self.used_mass as f64 / self.max_mass as f64
+    }
+}
+
+impl LoadingPlan {
+    pub fn placements(&self) -> &[Placement] {
+        &self.placements
+    }
+
+    pub fn utilisation(&self) -> &[Utilisation] {
+        &self.utilisation
+    }
+
+    pub fn unplaceable(&self) -> &[usize] {
+        &self.unplaceable
+    }
+}
+
+/**
+ * Loads the packages of a stack into the given containers, filling
+ * them in order
+ *
+ * Packages are loaded from the largest to the smallest, each in the
+ * first container where it fits, at the lowest free corner next to or
+ * on top of the packages already loaded, turned in any direction.
+ * Whether a load is stable is left to the people loading it.
+ */
+pub(crate) fn load(items: &[(PackageDimensions, Mass)], containers: &[Container]) -> LoadingPlan {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by_key(|index| std::cmp::Reverse(items[*index].0.volume()));
+
+    let mut placements: Vec<Placement> = vec![];
+    let mut corners: Vec<Vec<(usize, usize, usize)>> = vec![vec![(0, 0, 0)]; containers.len()];
+    let mut masses: Vec<usize> = vec![0; containers.len()];
+    let mut unplaceable = vec![];
+
+    for item in order {
+        let (dimensions, mass) = &items[item];
+        let mass = mass.value().value();
+
+        let placement = containers.iter().enumerate()
+            .filter(|(container, limit)| masses[*container].checked_add(mass).is_some_and(|total| total <= limit.max_mass.value()))
+            .find_map(|(container, limit)| place(item, container, limit.extent(), dimensions, &corners[container], &placements));
+
+        match placement {
+            Some(placement) => {
+                let ((x, y, z), (width, height, length)) = (placement.position, placement.extent);
+                let corners = &mut corners[placement.container];
+                corners.retain(|corner| corner != &(x, y, z));
+                corners.extend([(x + width, y, z), (x, y + height, z), (x, y, z + length)]);
+                corners.sort_by_key(|(x, y, z)| (*y, *z, *x));
+                corners.dedup();
+
+                masses[placement.container] += mass;
+                placements.push(placement);
+            },
+            None => unplaceable.push(item)
+        }
+    }
+
+    placements.sort_by_key(|placement| placement.item);
+    unplaceable.sort();
+
+    let utilisation = containers.iter().enumerate()
+        .map(|(index, container)| Utilisation {
+            container: container.name.clone(),
+            used_volume: placements.iter()
+                .filter(|placement| placement.container == index)
+                .map(|placement| items[placement.item].0.volume())
+                .sum(),
+            volume: container.dimensions.volume(),
+            used_mass: masses[index],
+            max_mass: container.max_mass.value()
+        })
+        .collect();
+
+    LoadingPlan {
+        placements,
+        utilisation,
+        unplaceable
+    }
+}
+
+// the first free corner of the container where the package fits, in any orientation
+fn place(item: usize, container: usize, bounds: (usize, usize, usize), dimensions: &PackageDimensions, corners: &[(usize, usize, usize)], placements: &[Placement]) -> Option<Placement> {
+    let (a, b, c) = (dimensions.width().value(), dimensions.height().value(), dimensions.length().value());
+    let orientations = [(a, b, c), (a, c, b), (b, a, c), (b, c, a), (c, a, b), (c, b, a)];
+    let fits = |from: usize, size: usize, bound: usize| from.checked_add(size).is_some_and(|to| to <= bound);
+
+    corners.iter()
+        .flat_map(|corner| orientations.iter().map(move |extent| (*corner, *extent)))
+        .map(|(position, extent)| Placement { item, container, position, extent })
+        .find(|candidate| fits(candidate.position.0, candidate.extent.0, bounds.0)
+            && fits(candidate.position.1, candidate.extent.1, bounds.1)
+            && fits(candidate.position.2, candidate.extent.2, bounds.2)
+            && !placements.iter().any(|placed| placed.overlaps(candidate)))
+}
+
+impl std::fmt::Display for Utilisation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {:.0}% of volume, {:.0}% of weight limit", self.container, self.volume_ratio() * 100.0, self.mass_ratio() * 100.0)
+    }
+}
+
+impl std::fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ContainerError::InvalidDimension(name, value) => write!(f, "Invalid dimension for container {}: expecting a value of 1 or more, but got {}", name, value),
+            ContainerError::InvalidWeightLimit(name, value) => write!(f, "Invalid weight limit for container {}: expecting a value of 1 or more, but got {}", name, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::test_dependencies::ParcelDimensions;
+    use quickcheck::{Arbitrary, Gen};
+
+    fn item(width: usize, height: usize, length: usize, mass: usize) -> (PackageDimensions, Mass) {
+        let cm = |value| Cm::new(value).unwrap();
+        (PackageDimensions::new(cm(width), cm(height), cm(length)), Mass::new(Kg::new(mass).unwrap()))
+    }
+
+    fn cage() -> Container {
+        Container::new("cage", 100, 100, 100, 50).unwrap()
+    }
+
+    #[test]
+    fn packages_are_loaded_from_the_floor_up() {
+        let plan = load(&[item(50, 50, 100, 10), item(50, 50, 100, 10), item(100, 50, 100, 10)], &[cage()]);
+
+        let positions: Vec<_> = plan.placements().iter().map(|placement| placement.position()).collect();
+        assert_eq!(positions, vec![(0, 50, 0), (50, 50, 0), (0, 0, 0)]);
+        assert_eq!(plan.unplaceable(), &[] as &[usize]);
+        assert_eq!(plan.utilisation()[0].volume_ratio(), 1.0);
+        assert_eq!(plan.utilisation()[0].used_mass(), 30);
+    }
+
+    #[test]
+    fn packages_are_turned_to_fit() {
+        let plan = load(&[item(100, 10, 20, 1)], &[Container::new("low", 100, 30, 20, 50).unwrap()]);
+        assert_eq!(plan.placements()[0].extent(), (100, 10, 20));
+
+        let plan = load(&[item(10, 100, 20, 1)], &[Container::new("low", 100, 30, 20, 50).unwrap()]);
+        assert_eq!(plan.placements()[0].extent(), (100, 10, 20));
+    }
+
+    #[test]
+    fn containers_are_filled_in_order() {
+        let plan = load(&[item(100, 100, 60, 5), item(100, 100, 60, 5)], &[cage(), cage()]);
+        let containers: Vec<_> = plan.placements().iter().map(|placement| placement.container()).collect();
+        assert_eq!(containers, vec![0, 1]);
+    }
+
+    #[test]
+    fn weight_limits_are_respected() {
+        let plan = load(&[item(10, 10, 10, 30), item(10, 10, 10, 30)], &[cage()]);
+        assert_eq!(plan.placements().len(), 1);
+        assert_eq!(plan.unplaceable(), &[1]);
+        assert_eq!(plan.utilisation()[0].mass_ratio(), 0.6);
+    }
+
+    #[test]
+    fn packages_larger_than_any_container_are_unplaceable() {
+        let plan = load(&[item(10, 10, 10, 1), item(150, 10, 10, 1)], &[cage()]);
+        assert_eq!(plan.unplaceable(), &[1]);
+        assert_eq!(plan.placements()[0].item(), 0);
+
+        assert_eq!(load(&[item(10, 10, 10, 1)], &[]).unplaceable(), &[0]);
+    }
+
+    #[test]
+    fn invalid_containers_are_refused() {
+        assert_eq!(Container::new("cage", 0, 100, 100, 50), Err(ContainerError::InvalidDimension("cage".to_string(), 0)));
+        assert_eq!(Container::new("cage", 100, 100, 100, 0), Err(ContainerError::InvalidWeightLimit("cage".to_string(), 0)));
+    }
+
+    #[test]
+    fn display_utilisation_and_errors() {
+        let plan = load(&[item(50, 100, 100, 10)], &[cage()]);
+        assert_eq!(plan.utilisation()[0].to_string(), "cage: 50% of volume, 20% of weight limit");
+        assert_eq!(
+            ContainerError::InvalidDimension("cage".to_string(), 0).to_string(),
+            "Invalid dimension for container cage: expecting a value of 1 or more, but got 0"
+        );
+        assert_eq!(
+            ContainerError::InvalidWeightLimit("cage".to_string(), 0).to_string(),
+            "Invalid weight limit for container cage: expecting a value of 1 or more, but got 0"
+        );
+    }
+
+    // rasterises the placements of every container on a grid made of their
+    // own offsets, so that no cell may be taken by more than one package
+    #[quickcheck]
+    fn placements_never_overlap(stack: Stack) -> bool {
+        let plan = load(&stack.items, &stack.containers);
+
+        (0..stack.containers.len()).all(|container| {
+            let boxes: Vec<[(usize, usize); 3]> = plan.placements().iter()
+                .filter(|placement| placement.container() == container)
+                .map(|placement| {
+                    let ((x, y, z), (w, h, l)) = (placement.position(), placement.extent());
+                    [(x, x + w), (y, y + h), (z, z + l)]
+                })
+                .collect();
+            let grid: Vec<Vec<usize>> = (0..3)
+                .map(|axis| {
+                    let mut offsets: Vec<usize> = boxes.iter().flat_map(|bounds| [bounds[axis].0, bounds[axis].1]).collect();
+                    offsets.sort();
+                    offsets.dedup();
+                    offsets
+                })
+                .collect();
+            let cells = |axis: usize, (from, to): (usize, usize)| {
+                grid[axis].iter().position(|offset| *offset == from).unwrap()..grid[axis].iter().position(|offset| *offset == to).unwrap()
+            };
+
+            let mut taken = vec![false; grid.iter().map(Vec::len).product()];
+            boxes.iter().all(|bounds| {
+                cells(0, bounds[0]).all(|i| cells(1, bounds[1]).all(|j| cells(2, bounds[2]).all(|k| {
+                    let cell = (i * grid[1].len() + j) * grid[2].len() + k;
+                    !std::mem::replace(&mut taken[cell], true)
+                })))
+            })
+        })
+    }
+
+    #[quickcheck]
+    fn packages_stay_within_their_container(stack: Stack) -> bool {
+        let plan = load(&stack.items, &stack.containers);
+
+        plan.placements().iter().all(|placement| {
+            let (width, height, length) = stack.containers[placement.container()].extent();
+            let ((x, y, z), (w, h, l)) = (placement.position(), placement.extent());
+            x + w <= width && y + h <= height && z + l <= length
+        }) && plan.utilisation().iter().zip(&stack.containers)
+            .all(|(utilisation, container)| utilisation.used_mass() <= container.max_mass.value())
+    }
+
+    #[quickcheck]
+    fn every_package_is_placed_or_reported_once(stack: Stack) -> bool {
+        let plan = load(&stack.items, &stack.containers);
+        let mut items: Vec<usize> = plan.placements().iter().map(|placement| placement.item()).chain(plan.unplaceable().iter().copied()).collect();
+        items.sort();
+        items == (0..stack.items.len()).collect::<Vec<_>>()
+    }
+
+    #[derive(Clone, Debug)]
+    struct Stack {
+        items: Vec<(PackageDimensions, Mass)>,
+        containers: Vec<Container>
+    }
+
+    impl Arbitrary for Stack {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let items = (0..usize::arbitrary(g) % 40)
+                .map(|_| ((*ParcelDimensions::arbitrary(g)).clone(), Mass::new(Kg::new(usize::arbitrary(g) % 30 + 1).unwrap())))
+                .collect();
+            let containers = (0..usize::arbitrary(g) % 3 + 1)
+                .map(|index| {
+                    let mut side = || usize::arbitrary(g) % 300 + 1;
+                    Container::new(format!("cage-{}", index), side(), side(), side(), usize::arbitrary(g) % 500 + 1).unwrap()
+                })
+                .collect();
+            Stack { items, containers }
+        }
+    }
+}
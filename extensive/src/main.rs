@@ -0,0 +1,578 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, SecondsFormat, Utc};
+use otta::{
+    analyse_impact, replay, Attribute, Dataset, DecisionLog, Destination, LogEntry, Metrics, PackageInput, PolicyHolder, PolicySchedule, ServiceLevel,
+    SortPolicy
+};
+
+const USAGE: &str = "Usage:
+  otta policy check <file>    validates a TOML, YAML or JSON policy file,
+                              listing its versions when it holds several
+  otta serve --policy <file> [--metrics <address>] [--log <log>]
+                              sorts packages read from stdin, one per line as
+                              `width height length mass [attribute...]`,
+                              optionally followed by `country=<code>`,
+                              `postcode=<code>` and `service_level=<level>`,
+                              with the version of the policy file in effect,
+                              reloading the file when it changes, exposing
+                              Prometheus metrics on http://<address>/metrics
+                              and appending every decision to a decision log
+  otta replay <log> --policy <file>
+                              reports the packages of a decision log that
+                              would change stack under the policy file
+  otta impact <dataset> --from <file> --to <file>
+                              reports how a CSV or NDJSON dataset of packages
+                              moves between stacks when the first policy file
+                              is replaced by the second";
+
+// how often a served policy file is checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+// how long a client of the metrics endpoint may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * Command
+ *
+ * The commands supported by the command line interface.
+ */
+#[derive(Debug, PartialEq)]
+enum Command {
+    PolicyCheck(String),
+    Serve { policy: String, metrics: Option<String>, log: Option<String> },
+    Replay(String, String),
+    Impact(String, String, String)
+}
+
+impl Command {
+    fn parse(args: &[String]) -> Result<Command, String> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            ["policy", "check", file] => Ok(Command::PolicyCheck(file.to_string())),
+            ["serve", "--policy", file, options @ ..] => {
+                let (mut metrics, mut log) = (None, None);
+                for option in options.chunks(2) {
+                    match option {
+                        ["--metrics", address] if metrics.is_none() => metrics = Some(address.to_string()),
+                        ["--log", file] if log.is_none() => log = Some(file.to_string()),
+                        _ => return Err(USAGE.to_string())
+                    }
+                }
+                Ok(Command::Serve { policy: file.to_string(), metrics, log })
+            },
+            ["replay", log, "--policy", file] => Ok(Command::Replay(log.to_string(), file.to_string())),
+            ["impact", dataset, "--from", current, "--to", proposed] => Ok(Command::Impact(dataset.to_string(), current.to_string(), proposed.to_string())),
+            _ => Err(USAGE.to_string())
+        }
+    }
+
+    fn run(&self) -> Result<String, String> {
+        match self {
+            Command::PolicyCheck(file) => PolicySchedule::from_any_file(file)
+                .map(|schedule| match schedule.versions() {
+                    [(effective_from, _)] if *effective_from == DateTime::<Utc>::MIN_UTC => format!("{}: valid policy", file),
+                    versions => versions.iter().fold(format!("{}: valid policy with {} versions", file, versions.len()), |listed, (effective_from, policy)| {
+                        format!("{}\n{} from {}", listed, policy.version(), effective_from.to_rfc3339_opts(SecondsFormat::Secs, true))
+                    })
+                })
+                .map_err(|e| format!("{}: {}", file, e)),
+            Command::Serve { policy: file, metrics: address, log } => {
+                let holder = Arc::new(PolicyHolder::load(file).map_err(|e| format!("{}: {}", file, e))?);
+                let mut log = log.as_ref().map(DecisionLog::open).transpose().map_err(|e| e.to_string())?;
+                let _watcher = holder.watch(RELOAD_INTERVAL, |event| eprintln!("{}", event));
+                let metrics = Arc::new(Metrics::new());
+                if let Some(address) = address {
+                    let listener = TcpListener::bind(address).map_err(|e| format!("{}: {}", address, e))?;
+                    let exposed = metrics.clone();
+                    std::thread::spawn(move || expose(listener, &exposed));
+                }
+                serve(&holder, &metrics, log.as_mut(), std::io::stdin().lock(), std::io::stdout().lock())
+                    .map(|_| String::new())
+                    .map_err(|e| e.to_string())
+            },
+            Command::Replay(log, file) => {
+                let policy = SortPolicy::from_file(file).map_err(|e| format!("{}: {}", file, e))?;
+                let entries = DecisionLog::read(log).map_err(|e| e.to_string())?;
+                replay(&entries, &policy)
+                    .map(|report| report.to_string())
+                    .map_err(|e| format!("{}: {}", log, e))
+            },
+            Command::Impact(dataset, current, proposed) => {
+                let current = SortPolicy::from_file(current).map_err(|e| format!("{}: {}", current, e))?;
+                let proposed = SortPolicy::from_file(proposed).map_err(|e| format!("{}: {}", proposed, e))?;
+                let packages = Dataset::from_file(dataset).map_err(|e| e.to_string())?;
+                analyse_impact(&packages, &current, &proposed)
+                    .map(|analysis| analysis.to_string())
+                    .map_err(|e| format!("{}: {}", dataset, e))
+            }
+        }
+    }
+}
+
+/**
+ * Sorts every package read from the input with the version of the
+ * current policy in effect, writing one decision per line to the output
+ *
+ * Every decision is appended to the log, if any, before it is written,
+ * packages being named after their line. Serving stops when the log
+ * can't be written, as the decision would go unrecorded.
+ */
+fn serve<R: BufRead, W: Write>(holder: &PolicyHolder, metrics: &Metrics, mut log: Option<&mut DecisionLog>, input: R, mut output: W) -> std::io::Result<()> {
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match parse_package(&line) {
+            Ok(package) => {
+                let schedule = holder.current();
+                let (started, at) = (Instant::now(), Utc::now());
+                let sorted = schedule.sort_at(&package, at);
+                metrics.observe(&package, &sorted, started.elapsed());
+                if let (Some(log), Ok(decision)) = (log.as_deref_mut(), &sorted) {
+                    log.append(&LogEntry::new(format!("line {}", index + 1), at, package, decision))
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                }
+                match sorted {
+                    Ok(decision) => match decision.routing_key() {
+                        Some(routing_key) => format!("{} [{}] (policy {})", decision, routing_key, decision.policy_version()),
+                        None => format!("{} (policy {})", decision, decision.policy_version())
+                    },
+                    Err(error) => format!("error: {}", error)
+                }
+            },
+            Err(error) => format!("error: {}", error)
+        };
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/**
+ * Answers every request for `/metrics` with the metrics in the
+ * Prometheus text format, until the listener fails. Every connection
+ * is answered on its own thread, so that a slow client doesn't hold up
+ * the others.
+ */
+fn expose(listener: TcpListener, metrics: &Arc<Metrics>) {
+    for stream in listener.incoming().flatten() {
+        let metrics = metrics.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = respond(stream, &metrics) {
+                eprintln!("metrics: {}", error);
+            }
+        });
+    }
+}
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = BufReader::new(&stream);
+    let mut request_line = String::new();
+    request.read_line(&mut request_line)?;
+    // the headers are read, but ignored
+    let mut header = String::new();
+    while request.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>().as_slice() {
+        ["GET", "/metrics"] => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not found: expecting GET /metrics\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn parse_package(line: &str) -> Result<PackageInput, String> {
+    let mut fields = line.split_whitespace();
+    let mut measurement = |name: &str| -> Result<usize, String> {
+        let field = fields.next().ok_or_else(|| format!("Missing {}: expecting `width height length mass [attribute...]`", name))?;
+        field.parse().map_err(|_| format!("Invalid {}: expecting a whole number, but got {}", name, field))
+    };
+    let (width, height, length, mass) = (measurement("width")?, measurement("height")?, measurement("length")?, measurement("mass")?);
+
+    let (mut package, mut country, mut postcode) = (PackageInput::new(width, height, length, mass), None, None);
+    for field in fields {
+        match field.split_once('=') {
+            Some(("country", code)) => country = Some(code),
+            Some(("postcode", code)) => postcode = Some(code),
+            Some(("service_level", level)) => package = package.with_service_level(level.parse::<ServiceLevel>().map_err(|e| e.to_string())?),
+            Some((name, _)) => return Err(format!("Unknown field: expecting country, postcode or service_level, but got '{}'", name)),
+            None => package = package.with_attribute(field.parse::<Attribute>().map_err(|e| e.to_string())?)
+        }
+    }
+
+    match (country, postcode) {
+        (Some(country), postcode) => {
+            let mut destination = Destination::new(country).map_err(|e| e.to_string())?;
+            if let Some(postcode) = postcode {
+                destination = destination.with_postcode(postcode).map_err(|e| e.to_string())?;
+            }
+            Ok(package.with_destination(destination))
+        },
+        (None, Some(_)) => Err("Missing country: expecting a country along with the postcode".to_string()),
+        (None, None) => Ok(package)
+    }
+}
+
+fn main() -> ExitCode {
+    // spans are written as JSON to stderr, filtered by the OTTA_LOG variable
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .json()
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_env_filter(tracing_subscriber::EnvFilter::from_env("OTTA_LOG"))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = match Command::parse(&args) {
+        Ok(command) => command,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            return ExitCode::from(2);
+        }
+    };
+
+    match command.run() {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            ExitCode::SUCCESS
+        },
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // a file in the temporary directory, deleted once dropped
+    struct ScratchFile(std::path::PathBuf);
+
+    impl AsRef<std::path::Path> for ScratchFile {
+        fn as_ref(&self) -> &std::path::Path {
+            &self.0
+        }
+    }
+
+    impl std::fmt::Display for ScratchFile {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}", self.0.display())
+        }
+    }
+
+    impl Drop for ScratchFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn scratch_file(name: &str) -> ScratchFile {
+        let path = std::env::temp_dir().join(format!("otta-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        ScratchFile(path)
+    }
+
+    fn policy_file(name: &str, contents: &str) -> ScratchFile {
+        let file = scratch_file(name);
+        std::fs::write(&file, contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn policy_check_is_parsed() {
+        assert_eq!(Command::parse(&args(&["policy", "check", "policy.toml"])), Ok(Command::PolicyCheck("policy.toml".to_string())));
+    }
+
+    #[test]
+    fn serve_is_parsed() {
+        let served = |metrics: Option<&str>, log: Option<&str>| Ok(Command::Serve {
+            policy: "policy.toml".to_string(),
+            metrics: metrics.map(str::to_string),
+            log: log.map(str::to_string)
+        });
+        assert_eq!(Command::parse(&args(&["serve", "--policy", "policy.toml"])), served(None, None));
+        assert_eq!(Command::parse(&args(&["serve", "--policy", "policy.toml", "--metrics", "0.0.0.0:9090"])), served(Some("0.0.0.0:9090"), None));
+        assert_eq!(
+            Command::parse(&args(&["serve", "--policy", "policy.toml", "--log", "decisions.log", "--metrics", "0.0.0.0:9090"])),
+            served(Some("0.0.0.0:9090"), Some("decisions.log"))
+        );
+        assert_eq!(Command::parse(&args(&["serve", "--policy", "policy.toml", "--log"])), Err(USAGE.to_string()));
+        assert_eq!(Command::parse(&args(&["serve", "--policy", "policy.toml", "--log", "a.log", "--log", "b.log"])), Err(USAGE.to_string()));
+    }
+
+    #[test]
+    fn served_packages_are_sorted_with_the_current_policy() {
+        let file = policy_file("served.toml", "version = \"v1\"\n\n[thresholds]\nbulky_cm = 150\nheavy_kg = 18\n");
+        let holder = PolicyHolder::load(&file).unwrap();
+        let input = "10 10 10 1\n\n10 10 10 18 fragile\n10 0 10 1\n10 ten 10 1\n10 10\n10 10 10 1 irregular\n";
+        let mut output = Vec::new();
+
+        serve(&holder, &Metrics::new(), None, input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "\
+standard (policy v1)
+special (policy v1)
+error: Invalid height: expecting a value of 1 or more, but got 0
+error: Invalid height: expecting a whole number, but got ten
+error: Missing length: expecting `width height length mass [attribute...]`
+error: Unknown attribute: expecting one of fragile, hazmat, liquid, temperature_controlled, lithium_batteries, but got 'irregular'
+");
+    }
+
+    #[test]
+    fn served_packages_are_sorted_with_the_version_in_effect() {
+        let file = policy_file("served-versions.toml", VERSIONS);
+        let holder = PolicyHolder::load(&file).unwrap();
+        let mut output = Vec::new();
+
+        serve(&holder, &Metrics::new(), None, "10 10 10 19\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "special (policy 2026)\n");
+    }
+
+    #[test]
+    fn served_packages_can_have_a_destination_and_a_service_level() {
+        let file = policy_file("destined.toml", "\
+[[rules.rule]]
+name = \"express to london\"
+result = \"special\"
+routing_key = \"london-express\"
+when = { destination_country = \"GB\", postcode_prefix = \"EC\", service_level = \"express\" }
+
+[[rules.rule]]
+name = \"standard\"
+result = \"standard\"
+");
+        let holder = PolicyHolder::load(&file).unwrap();
+        let input = "\
+10 10 10 1 fragile country=GB postcode=EC1A1BB service_level=express
+10 10 10 1 country=gb postcode=ec1a1bb
+10 10 10 1 country=GBR
+10 10 10 1 postcode=EC1A1BB
+10 10 10 1 service_level=overnight
+10 10 10 1 carrier=acme
+";
+        let mut output = Vec::new();
+
+        serve(&holder, &Metrics::new(), None, input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "\
+special [london-express] (policy default)
+standard (policy default)
+error: Invalid country: expecting a two-letter code, but got 'GBR'
+error: Missing country: expecting a country along with the postcode
+error: Unknown service level: expecting economy, standard or express, but got 'overnight'
+error: Unknown field: expecting country, postcode or service_level, but got 'carrier'
+");
+    }
+
+    #[test]
+    fn served_decisions_carry_their_routing_key() {
+        let file = policy_file("routed.toml", "[[rules.rule]]\nname = \"fragile\"\nresult = \"special\"\nrouting_key = \"by-hand\"\nwhen = { attributes = [\"fragile\"] }\n\n[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\n");
+        let holder = PolicyHolder::load(&file).unwrap();
+        let mut output = Vec::new();
+
+        serve(&holder, &Metrics::new(), None, "10 10 10 1 fragile\n10 10 10 1\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "special [by-hand] (policy default)\nstandard (policy default)\n");
+    }
+
+    #[test]
+    fn served_decisions_are_logged_for_replays() {
+        let file = policy_file("logged.toml", "version = \"v1\"\n");
+        let log = scratch_file("served.log");
+        let holder = PolicyHolder::load(&file).unwrap();
+        let mut decisions = DecisionLog::open(&log).unwrap();
+
+        serve(&holder, &Metrics::new(), Some(&mut decisions), "10 10 10 19\n10 0 10 1\n\n10 10 10 1\n".as_bytes(), &mut Vec::new()).unwrap();
+
+        let entries = DecisionLog::read(&log).unwrap();
+        assert_eq!(entries.iter().map(LogEntry::package).collect::<Vec<_>>(), vec!["line 1", "line 4"]);
+        assert_eq!(entries[0].input(), &PackageInput::new(10, 10, 10, 19));
+        assert_eq!(entries[0].policy_version(), "v1");
+        let proposed = policy_file("logged-proposed.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 18\n");
+        assert_eq!(
+            Command::Replay(log.to_string(), proposed.to_string()).run(),
+            Ok("1 of 2 packages would change stack\nline 1: standard -> special".to_string())
+        );
+    }
+
+    fn scrape(address: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+        response
+    }
+
+    fn exposed(metrics: &Arc<Metrics>) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let metrics = metrics.clone();
+        std::thread::spawn(move || expose(listener, &metrics));
+        address
+    }
+
+    #[test]
+    fn served_packages_are_scraped_as_metrics() {
+        let file = policy_file("scraped.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 20\n");
+        let holder = PolicyHolder::load(&file).unwrap();
+        let metrics = Arc::new(Metrics::new());
+        let address = exposed(&metrics);
+
+        serve(&holder, &metrics, None, "10 10 10 1\n10 10 10 25\n10 10 10 2\n10 0 10 1\n".as_bytes(), &mut Vec::new()).unwrap();
+        let response = scrape(address, "/metrics");
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n"), "{}", response);
+        for line in [
+            "otta_packages_sorted_total{result=\"standard\"} 2",
+            "otta_packages_sorted_total{result=\"special\"} 1",
+            "otta_sort_errors_total{error=\"invalid_height\"} 1",
+            "otta_package_mass_kg_count 3",
+            "otta_sort_latency_seconds_count 3"
+        ] {
+            assert!(response.lines().any(|scraped| scraped == line), "missing {} in {}", line, response);
+        }
+    }
+
+    #[test]
+    fn idle_clients_do_not_hold_up_scrapes() {
+        let address = exposed(&Arc::new(Metrics::new()));
+        let _idle = TcpStream::connect(address).unwrap();
+        assert!(scrape(address, "/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn only_metrics_are_exposed() {
+        let address = exposed(&Arc::new(Metrics::new()));
+        assert!(scrape(address, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[test]
+    fn replay_is_parsed() {
+        assert_eq!(
+            Command::parse(&args(&["replay", "decisions.log", "--policy", "policy.toml"])),
+            Ok(Command::Replay("decisions.log".to_string(), "policy.toml".to_string()))
+        );
+    }
+
+    #[test]
+    fn replays_report_the_packages_changing_stack() {
+        let log = scratch_file("replayed.log");
+        let mut decisions = DecisionLog::open(&log).unwrap();
+        for (package, mass) in [("PKG-1", 19), ("PKG-2", 1)] {
+            let input = PackageInput::new(10, 10, 10, mass);
+            let decision = otta::sort_package(&input, &SortPolicy::default()).unwrap();
+            decisions.append(&otta::LogEntry::new(package, "2026-10-19T08:00:00Z".parse().unwrap(), input, &decision)).unwrap();
+        }
+        let file = policy_file("replayed.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 18\n");
+
+        assert_eq!(
+            Command::Replay(log.to_string(), file.to_string()).run(),
+            Ok("1 of 2 packages would change stack\nPKG-1: standard -> special".to_string())
+        );
+    }
+
+    #[test]
+    fn impact_is_parsed() {
+        assert_eq!(
+            Command::parse(&args(&["impact", "week-42.csv", "--from", "current.toml", "--to", "proposed.toml"])),
+            Ok(Command::Impact("week-42.csv".to_string(), "current.toml".to_string(), "proposed.toml".to_string()))
+        );
+    }
+
+    #[test]
+    fn impacts_report_the_transitions_between_stacks() {
+        let dataset = policy_file("impact.csv", "id,width,height,length,mass
+PKG-1,10,10,10,19
+PKG-2,10,10,10,1
+");
+        let current = policy_file("current.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 20\n");
+        let proposed = policy_file("proposed.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 18\n");
+
+        assert_eq!(Command::Impact(dataset.to_string(), current.to_string(), proposed.to_string()).run(), Ok("\
+2 packages, 1 changed stack (50.0%)
+
+before \\ after  standard  special
+standard               1        1
+special                0        0
+
+standard -> standard: 1 (50.0%), e.g. PKG-2
+standard -> special: 1 (50.0%), e.g. PKG-1".to_string()));
+    }
+
+    #[test]
+    fn unknown_commands_print_the_usage() {
+        assert_eq!(Command::parse(&args(&["policy"])), Err(USAGE.to_string()));
+        assert_eq!(Command::parse(&args(&[])), Err(USAGE.to_string()));
+    }
+
+    #[test]
+    fn a_valid_policy_passes_the_check() {
+        let file = policy_file("valid.toml", "[thresholds]\nbulky_cm = 150\nheavy_kg = 18\n");
+        assert_eq!(Command::PolicyCheck(file.to_string()).run(), Ok(format!("{}: valid policy", file)));
+    }
+
+    // the version from 2999 isn't in effect yet
+    const VERSIONS: &str = "\
+[[versions]]
+version = \"2025\"
+effective_from = 2025-01-01T00:00:00Z
+
+[[versions]]
+version = \"2026\"
+effective_from = 2026-01-01T00:00:00Z
+
+[versions.thresholds]
+bulky_cm = 150
+heavy_kg = 18
+
+[[versions]]
+version = \"2999\"
+effective_from = 2999-01-01T00:00:00Z
+";
+
+    #[test]
+    fn the_check_lists_the_versions_of_a_policy() {
+        let file = policy_file("versions.toml", VERSIONS);
+        assert_eq!(Command::PolicyCheck(file.to_string()).run(), Ok(format!("\
+{}: valid policy with 3 versions
+2025 from 2025-01-01T00:00:00Z
+2026 from 2026-01-01T00:00:00Z
+2999 from 2999-01-01T00:00:00Z", file)));
+    }
+
+    #[test]
+    fn an_invalid_policy_fails_the_check() {
+        let file = policy_file("invalid.json", "{ \"thresholds\": { \"bulky_cm\": 0, \"heavy_kg\": 20 } }");
+        assert_eq!(
+            Command::PolicyCheck(file.to_string()).run(),
+            Err(format!("{}: Invalid policy at line 1, column 31: Invalid bulky threshold: expecting a value of 1 or more, but got 0", file))
+        );
+    }
+}
@@ -16,14 +16,14 @@ pub(crate) enum CmError {
 impl CmError {
     pub(crate) fn original_value(&self) -> usize {
         match self {
-            CmError::InvalidCm(value) => value.clone()
+            CmError::InvalidCm(value) => *value
         }
     }
 }
 
 impl Cm {
     fn is_valid(value: usize) -> bool {
-        value >= MIN && value <= MAX
+        (MIN..=MAX).contains(&value)
     }
 
     pub(crate) fn new(value: usize) -> Result<Self, CmError> {
@@ -39,6 +39,10 @@ impl Cm {
             }
         )
     }
+
+    pub(crate) fn value(&self) -> usize {
+        self.value
+    }
 }
 
 impl std::ops::Add for Cm {
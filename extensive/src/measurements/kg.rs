@@ -26,4 +26,8 @@ impl Kg {
             }
         )
     }
+
+    pub(crate) fn value(&self) -> usize {
+        self.value
+    }
 }
\ No newline at end of file
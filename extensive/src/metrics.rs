@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::package_input::PackageInput;
+use crate::sort_decision::SortDecision;
+use crate::sort_error::SortError;
+use crate::sort_result::SortResult;
+
+// the upper bounds of the buckets of every histogram
+const SUMMED_DIMENSIONS_CM: [f64; 8] = [25.0, 50.0, 100.0, 150.0, 200.0, 300.0, 450.0, 600.0];
+const MASS_KG: [f64; 7] = [1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 50.0];
+const LATENCY_SECONDS: [f64; 6] = [0.000_01, 0.000_1, 0.001, 0.01, 0.1, 1.0];
+
+/**
+ * Metrics
+ *
+ * Counts what a long-running sorter decided and how long it took, to be
+ * scraped by Prometheus. Metrics can be shared between threads, as
+ * every observation is recorded under a lock.
+ */
+pub struct Metrics {
+    recorded: Mutex<Recorded>
+}
+
+struct Recorded {
+    results: BTreeMap<String, u64>,
+    remeasured: u64,
+    errors: BTreeMap<&'static str, u64>,
+    summed_dimensions: Histogram,
+    mass: Histogram,
+    latency: Histogram
+}
+
+// a cumulative histogram, as Prometheus expects it
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let results = [SortResult::Standard, SortResult::Special, SortResult::Rejected].iter()
+            .map(|result| (result.to_string(), 0))
+            .collect();
+
+        Metrics {
+            recorded: Mutex::new(Recorded {
+                results,
+                remeasured: 0,
+                errors: BTreeMap::new(),
+                summed_dimensions: Histogram::new(&SUMMED_DIMENSIONS_CM),
+                mass: Histogram::new(&MASS_KG),
+                latency: Histogram::new(&LATENCY_SECONDS)
+            })
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    /**
+     * Records how a package was sorted, how long sorting it took and,
+     * once it was sent onto a stack, its measurements. Uncertain packages
+     * count towards the stack they are sent to. Invalid and implausible
+     * measurements are left out of the histograms, so that they don't
+     * distort them.
+     */
+    pub fn observe(&self, input: &PackageInput, sorted: &Result<SortDecision, SortError>, latency: Duration) {
+        let mut recorded = self.recorded.lock().unwrap();
+        let decision = match sorted {
+            Ok(decision) => decision,
+            Err(error) => {
+                *recorded.errors.entry(error.kind()).or_insert(0) += 1;
+                return;
+            }
+        };
+        recorded.latency.observe(latency.as_secs_f64());
+
+        match decision.outcome().stack() {
+            Some(result) => {
+                *recorded.results.entry(result.to_string()).or_insert(0) += 1;
+                let summed = input.width().saturating_add(input.height()).saturating_add(input.length());
+                recorded.summed_dimensions.observe(summed as f64);
+                recorded.mass.observe(input.mass() as f64);
+            },
+            None => recorded.remeasured += 1
+        }
+    }
+
+    /**
+     * Renders every metric in the Prometheus text exposition format
+     */
+    pub fn render(&self) -> String {
+        let recorded = self.recorded.lock().unwrap();
+        let mut text = String::new();
+
+        header(&mut text, "otta_packages_sorted_total", "Packages sent onto a stack, per stack.", "counter");
+        for (result, count) in &recorded.results {
+            writeln!(text, "otta_packages_sorted_total{{result=\"{}\"}} {}", result, count).unwrap();
+        }
+        header(&mut text, "otta_packages_remeasured_total", "Packages that must be remeasured.", "counter");
+        writeln!(text, "otta_packages_remeasured_total {}", recorded.remeasured).unwrap();
+        header(&mut text, "otta_sort_errors_total", "Packages that could not be sorted, per kind of error.", "counter");
+        for (error, count) in &recorded.errors {
+            writeln!(text, "otta_sort_errors_total{{error=\"{}\"}} {}", error, count).unwrap();
+        }
+
+        recorded.summed_dimensions.render(&mut text, "otta_package_summed_dimensions_cm", "The width, height and length of a package added up.");
+        recorded.mass.render(&mut text, "otta_package_mass_kg", "The mass of a package.");
+        recorded.latency.render(&mut text, "otta_sort_latency_seconds", "The time it took to sort a package.");
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(text, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind).unwrap();
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram { bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, text: &mut String, name: &str, help: &str) {
+        header(text, name, help, "histogram");
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, count).unwrap();
+        }
+        writeln!(text, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count).unwrap();
+        writeln!(text, "{}_sum {}", name, self.sum).unwrap();
+        writeln!(text, "{}_count {}", name, self.count).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort_policy::plausibility::PlausibilityLimits;
+    use crate::sort_policy::SortPolicy;
+
+    fn observe(metrics: &Metrics, input: PackageInput, latency_ms: u64) {
+        let sorted = crate::sort_package(&input, &SortPolicy::default());
+        metrics.observe(&input, &sorted, Duration::from_millis(latency_ms));
+    }
+
+    fn lines(metrics: &Metrics, prefix: &str) -> Vec<String> {
+        metrics.render().lines().filter(|line| line.starts_with(prefix)).map(str::to_string).collect()
+    }
+
+    #[test]
+    fn results_are_counted_per_stack() {
+        let metrics = Metrics::new();
+        observe(&metrics, PackageInput::new(10, 10, 10, 1), 1);
+        observe(&metrics, PackageInput::new(10, 10, 10, 1), 1);
+        observe(&metrics, PackageInput::new(10, 10, 10, 25), 1);
+
+        assert_eq!(lines(&metrics, "otta_packages_sorted_total{"), vec![
+            "otta_packages_sorted_total{result=\"rejected\"} 0",
+            "otta_packages_sorted_total{result=\"special\"} 1",
+            "otta_packages_sorted_total{result=\"standard\"} 2"
+        ]);
+    }
+
+    #[test]
+    fn errors_are_counted_per_kind() {
+        let metrics = Metrics::new();
+        observe(&metrics, PackageInput::new(10, 0, 10, 1), 1);
+        observe(&metrics, PackageInput::new(10, 10, 10, 0), 1);
+        observe(&metrics, PackageInput::new(10, 10, 10, 0), 1);
+
+        assert_eq!(lines(&metrics, "otta_sort_errors_total{"), vec![
+            "otta_sort_errors_total{error=\"invalid_height\"} 1",
+            "otta_sort_errors_total{error=\"invalid_mass\"} 2"
+        ]);
+        assert_eq!(lines(&metrics, "otta_packages_sorted_total{result=\"standard\"}"), vec!["otta_packages_sorted_total{result=\"standard\"} 0"]);
+    }
+
+    #[test]
+    fn invalid_and_implausible_measurements_are_left_out_of_the_histograms() {
+        let metrics = Metrics::new();
+        let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default().with_max_side(250));
+        observe(&metrics, PackageInput::new(10, 0, 10, 1), 1);
+        let implausible = PackageInput::new(10, 10, 1_000, 1);
+        metrics.observe(&implausible, &crate::sort_package(&implausible, &policy), Duration::from_millis(1));
+
+        assert_eq!(lines(&metrics, "otta_packages_remeasured_total "), vec!["otta_packages_remeasured_total 1"]);
+        assert_eq!(lines(&metrics, "otta_package_summed_dimensions_cm_count"), vec!["otta_package_summed_dimensions_cm_count 0"]);
+        assert_eq!(lines(&metrics, "otta_package_mass_kg_count"), vec!["otta_package_mass_kg_count 0"]);
+        assert_eq!(lines(&metrics, "otta_sort_latency_seconds_count"), vec!["otta_sort_latency_seconds_count 1"]);
+    }
+
+    #[test]
+    fn histograms_are_cumulative() {
+        let metrics = Metrics::new();
+        observe(&metrics, PackageInput::new(10, 10, 10, 1), 2);
+        observe(&metrics, PackageInput::new(100, 40, 30, 25), 20);
+
+        assert_eq!(lines(&metrics, "otta_package_summed_dimensions_cm"), vec![
+            "otta_package_summed_dimensions_cm_bucket{le=\"25\"} 0",
+            "otta_package_summed_dimensions_cm_bucket{le=\"50\"} 1",
+            "otta_package_summed_dimensions_cm_bucket{le=\"100\"} 1",
+            "otta_package_summed_dimensions_cm_bucket{le=\"150\"} 1",
+            "otta_package_summed_dimensions_cm_bucket{le=\"200\"} 2",
+            "otta_package_summed_dimensions_cm_bucket{le=\"300\"} 2",
+            "otta_package_summed_dimensions_cm_bucket{le=\"450\"} 2",
+            "otta_package_summed_dimensions_cm_bucket{le=\"600\"} 2",
+            "otta_package_summed_dimensions_cm_bucket{le=\"+Inf\"} 2",
+            "otta_package_summed_dimensions_cm_sum 200",
+            "otta_package_summed_dimensions_cm_count 2"
+        ]);
+        assert_eq!(lines(&metrics, "otta_package_mass_kg_sum"), vec!["otta_package_mass_kg_sum 26"]);
+        assert_eq!(lines(&metrics, "otta_sort_latency_seconds_bucket{le=\"0.01\"}"), vec!["otta_sort_latency_seconds_bucket{le=\"0.01\"} 1"]);
+        assert_eq!(lines(&metrics, "otta_sort_latency_seconds_count"), vec!["otta_sort_latency_seconds_count 2"]);
+    }
+
+    #[test]
+    fn summed_dimensions_saturate() {
+        let metrics = Metrics::new();
+        observe(&metrics, PackageInput::new(usize::MAX, usize::MAX, usize::MAX, 1), 1);
+        assert_eq!(lines(&metrics, "otta_package_summed_dimensions_cm_bucket{le=\"+Inf\"}"), vec!["otta_package_summed_dimensions_cm_bucket{le=\"+Inf\"} 1"]);
+    }
+
+    #[test]
+    fn every_metric_is_documented() {
+        let rendered = Metrics::new().render();
+        for (name, kind) in [
+            ("otta_packages_sorted_total", "counter"),
+            ("otta_packages_remeasured_total", "counter"),
+            ("otta_sort_errors_total", "counter"),
+            ("otta_package_summed_dimensions_cm", "histogram"),
+            ("otta_package_mass_kg", "histogram"),
+            ("otta_sort_latency_seconds", "histogram")
+        ] {
+            assert!(rendered.contains(&format!("# HELP {} ", name)), "{} has no help", name);
+            assert!(rendered.contains(&format!("# TYPE {} {}\n", name, kind)), "{} is no {}", name, kind);
+        }
+    }
+}
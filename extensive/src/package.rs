@@ -1,13 +1,29 @@
+use std::collections::BTreeSet;
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::service_level::ServiceLevel;
 use crate::package::dimensions::DimensionClass;
 use crate::package::mass::MassClass;
+use crate::sort_decision::{SortDecision, SortOutcome, TraceStep};
+use crate::sort_policy::rules::Facts;
+use crate::sort_policy::SortPolicy;
 use crate::sort_result::SortResult;
 
+pub(crate) mod attributes;
+pub(crate) mod destination;
+pub(crate) mod dimensional_weight;
 pub(crate) mod dimensions;
+pub(crate) mod fusion;
 pub(crate) mod mass;
+pub(crate) mod service_level;
+pub(crate) mod sorted_dimensions;
 
 pub(crate) struct Package {
     dimensions: dimensions::PackageDimensions,
-    mass: mass::Mass
+    mass: mass::Mass,
+    attributes: BTreeSet<Attribute>,
+    destination: Option<Destination>,
+    service_level: Option<ServiceLevel>
 }
 
 impl Package {
@@ -15,25 +31,116 @@ impl Package {
     pub(crate) fn new(dimensions: dimensions::PackageDimensions, mass: mass::Mass) -> Self {
         Package {
             dimensions,
-            mass
+            mass,
+            attributes: BTreeSet::new(),
+            destination: None,
+            service_level: None
         }
     }
 
-    pub(crate) fn sort(&self) -> SortResult {
-        match (self.dimensions.classify(), self.mass.classify()) {
-            (DimensionClass::BULKY, MassClass::HEAVY) => SortResult::Rejected,
-            (DimensionClass::BULKY, _) => SortResult::Special,
-            (_, MassClass::HEAVY) => SortResult::Special,
-            _ => SortResult::Standard
+    pub(crate) fn with_attributes(mut self, attributes: BTreeSet<Attribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub(crate) fn with_destination(mut self, destination: Option<Destination>) -> Self {
+        self.destination = destination;
+        self
+    }
+
+    pub(crate) fn with_service_level(mut self, service_level: Option<ServiceLevel>) -> Self {
+        self.service_level = service_level;
+        self
+    }
+
+    pub(crate) fn dimensions(&self) -> &dimensions::PackageDimensions {
+        &self.dimensions
+    }
+
+    /**
+     * Sorts the package under the given policy, flagging it for
+     * remeasurement when its measurements are implausible, and as
+     * uncertain when they are within tolerance of a threshold
+     */
+    pub(crate) fn sort(&self, policy: &SortPolicy) -> SortDecision {
+        if let Err(reason) = policy.check_plausibility(&self.dimensions, &self.mass) {
+            return SortDecision::new(SortOutcome::NeedsRemeasure(reason), vec![], policy.version().to_string());
+        }
+
+        let dimension_class = self.dimensions.classify(policy);
+        let mass_class = self.mass.classify(&self.dimensions, policy);
+        let lenient = policy.rules().evaluate(&self.facts(dimension_class.lenient(), mass_class.lenient(), policy));
+        let conservative = policy.rules().evaluate(&self.facts(dimension_class.conservative(), mass_class.conservative(), policy));
+
+        let mut trace = vec![
+            TraceStep::DimensionMeasure(policy.bulky_strategy().clone(), policy.bulky_strategy().measure(&self.dimensions.sorted())),
+            TraceStep::DimensionClass(dimension_class),
+            TraceStep::MassClass(mass_class)
+        ];
+        trace.extend(lenient.attributes.iter().map(|attribute| TraceStep::AttributeConsulted(attribute.clone())));
+        trace.extend(lenient.matched.iter().map(|(rule, result)| TraceStep::RuleMatched(rule.clone(), result.clone())));
+
+        if lenient != conservative {
+            trace.extend(conservative.attributes.iter()
+                .filter(|attribute| !lenient.attributes.contains(attribute))
+                .map(|attribute| TraceStep::AttributeConsulted(attribute.clone())));
+            trace.extend(conservative.matched.iter().map(|(rule, result)| TraceStep::RuleMatched(rule.clone(), result.clone())));
+        }
+
+        // the package is only uncertain when it could end up on another stack
+        let outcome = if lenient.result == conservative.result {
+            SortOutcome::Sorted(conservative.result)
+        } else {
+            SortOutcome::Uncertain { lenient: lenient.result, conservative: conservative.result }
+        };
+        SortDecision::new(outcome, trace, policy.version().to_string()).with_routing_key(conservative.routing_key)
+    }
+
+    /**
+     * Determines the stack based on the rules of the policy only,
+     * routing uncertain packages conservatively
+     */
+    pub(crate) fn stack(&self, policy: &SortPolicy) -> SortResult {
+        let facts = self.facts(
+            self.dimensions.classify(policy).conservative(),
+            self.mass.classify(&self.dimensions, policy).conservative(),
+            policy
+        );
+        policy.rules().evaluate(&facts).result
+    }
+
+    fn facts(&self, dimension_class: DimensionClass, mass_class: MassClass, policy: &SortPolicy) -> Facts<'_> {
+        Facts {
+            dimensions: &self.dimensions,
+            billable_mass: self.mass.billable(&self.dimensions, policy),
+            dimension_class,
+            mass_class,
+            attributes: &self.attributes,
+            destination: self.destination.as_ref(),
+            service_level: self.service_level.as_ref()
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::package::dimensions::test_dependencies::{BulkyPackageDimensions, StandardPackageDimensions};
+    use crate::measurements::cm::Cm;
+    use crate::measurements::kg::Kg;
+    use crate::package::attributes::Attribute;
+    use crate::package::dimensional_weight::DimensionalDivisor;
+    use crate::package::dimensions::test_dependencies::{BulkyPackageDimensions, ParcelDimensions, StandardPackageDimensions};
+    use crate::package::sorted_dimensions::Side;
     use crate::package::mass::test_dependencies::{HeavyMass, StandardMass};
+    use crate::package::service_level::ServiceLevel;
+    use crate::package::dimensions::DimensionClass;
+    use crate::package::mass::MassClass;
     use crate::package::{dimensions, mass, Package};
+    use crate::sort_decision::{SortOutcome, TraceStep};
+    use crate::sort_policy::bulky_strategy::BulkyStrategy;
+    use crate::sort_policy::rules::{EvaluationMode, Predicate, Rule, RuleSet};
+    use crate::sort_policy::plausibility::{Implausibility, PlausibilityLimits};
+    use crate::sort_policy::tolerance::Tolerance;
+    use crate::sort_policy::SortPolicy;
     use crate::sort_result::SortResult;
 
     fn package<T, S>(dimensions: T, mass: S) -> Package
@@ -46,21 +153,212 @@ mod tests {
 
     #[quickcheck]
     fn bulky_and_heavy_items_are_rejected(dimensions: BulkyPackageDimensions, mass: HeavyMass) -> bool {
-        package(dimensions, mass).sort() == SortResult::Rejected
+        package(dimensions, mass).stack(&SortPolicy::default()) == SortResult::Rejected
     }
 
     #[quickcheck]
     fn bulky_but_not_heavy_items_are_special(dimensions: BulkyPackageDimensions, mass: StandardMass) -> bool {
-        package(dimensions, mass).sort() == SortResult::Special
+        package(dimensions, mass).stack(&SortPolicy::default()) == SortResult::Special
     }
 
     #[quickcheck]
     fn heavy_but_not_bulky_items_are_special(dimensions: StandardPackageDimensions, mass: HeavyMass) -> bool {
-        package(dimensions, mass).sort() == SortResult::Special
+        package(dimensions, mass).stack(&SortPolicy::default()) == SortResult::Special
     }
 
     #[quickcheck]
     fn packages_with_standard_dimensions_and_weight_are_standard(dimensions: StandardPackageDimensions, mass: StandardMass) -> bool {
-        package(dimensions, mass).sort() == SortResult::Standard
+        package(dimensions, mass).stack(&SortPolicy::default()) == SortResult::Standard
+    }
+
+    #[quickcheck]
+    fn without_plausibility_limits_every_package_is_sorted(dimensions: BulkyPackageDimensions, mass: HeavyMass) -> bool {
+        package(dimensions, mass).sort(&SortPolicy::default()).outcome() == &SortOutcome::Sorted(SortResult::Rejected)
+    }
+
+    #[test]
+    fn implausible_packages_need_remeasuring() {
+        let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default());
+        let p = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(9_000_000).unwrap(), Cm::new(30).unwrap(), Cm::new(20).unwrap()),
+            mass::Mass::new(Kg::new(5).unwrap())
+        );
+        assert_eq!(p.sort(&policy).outcome(), &SortOutcome::NeedsRemeasure(Implausibility::SideTooLong(9_000_000, 500)))
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn packages_within_tolerance_of_a_threshold_are_uncertain() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1).with_mass(1));
+        let p = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(100).unwrap(), Cm::new(30).unwrap(), Cm::new(19).unwrap()),
+            mass::Mass::new(Kg::new(5).unwrap())
+        );
+        assert_eq!(p.sort(&policy).outcome(), &SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special });
+        assert_eq!(p.stack(&policy), SortResult::Special);
+    }
+
+    #[quickcheck]
+    fn packages_far_from_the_thresholds_are_certain(dimensions: StandardPackageDimensions, mass: HeavyMass) -> bool {
+        // heavy masses start at 20 kg, so only the dimensions can be uncertain
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1));
+        match package(dimensions, mass).sort(&policy).outcome() {
+            SortOutcome::Sorted(result) => result == &SortResult::Special,
+            SortOutcome::Uncertain { lenient, conservative } => lenient == &SortResult::Special && conservative == &SortResult::Rejected,
+            _ => false
+        }
+    }
+
+    #[test]
+    fn the_trace_documents_the_classes_and_matching_rules() {
+        let p = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(100).unwrap(), Cm::new(30).unwrap(), Cm::new(20).unwrap()),
+            mass::Mass::new(Kg::new(5).unwrap())
+        );
+        assert_eq!(p.sort(&SortPolicy::default()).trace(), &[
+            TraceStep::DimensionMeasure(BulkyStrategy::SumOfSides, 150),
+            TraceStep::DimensionClass(DimensionClass::BULKY),
+            TraceStep::MassClass(MassClass::STANDARD),
+            TraceStep::RuleMatched("bulky".to_string(), SortResult::Special)
+        ]);
+    }
+
+    fn content_policy() -> SortPolicy {
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("lithium batteries", SortResult::Rejected)
+                .when(Predicate::HasAttribute(Attribute::LithiumBatteries))
+                .when(Predicate::MassAtLeast(5)),
+            Rule::new("hazmat", SortResult::Special).when(Predicate::HasAttribute(Attribute::Hazmat)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        SortPolicy::default().with_rules(rules)
+    }
+
+    fn labelled(kg: usize, attributes: &[Attribute]) -> Package {
+        Package::new(
+            dimensions::PackageDimensions::new(Cm::new(10).unwrap(), Cm::new(10).unwrap(), Cm::new(10).unwrap()),
+            mass::Mass::new(Kg::new(kg).unwrap())
+        ).with_attributes(attributes.iter().cloned().collect())
+    }
+
+    #[test]
+    fn attributes_are_consulted_by_the_rules() {
+        assert_eq!(labelled(1, &[]).stack(&content_policy()), SortResult::Standard);
+        assert_eq!(labelled(1, &[Attribute::Fragile]).stack(&content_policy()), SortResult::Standard);
+        assert_eq!(labelled(1, &[Attribute::Hazmat]).stack(&content_policy()), SortResult::Special);
+        assert_eq!(labelled(4, &[Attribute::LithiumBatteries]).stack(&content_policy()), SortResult::Standard);
+        assert_eq!(labelled(5, &[Attribute::LithiumBatteries, Attribute::Hazmat]).stack(&content_policy()), SortResult::Rejected);
+    }
+
+    #[test]
+    fn rules_compare_the_billable_weight() {
+        // 30x30x30 cm weighs 6 kg dimensionally
+        let voluminous = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(30).unwrap(), Cm::new(30).unwrap(), Cm::new(30).unwrap()),
+            mass::Mass::new(Kg::new(1).unwrap())
+        ).with_attributes([Attribute::LithiumBatteries].into_iter().collect());
+        assert_eq!(voluminous.stack(&content_policy()), SortResult::Standard);
+        assert_eq!(voluminous.stack(&content_policy().with_dimensional_weight(DimensionalDivisor::EXPRESS)), SortResult::Rejected);
+    }
+
+    #[test]
+    fn the_trace_documents_the_attribute_driving_the_decision() {
+        assert_eq!(labelled(5, &[Attribute::LithiumBatteries, Attribute::Hazmat]).sort(&content_policy()).trace()[3..], [
+            TraceStep::AttributeConsulted(Attribute::LithiumBatteries),
+            TraceStep::RuleMatched("lithium batteries".to_string(), SortResult::Rejected)
+        ]);
+        assert_eq!(labelled(1, &[Attribute::Fragile]).sort(&content_policy()).trace()[3..], [
+            TraceStep::RuleMatched("standard".to_string(), SortResult::Standard)
+        ]);
+    }
+
+    #[test]
+    fn uncertain_decisions_carry_the_routing_key_of_their_conservative_stack() {
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("heavy express", SortResult::Special)
+                .when(Predicate::MassClass(MassClass::HEAVY))
+                .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                .with_routing_key("express-heavy"),
+            Rule::new("heavy", SortResult::Special).when(Predicate::MassClass(MassClass::HEAVY)),
+            Rule::new("bulky", SortResult::Special).when(Predicate::DimensionClass(DimensionClass::BULKY)),
+            Rule::new("express", SortResult::Standard)
+                .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                .with_routing_key("express"),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules).with_tolerance(Tolerance::default().with_mass(1));
+        let p = labelled(19, &[]).with_service_level(Some(ServiceLevel::Express));
+
+        let decision = p.sort(&policy);
+        assert_eq!(decision.outcome(), &SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special });
+        assert_eq!(decision.routing_key(), Some("express-heavy"));
+        assert_eq!(labelled(1, &[]).with_service_level(Some(ServiceLevel::Express)).sort(&policy).routing_key(), Some("express"));
+        assert_eq!(labelled(1, &[]).sort(&policy).routing_key(), None);
+    }
+
+    #[test]
+    fn packages_sent_onto_the_same_stack_by_other_rules_are_certain() {
+        let rules = RuleSet::new(EvaluationMode::AllMatch, vec![
+            Rule::new("hazmat", SortResult::Special).when(Predicate::HasAttribute(Attribute::Hazmat)),
+            Rule::new("heavy", SortResult::Special).when(Predicate::MassClass(MassClass::HEAVY)).with_routing_key("heavy"),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules).with_tolerance(Tolerance::default().with_mass(1));
+        let p = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(30).unwrap(), Cm::new(20).unwrap(), Cm::new(10).unwrap()),
+            mass::Mass::new(Kg::new(20).unwrap())
+        ).with_attributes([Attribute::Hazmat].into_iter().collect());
+
+        let decision = p.sort(&policy);
+        assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+        assert_eq!(decision.routing_key(), Some("heavy"));
+        assert!(decision.trace().contains(&TraceStep::RuleMatched("heavy".to_string(), SortResult::Special)));
+    }
+
+    #[test]
+    fn decisions_record_the_version_of_the_policy() {
+        let p = Package::new(
+            dimensions::PackageDimensions::new(Cm::new(10).unwrap(), Cm::new(10).unwrap(), Cm::new(10).unwrap()),
+            mass::Mass::new(Kg::new(1).unwrap())
+        );
+        assert_eq!(p.sort(&SortPolicy::default()).policy_version(), "default");
+        assert_eq!(p.sort(&SortPolicy::default().with_version("2026-10")).policy_version(), "2026-10");
+    }
+
+    // every orientation dependent setting a policy can hold
+    fn orientation_sensitive_policy(strategy: BulkyStrategy) -> SortPolicy {
+        let rules = RuleSet::new(EvaluationMode::AllMatch, vec![
+            Rule::new("does not fit the chute", SortResult::Special).when(Predicate::SideAtLeast(Side::Shortest, 61)),
+            Rule::new("long", SortResult::Special).when(Predicate::SideAtLeast(Side::Longest, 120)),
+            Rule::new("oversize", SortResult::Rejected).when(Predicate::LengthPlusGirthAtLeast(400)),
+            Rule::new("bulky and heavy", SortResult::Rejected)
+                .when(Predicate::DimensionClass(DimensionClass::BULKY))
+                .when(Predicate::MassClass(MassClass::HEAVY)),
+            Rule::new("bulky", SortResult::Special).when(Predicate::DimensionClass(DimensionClass::BULKY)),
+            Rule::new("heavy", SortResult::Special).when(Predicate::MassClass(MassClass::HEAVY)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+
+        SortPolicy::default()
+            .with_bulky_strategy(strategy)
+            .with_rules(rules)
+            .with_tolerance(Tolerance::default().with_dimension(1).with_mass(1))
+            .with_dimensional_weight(DimensionalDivisor::EXPRESS)
+            .with_plausibility(PlausibilityLimits::default().with_max_side(250))
+    }
+
+    #[quickcheck]
+    fn permuting_the_sides_never_changes_the_decision(dimensions: ParcelDimensions, mass: StandardMass) -> bool {
+        [BulkyStrategy::SumOfSides, BulkyStrategy::LengthPlusGirth].into_iter().all(|strategy| {
+            let policy = orientation_sensitive_policy(strategy);
+            let expected = package(dimensions.permutations()[0].clone(), mass.clone()).sort(&policy);
+            dimensions.permutations().into_iter().all(|permutation| package(permutation, mass.clone()).sort(&policy) == expected)
+        })
+    }
+
+    #[quickcheck]
+    fn permuting_the_sides_never_changes_the_stack(dimensions: ParcelDimensions, mass: HeavyMass) -> bool {
+        let policy = orientation_sensitive_policy(BulkyStrategy::LengthPlusGirth);
+        let expected = package(dimensions.permutations()[0].clone(), mass.clone()).stack(&policy);
+        dimensions.permutations().into_iter().all(|permutation| package(permutation, mass.clone()).stack(&policy) == expected)
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/**
+ * Attribute
+ *
+ * Documents the contents a package is labelled with, which rules
+ * can consult next to its measurements:
+ * - fragile contents
+ * - hazardous materials
+ * - liquids
+ * - contents that must be kept within a temperature range
+ * - lithium batteries
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Attribute {
+    Fragile, Hazmat, Liquid, TemperatureControlled, LithiumBatteries
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeError {
+    Unknown(String)
+}
+
+impl Attribute {
+    pub fn all() -> [Self; 5] {
+        [Attribute::Fragile, Attribute::Hazmat, Attribute::Liquid, Attribute::TemperatureControlled, Attribute::LithiumBatteries]
+    }
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Attribute::Fragile => write!(f, "fragile"),
+            Attribute::Hazmat => write!(f, "hazmat"),
+            Attribute::Liquid => write!(f, "liquid"),
+            Attribute::TemperatureControlled => write!(f, "temperature_controlled"),
+            Attribute::LithiumBatteries => write!(f, "lithium_batteries")
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeError::Unknown(name) => write!(
+                f,
+                "Unknown attribute: expecting one of {}, but got '{}'",
+                Attribute::all().map(|attribute| attribute.to_string()).join(", "),
+                name
+            )
+        }
+    }
+}
+
+impl FromStr for Attribute {
+    type Err = AttributeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Attribute::all().into_iter()
+            .find(|attribute| attribute.to_string() == s)
+            .ok_or_else(|| AttributeError::Unknown(s.to_string()))
+    }
+}
+
+impl Serialize for Attribute {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Attribute {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips() {
+        for attribute in Attribute::all() {
+            assert_eq!(attribute.to_string().parse::<Attribute>(), Ok(attribute));
+        }
+    }
+
+    #[test]
+    fn display_temperature_controlled() {
+        assert_eq!(Attribute::TemperatureControlled.to_string(), "temperature_controlled");
+    }
+
+    #[test]
+    fn unknown_attributes_are_not_parsed() {
+        assert_eq!("irregular".parse::<Attribute>(), Err(AttributeError::Unknown("irregular".to_string())));
+    }
+
+    #[test]
+    fn display_unknown_attribute() {
+        assert_eq!(
+            AttributeError::Unknown("irregular".to_string()).to_string(),
+            "Unknown attribute: expecting one of fragile, hazmat, liquid, temperature_controlled, lithium_batteries, but got 'irregular'"
+        );
+    }
+
+    #[test]
+    fn serde_round_trips() {
+        let json = serde_json::to_string(&Attribute::LithiumBatteries).unwrap();
+        assert_eq!(json, "\"lithium_batteries\"");
+        assert_eq!(serde_json::from_str::<Attribute>(&json).unwrap(), Attribute::LithiumBatteries);
+        assert!(serde_json::from_str::<Attribute>("\"irregular\"").unwrap_err().to_string().starts_with("Unknown attribute"));
+    }
+}
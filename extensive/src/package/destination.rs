@@ -0,0 +1,152 @@
+use std::fmt::Formatter;
+
+/**
+ * Destination
+ *
+ * Where a package is shipped to: a country, written as its two-letter
+ * ISO 3166 code, and optionally a postcode within it. Both are stored
+ * in uppercase, and postcodes without spaces, so "gb", "sw1a 1aa"
+ * equals "GB", "SW1A1AA".
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Destination {
+    country: String,
+    postcode: Option<String>
+}
+
+/**
+ * DestinationError
+ *
+ * Documents why a destination is invalid:
+ * - its country isn't a two-letter code
+ * - its postcode is empty or holds characters other than letters,
+ *   digits, spaces and '-'
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum DestinationError {
+    InvalidCountry(String),
+    InvalidPostcode(String)
+}
+
+impl Destination {
+    pub fn new<T: Into<String>>(country: T) -> Result<Self, DestinationError> {
+        let country = country.into();
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(DestinationError::InvalidCountry(country));
+        }
+
+        Ok(Destination {
+            country: country.to_ascii_uppercase(),
+            postcode: None
+        })
+    }
+
+    pub fn with_postcode<T: Into<String>>(mut self, postcode: T) -> Result<Self, DestinationError> {
+        let postcode = postcode.into();
+        let normalised = normalise_postcode(&postcode);
+        if normalised.is_empty() || !postcode.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-') {
+            return Err(DestinationError::InvalidPostcode(postcode));
+        }
+
+        self.postcode = Some(normalised);
+        Ok(self)
+    }
+
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    pub fn postcode(&self) -> Option<&str> {
+        self.postcode.as_deref()
+    }
+
+    pub(crate) fn is_in_country(&self, country: &str) -> bool {
+        self.country.eq_ignore_ascii_case(country)
+    }
+
+    pub(crate) fn has_postcode_prefix(&self, prefix: &str) -> bool {
+        self.postcode.as_ref().is_some_and(|postcode| postcode.starts_with(&normalise_postcode(prefix)))
+    }
+}
+
+fn normalise_postcode(postcode: &str) -> String {
+    postcode.chars()
+        .filter(|c| *c != ' ')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.postcode {
+            Some(postcode) => write!(f, "{}-{}", self.country, postcode),
+            None => write!(f, "{}", self.country)
+        }
+    }
+}
+
+impl std::fmt::Display for DestinationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DestinationError::InvalidCountry(country) => write!(f, "Invalid country: expecting a two-letter code, but got '{}'", country),
+            DestinationError::InvalidPostcode(postcode) => write!(
+                f,
+                "Invalid postcode: expecting letters, digits, spaces or '-', but got '{}'",
+                postcode
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_are_normalised() {
+        let destination = Destination::new("gb").unwrap().with_postcode("sw1a 1aa").unwrap();
+        assert_eq!(destination.country(), "GB");
+        assert_eq!(destination.postcode(), Some("SW1A1AA"));
+        assert_eq!(destination, Destination::new("GB").unwrap().with_postcode("SW1A1AA").unwrap());
+    }
+
+    #[test]
+    fn countries_are_two_letter_codes() {
+        assert_eq!(Destination::new("GBR"), Err(DestinationError::InvalidCountry("GBR".to_string())));
+        assert_eq!(Destination::new("1A"), Err(DestinationError::InvalidCountry("1A".to_string())));
+        assert_eq!(Destination::new(""), Err(DestinationError::InvalidCountry("".to_string())));
+    }
+
+    #[test]
+    fn postcodes_are_validated() {
+        let de = Destination::new("DE").unwrap();
+        assert_eq!(de.clone().with_postcode(" "), Err(DestinationError::InvalidPostcode(" ".to_string())));
+        assert_eq!(de.with_postcode("10115?"), Err(DestinationError::InvalidPostcode("10115?".to_string())));
+    }
+
+    #[test]
+    fn destinations_match_countries_and_postcode_prefixes() {
+        let destination = Destination::new("GB").unwrap().with_postcode("SW1A 1AA").unwrap();
+        assert!(destination.is_in_country("gb"));
+        assert!(!destination.is_in_country("IE"));
+        assert!(destination.has_postcode_prefix("sw1"));
+        assert!(destination.has_postcode_prefix("SW1A 1"));
+        assert!(!destination.has_postcode_prefix("SW2"));
+        assert!(!Destination::new("GB").unwrap().has_postcode_prefix("SW"));
+    }
+
+    #[test]
+    fn display_destination() {
+        assert_eq!(Destination::new("de").unwrap().to_string(), "DE");
+        assert_eq!(Destination::new("de").unwrap().with_postcode("10115").unwrap().to_string(), "DE-10115");
+    }
+
+    #[test]
+    fn display_invalid_destination() {
+        assert_eq!(DestinationError::InvalidCountry("GBR".to_string()).to_string(), "Invalid country: expecting a two-letter code, but got 'GBR'");
+        assert_eq!(
+            DestinationError::InvalidPostcode("?".to_string()).to_string(),
+            "Invalid postcode: expecting letters, digits, spaces or '-', but got '?'"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// 1 in³ = 16.387064 cm³ and 1 lb = 0.45359237 kg, scaled to whole numbers
+const CUBIC_CM_PER_CUBIC_INCH: u128 = 16_387_064;
+const CUBIC_CM_PER_CUBIC_INCH_SCALE: u128 = 1_000_000;
+const KG_PER_LB: u128 = 45_359_237;
+const KG_PER_LB_SCALE: u128 = 100_000_000;
+
+/**
+ * DimensionalDivisor
+ *
+ * The volume a carrier prices as a unit of mass, which turns the
+ * volume of a package into its dimensional weight. Carriers quote it
+ * either in cm³ per kg or in in³ per lb. A divisor of 0 is treated as 1.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum DimensionalDivisor {
+    CubicCmPerKg(usize),
+    CubicInchPerLb(usize)
+}
+
+impl DimensionalDivisor {
+    // international express and air networks
+    pub const EXPRESS: DimensionalDivisor = DimensionalDivisor::CubicCmPerKg(5000);
+    // road parcel networks
+    pub const ROAD: DimensionalDivisor = DimensionalDivisor::CubicCmPerKg(6000);
+    // domestic US parcel networks
+    pub const US_DOMESTIC: DimensionalDivisor = DimensionalDivisor::CubicInchPerLb(139);
+
+    /**
+     * The dimensional weight of the given volume in kg, rounded up to
+     * the next whole kg as carriers do
+     */
+    pub(crate) fn weight_of(&self, cubic_cm: u128) -> usize {
+        let (numerator, denominator) = match self {
+            DimensionalDivisor::CubicCmPerKg(divisor) => (1, (*divisor).max(1) as u128),
+            DimensionalDivisor::CubicInchPerLb(divisor) => (
+                KG_PER_LB * CUBIC_CM_PER_CUBIC_INCH_SCALE,
+                CUBIC_CM_PER_CUBIC_INCH * KG_PER_LB_SCALE * (*divisor).max(1) as u128
+            )
+        };
+
+        let kg = cubic_cm.saturating_mul(numerator).div_ceil(denominator);
+        usize::try_from(kg).unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    #[test]
+    fn metric_divisors_divide_the_volume() {
+        assert_eq!(DimensionalDivisor::EXPRESS.weight_of(60 * 40 * 40), 20);
+        assert_eq!(DimensionalDivisor::ROAD.weight_of(60 * 40 * 40), 16);
+        assert_eq!(DimensionalDivisor::CubicCmPerKg(4000).weight_of(60 * 40 * 40), 24);
+    }
+
+    #[test]
+    fn weights_are_rounded_up() {
+        assert_eq!(DimensionalDivisor::EXPRESS.weight_of(5001), 2);
+        assert_eq!(DimensionalDivisor::EXPRESS.weight_of(1), 1);
+        assert_eq!(DimensionalDivisor::EXPRESS.weight_of(5000), 1);
+    }
+
+    #[test]
+    fn imperial_divisors_are_converted() {
+        // 139 in³/lb is about 5021.7 cm³/kg
+        assert_eq!(DimensionalDivisor::US_DOMESTIC.weight_of(5021), 1);
+        assert_eq!(DimensionalDivisor::US_DOMESTIC.weight_of(5022), 2);
+        assert_eq!(DimensionalDivisor::US_DOMESTIC.weight_of(60 * 40 * 40), 20);
+    }
+
+    #[test]
+    fn zero_divisors_are_treated_as_one() {
+        assert_eq!(DimensionalDivisor::CubicCmPerKg(0).weight_of(10), 10);
+    }
+
+    #[quickcheck]
+    fn huge_volumes_saturate(divisor: DimensionalDivisorArbitrary) -> bool {
+        divisor.0.weight_of(u128::MAX) > 0
+    }
+
+    #[derive(Clone, Debug)]
+    struct DimensionalDivisorArbitrary(DimensionalDivisor);
+
+    impl Arbitrary for DimensionalDivisorArbitrary {
+        fn arbitrary(g: &mut Gen) -> Self {
+            match bool::arbitrary(g) {
+                true => DimensionalDivisorArbitrary(DimensionalDivisor::CubicCmPerKg(usize::arbitrary(g))),
+                false => DimensionalDivisorArbitrary(DimensionalDivisor::CubicInchPerLb(usize::arbitrary(g)))
+            }
+        }
+    }
+}
@@ -1,11 +1,43 @@
 use crate::measurements::cm::Cm;
+use crate::package::dimensional_weight::DimensionalDivisor;
+use crate::package::sorted_dimensions::SortedDimensions;
+use crate::sort_policy::tolerance::Band;
+use crate::sort_policy::SortPolicy;
 
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum DimensionClass {
-    STANDARD, BULKY
+pub enum DimensionClass {
+    STANDARD, BULKY, UNCERTAIN
 }
 
-#[derive(Clone, Debug)]
+impl std::fmt::Display for DimensionClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DimensionClass::STANDARD => write!(f, "standard"),
+            DimensionClass::BULKY => write!(f, "bulky"),
+            DimensionClass::UNCERTAIN => write!(f, "uncertain")
+        }
+    }
+}
+
+impl DimensionClass {
+    // uncertain packages are treated as standard
+    pub(crate) fn lenient(&self) -> DimensionClass {
+        match self {
+            DimensionClass::BULKY => DimensionClass::BULKY,
+            _ => DimensionClass::STANDARD
+        }
+    }
+
+    // uncertain packages are treated as bulky
+    pub(crate) fn conservative(&self) -> DimensionClass {
+        match self {
+            DimensionClass::STANDARD => DimensionClass::STANDARD,
+            _ => DimensionClass::BULKY
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct PackageDimensions {
     width: Cm,
     height: Cm,
@@ -21,16 +53,57 @@ impl PackageDimensions {
         }
     }
 
-    fn sum(&self) -> Cm {
+    pub(crate) fn width(&self) -> &Cm {
+        &self.width
+    }
+
+    pub(crate) fn height(&self) -> &Cm {
+        &self.height
+    }
+
+    pub(crate) fn length(&self) -> &Cm {
+        &self.length
+    }
+
+    pub(crate) fn sum(&self) -> Cm {
         self.width.clone() + self.height.clone() + self.length.clone()
     }
 
-    pub(crate) fn classify(&self) -> DimensionClass {
-        if self.sum() >= Cm::new(150).unwrap() {
-            DimensionClass::BULKY
-        } else {
-            DimensionClass::STANDARD
-        }
+    // the sides from longest to shortest, whatever the orientation of the package
+    pub(crate) fn sorted(&self) -> SortedDimensions {
+        SortedDimensions::from(self)
+    }
+
+    // expressed in cm³, which can exceed the range of a usize
+    pub(crate) fn volume(&self) -> u128 {
+        (self.width.value() as u128)
+            .saturating_mul(self.height.value() as u128)
+            .saturating_mul(self.length.value() as u128)
+    }
+
+    // expressed in whole kg
+    pub(crate) fn dimensional_weight(&self, divisor: &DimensionalDivisor) -> usize {
+        divisor.weight_of(self.volume())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(width = self.width.value(), height = self.height.value(), length = self.length.value(), dimension_class = tracing::field::Empty)
+    ))]
+    pub(crate) fn classify(&self, policy: &SortPolicy) -> DimensionClass {
+        // every one of the three sides may be off by the tolerance
+        let strategy = policy.bulky_strategy();
+        let tolerance = strategy.tolerance(policy.tolerance().dimension());
+        let class = match Band::of(strategy.measure(&self.sorted()), strategy.threshold(policy).value(), tolerance) {
+            Band::Below => DimensionClass::STANDARD,
+            Band::Uncertain => DimensionClass::UNCERTAIN,
+            Band::AtOrAbove => DimensionClass::BULKY
+        };
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("dimension_class", tracing::field::display(&class));
+
+        class
     }
 }
 
@@ -84,6 +157,55 @@ pub(crate) mod test_dependencies {
         }
     }
 
+    /**
+     * Dimensions of any package handled by a parcel network, with sides
+     * of up to 3 m, so that they fall on either side of the thresholds
+     */
+    #[derive(Clone, Debug)]
+    pub(crate) struct ParcelDimensions {
+        dimensions: PackageDimensions
+    }
+
+    impl Deref for ParcelDimensions {
+        type Target = PackageDimensions;
+
+        fn deref(&self) -> &Self::Target {
+            &self.dimensions
+        }
+    }
+
+    impl From<PackageDimensions> for ParcelDimensions {
+        fn from(dimensions: PackageDimensions) -> Self {
+            ParcelDimensions { dimensions }
+        }
+    }
+
+    impl ParcelDimensions {
+        // the six orientations in which the sides can be reported
+        pub(crate) fn permutations(&self) -> Vec<PackageDimensions> {
+            let (w, h, l) = (self.width().clone(), self.height().clone(), self.length().clone());
+            vec![
+                PackageDimensions::new(w.clone(), h.clone(), l.clone()),
+                PackageDimensions::new(w.clone(), l.clone(), h.clone()),
+                PackageDimensions::new(h.clone(), w.clone(), l.clone()),
+                PackageDimensions::new(h.clone(), l.clone(), w.clone()),
+                PackageDimensions::new(l.clone(), w.clone(), h.clone()),
+                PackageDimensions::new(l, h, w)
+            ]
+        }
+    }
+
+    impl Arbitrary for ParcelDimensions {
+        fn arbitrary(_g: &mut Gen) -> Self {
+            let mut rng = rand::thread_rng();
+            let mut side = || Cm::new(rng.gen_range(1..=300)).unwrap();
+
+            ParcelDimensions {
+                dimensions: PackageDimensions::new(side(), side(), side())
+            }
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub(crate) struct BulkyPackageDimensions {
         dimensions: PackageDimensions
@@ -113,7 +235,7 @@ pub(crate) mod test_dependencies {
             let width = rng.gen_range(min..=usize::MAX);
             let height = rng.gen_range(min..=usize::MAX);
 
-            min = (150 as usize).saturating_sub(width).saturating_sub(height);
+            min = 150_usize.saturating_sub(width).saturating_sub(height);
             let length = rng.gen_range(min..=usize::MAX);
 
             BulkyPackageDimensions {
@@ -130,7 +252,16 @@ pub(crate) mod test_dependencies {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::package::dimensions::test_dependencies::{BulkyPackageDimensions, StandardPackageDimensions};
+    use crate::sort_policy::tolerance::Tolerance;
+    use crate::package::dimensions::test_dependencies::{BulkyPackageDimensions, ParcelDimensions, StandardPackageDimensions};
+    use crate::sort_policy::bulky_strategy::BulkyStrategy;
+
+    // oversize from a length plus girth of 300 cm
+    fn girth_policy() -> SortPolicy {
+        SortPolicy::default()
+            .with_bulky_strategy(BulkyStrategy::LengthPlusGirth)
+            .with_length_plus_girth_threshold(Cm::new(300).unwrap())
+    }
 
 
     #[quickcheck]
@@ -145,11 +276,115 @@ mod tests {
 
     #[quickcheck]
     fn a_standard_package_is_not_bulky(dimensions: StandardPackageDimensions) -> bool {
-        dimensions.classify() == DimensionClass::STANDARD
+        dimensions.classify(&SortPolicy::default()) == DimensionClass::STANDARD
     }
 
     #[quickcheck]
     fn a_bulky_package_is_bulky(dimensions: BulkyPackageDimensions) -> bool {
-        dimensions.classify() == DimensionClass::BULKY
+        dimensions.classify(&SortPolicy::default()) == DimensionClass::BULKY
+    }
+
+    #[quickcheck]
+    fn a_standard_package_is_never_bulky_within_tolerance(dimensions: StandardPackageDimensions, tolerance: u8) -> bool {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(tolerance as usize));
+        dimensions.classify(&policy) != DimensionClass::BULKY
+    }
+
+    #[quickcheck]
+    fn a_bulky_package_is_never_standard_within_tolerance(dimensions: BulkyPackageDimensions, tolerance: u8) -> bool {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(tolerance as usize));
+        dimensions.classify(&policy) != DimensionClass::STANDARD
+    }
+
+    #[test]
+    fn the_girth_strategy_classifies_on_length_plus_girth() {
+        let cm = |value| Cm::new(value).unwrap();
+        let policy = girth_policy();
+        // bulky by the sum of its sides, but a long and narrow tube
+        assert_eq!(PackageDimensions::new(cm(10), cm(10), cm(130)).classify(&SortPolicy::default()), DimensionClass::BULKY);
+        assert_eq!(PackageDimensions::new(cm(10), cm(10), cm(130)).classify(&policy), DimensionClass::STANDARD);
+        // length plus girth of 300 and 298 cm
+        assert_eq!(PackageDimensions::new(cm(60), cm(60), cm(60)).classify(&policy), DimensionClass::BULKY);
+        assert_eq!(PackageDimensions::new(cm(60), cm(59), cm(60)).classify(&policy), DimensionClass::STANDARD);
+    }
+
+    #[test]
+    fn the_girth_strategy_does_not_use_the_sum_of_sides_threshold() {
+        let cm = |value| Cm::new(value).unwrap();
+        let policy = SortPolicy::default().with_bulky_strategy(BulkyStrategy::LengthPlusGirth);
+        // a length plus girth of 190 cm, over the 150 cm sum of sides threshold
+        assert_eq!(PackageDimensions::new(cm(50), cm(40), cm(30)).classify(&policy), DimensionClass::STANDARD);
+        assert_eq!(PackageDimensions::new(cm(100), cm(50), cm(50)).classify(&policy), DimensionClass::BULKY);
+    }
+
+    #[test]
+    fn girth_is_uncertain_within_the_tolerance_of_every_side() {
+        let cm = |value| Cm::new(value).unwrap();
+        let policy = girth_policy().with_tolerance(Tolerance::default().with_dimension(1));
+        // length plus girth of 294, 295, 304 and 305 cm
+        assert_eq!(PackageDimensions::new(cm(100), cm(50), cm(47)).classify(&policy), DimensionClass::STANDARD);
+        assert_eq!(PackageDimensions::new(cm(101), cm(50), cm(47)).classify(&policy), DimensionClass::UNCERTAIN);
+        assert_eq!(PackageDimensions::new(cm(100), cm(50), cm(52)).classify(&policy), DimensionClass::UNCERTAIN);
+        assert_eq!(PackageDimensions::new(cm(101), cm(50), cm(52)).classify(&policy), DimensionClass::BULKY);
+    }
+
+    #[quickcheck]
+    fn length_plus_girth_is_invariant_to_the_order_of_the_sides(dimensions: ParcelDimensions) -> bool {
+        let expected = dimensions.sorted().length_plus_girth();
+        dimensions.permutations().iter().all(|permutation| permutation.sorted().length_plus_girth() == expected)
+    }
+
+    #[quickcheck]
+    fn the_girth_class_is_invariant_to_the_order_of_the_sides(dimensions: ParcelDimensions) -> bool {
+        let policy = girth_policy().with_tolerance(Tolerance::default().with_dimension(2));
+        let expected = dimensions.classify(&policy);
+        dimensions.permutations().iter().all(|permutation| permutation.classify(&policy) == expected)
+    }
+
+    #[quickcheck]
+    fn length_plus_girth_is_never_less_than_the_sum_of_sides(dimensions: ParcelDimensions) -> bool {
+        dimensions.sorted().length_plus_girth() >= dimensions.sum().value()
+    }
+
+    #[test]
+    fn dimensional_weight_uses_the_volume() {
+        let cm = |value| Cm::new(value).unwrap();
+        let dimensions = PackageDimensions::new(cm(60), cm(40), cm(40));
+        assert_eq!(dimensions.dimensional_weight(&DimensionalDivisor::EXPRESS), 20);
+        assert_eq!(dimensions.dimensional_weight(&DimensionalDivisor::ROAD), 16);
+        assert_eq!(dimensions.dimensional_weight(&DimensionalDivisor::US_DOMESTIC), 20);
+    }
+
+    #[test]
+    fn packages_near_the_threshold_are_uncertain() {
+        let cm = |value| Cm::new(value).unwrap();
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1));
+        assert_eq!(PackageDimensions::new(cm(100), cm(25), cm(21)).classify(&policy), DimensionClass::STANDARD);
+        assert_eq!(PackageDimensions::new(cm(100), cm(25), cm(22)).classify(&policy), DimensionClass::UNCERTAIN);
+        assert_eq!(PackageDimensions::new(cm(100), cm(25), cm(27)).classify(&policy), DimensionClass::UNCERTAIN);
+        assert_eq!(PackageDimensions::new(cm(100), cm(25), cm(28)).classify(&policy), DimensionClass::BULKY);
+    }
+
+    #[test]
+    fn uncertain_dimensions_resolve_both_ways() {
+        assert_eq!(DimensionClass::UNCERTAIN.lenient(), DimensionClass::STANDARD);
+        assert_eq!(DimensionClass::UNCERTAIN.conservative(), DimensionClass::BULKY);
+        assert_eq!(DimensionClass::STANDARD.conservative(), DimensionClass::STANDARD);
+        assert_eq!(DimensionClass::BULKY.lenient(), DimensionClass::BULKY);
+    }
+
+    #[test]
+    fn the_longest_side_is_found_regardless_of_position() {
+        let cm = |value| Cm::new(value).unwrap();
+        assert_eq!(PackageDimensions::new(cm(3), cm(1), cm(2)).sorted().longest(), &cm(3));
+        assert_eq!(PackageDimensions::new(cm(1), cm(3), cm(2)).sorted().longest(), &cm(3));
+        assert_eq!(PackageDimensions::new(cm(1), cm(2), cm(3)).sorted().longest(), &cm(3));
+    }
+
+    #[test]
+    fn the_volume_does_not_overflow() {
+        let cm = |value| Cm::new(value).unwrap();
+        assert_eq!(PackageDimensions::new(cm(2), cm(3), cm(4)).volume(), 24);
+        assert_eq!(PackageDimensions::new(cm(usize::MAX), cm(usize::MAX), cm(usize::MAX)).volume(), u128::MAX);
     }
 }
\ No newline at end of file
@@ -0,0 +1,336 @@
+use crate::measurements::cm::Cm;
+use crate::measurements::kg::Kg;
+use crate::package::dimensions::PackageDimensions;
+use crate::package::mass::Mass;
+use crate::package::sorted_dimensions::{Side, SortedDimensions};
+use crate::package::Package;
+use crate::sort_policy::plausibility::Implausibility;
+use crate::sort_policy::SortPolicy;
+
+/**
+ * FusionStrategy
+ *
+ * Documents how the readings that survived outlier rejection are
+ * combined into a single measurement.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum FusionStrategy {
+    Median, TrimmedMean
+}
+
+/**
+ * Fusion
+ *
+ * Configures how multiple readings of the same package are fused.
+ * Readings further from the median than the outlier limit are
+ * rejected before the remaining readings are combined.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fusion {
+    strategy: FusionStrategy,
+    dimension_outlier: usize,
+    mass_outlier: usize
+}
+
+/**
+ * Disagreement
+ *
+ * Documents a measurement whose readings are further apart than the
+ * tolerance of the measuring equipment allows. Every variant holds the
+ * spread of the readings followed by the allowed spread. Sides are
+ * named by their rank, as every reading may report them in another
+ * order.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Disagreement {
+    Side(Side, usize, usize),
+    Mass(usize, usize)
+}
+
+/**
+ * FusionReport
+ *
+ * Documents how many readings were rejected as outliers, and which
+ * measurements disagreed beyond tolerance.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FusionReport {
+    rejected_readings: usize,
+    disagreements: Vec<Disagreement>
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion {
+            strategy: FusionStrategy::Median,
+            dimension_outlier: 10,
+            mass_outlier: 2
+        }
+    }
+}
+
+impl Fusion {
+    pub fn with_strategy(mut self, strategy: FusionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_dimension_outlier(mut self, cm: usize) -> Self {
+        self.dimension_outlier = cm;
+        self
+    }
+
+    pub fn with_mass_outlier(mut self, kg: usize) -> Self {
+        self.mass_outlier = kg;
+        self
+    }
+}
+
+impl FusionReport {
+    pub fn rejected_readings(&self) -> usize {
+        self.rejected_readings
+    }
+
+    pub fn disagreements(&self) -> &[Disagreement] {
+        &self.disagreements
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.disagreements.is_empty()
+    }
+}
+
+/**
+ * Fuses several readings of a single package into one package
+ *
+ * The sides of every reading are ranked from longest to shortest
+ * before being fused, so that the orientation in which a package was
+ * measured never matters. The package can't be fused when every reading
+ * of a measurement is an outlier, as any value would be made up. Both
+ * slices of readings must contain at least one reading.
+ */
+pub(crate) fn fuse(dimension_readings: &[PackageDimensions], mass_readings: &[Mass], policy: &SortPolicy) -> (Result<Package, Implausibility>, FusionReport) {
+    let fusion = policy.fusion();
+    let mut report = FusionReport::default();
+
+    // two readings within tolerance of the real value can be twice the tolerance apart
+    let dimension_spread = policy.tolerance().dimension().saturating_mul(2);
+    let mass_spread = policy.tolerance().mass().saturating_mul(2);
+
+    let sorted: Vec<SortedDimensions> = dimension_readings.iter().map(PackageDimensions::sorted).collect();
+    let mut fuse_side = |side: Side| {
+        let values: Vec<usize> = sorted.iter().map(|d| d.side(&side).value()).collect();
+        let disagreement = |spread, allowed| Disagreement::Side(side.clone(), spread, allowed);
+        fuse_values(&values, &fusion.strategy, fusion.dimension_outlier, dimension_spread, disagreement, &mut report)
+            .map(|fused| Cm::new(fused).unwrap())
+    };
+    let longest = fuse_side(Side::Longest);
+    let middle = fuse_side(Side::Middle);
+    let shortest = fuse_side(Side::Shortest);
+
+    let values: Vec<usize> = mass_readings.iter().map(|m| m.value().value()).collect();
+    let mass = fuse_values(&values, &fusion.strategy, fusion.mass_outlier, mass_spread, Disagreement::Mass, &mut report);
+
+    let package = (|| Ok(Package::new(
+        PackageDimensions::new(longest?, middle?, shortest?),
+        Mass::new(Kg::new(mass?).unwrap())
+    )))().map_err(Implausibility::Irreconcilable);
+
+    (package, report)
+}
+
+fn fuse_values(
+    values: &[usize],
+    strategy: &FusionStrategy,
+    outlier_limit: usize,
+    allowed_spread: usize,
+    disagreement: impl Fn(usize, usize) -> Disagreement,
+    report: &mut FusionReport
+) -> Result<usize, Disagreement> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let spread = sorted[sorted.len() - 1] - sorted[0];
+    if spread > allowed_spread {
+        report.disagreements.push(disagreement(spread, allowed_spread));
+    }
+
+    let center = median(&sorted);
+    let kept: Vec<usize> = sorted.iter().copied().filter(|value| value.abs_diff(center) <= outlier_limit).collect();
+
+    // when every reading is an outlier, there's nothing to prefer; the
+    // readings could only be kept within twice the outlier limit
+    if kept.is_empty() {
+        return Err(disagreement(spread, outlier_limit.saturating_mul(2)));
+    }
+    report.rejected_readings += sorted.len() - kept.len();
+
+    Ok(match strategy {
+        FusionStrategy::Median => median(&kept),
+        FusionStrategy::TrimmedMean => trimmed_mean(&kept)
+    })
+}
+
+// expects sorted values, and rounds down between the two middle values
+fn median(sorted: &[usize]) -> usize {
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        sorted[middle]
+    } else {
+        mean(&sorted[middle - 1..=middle])
+    }
+}
+
+// expects sorted values, and drops a quarter of the values on either end
+fn trimmed_mean(sorted: &[usize]) -> usize {
+    let trim = sorted.len() / 4;
+    mean(&sorted[trim..sorted.len() - trim])
+}
+
+fn mean(values: &[usize]) -> usize {
+    let sum: u128 = values.iter().map(|value| *value as u128).sum();
+    (sum / values.len() as u128) as usize
+}
+
+impl std::fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Disagreement::Side(side, spread, allowed) => write!(f, "Readings of the {} disagree: expecting a spread of at most {} cm, but got {} cm", side, allowed, spread),
+            Disagreement::Mass(spread, allowed) => write!(f, "Mass readings disagree: expecting a spread of at most {} kg, but got {} kg", allowed, spread)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::test_dependencies::ParcelDimensions;
+    use crate::sort_policy::tolerance::Tolerance;
+
+    fn dimensions(width: usize, height: usize, length: usize) -> PackageDimensions {
+        PackageDimensions::new(Cm::new(width).unwrap(), Cm::new(height).unwrap(), Cm::new(length).unwrap())
+    }
+
+    fn mass(kg: usize) -> Mass {
+        Mass::new(Kg::new(kg).unwrap())
+    }
+
+    fn fuse_all(values: &[usize], strategy: FusionStrategy, outlier_limit: usize) -> (Result<usize, Disagreement>, FusionReport) {
+        let mut report = FusionReport::default();
+        let fused = fuse_values(values, &strategy, outlier_limit, 0, Disagreement::Mass, &mut report);
+        (fused, report)
+    }
+
+    #[quickcheck]
+    fn the_fused_value_lies_between_the_readings(values: Vec<usize>, outlier_limit: usize, trimmed: bool) -> bool {
+        if values.is_empty() {
+            return true;
+        }
+        let strategy = if trimmed { FusionStrategy::TrimmedMean } else { FusionStrategy::Median };
+        match fuse_all(&values, strategy, outlier_limit) {
+            (Ok(fused), _) => values.iter().min().unwrap() <= &fused && &fused <= values.iter().max().unwrap(),
+            (Err(_), _) => true
+        }
+    }
+
+    #[quickcheck]
+    fn a_single_reading_is_kept_as_is(value: usize) -> bool {
+        fuse_all(&[value], FusionStrategy::TrimmedMean, 0) == (Ok(value), FusionReport::default())
+    }
+
+    #[test]
+    fn the_median_of_readings_is_used() {
+        assert_eq!(fuse_all(&[52, 50, 51], FusionStrategy::Median, 10).0, Ok(51));
+        assert_eq!(fuse_all(&[50, 53], FusionStrategy::Median, 10).0, Ok(51));
+    }
+
+    #[test]
+    fn the_trimmed_mean_of_readings_is_used() {
+        assert_eq!(fuse_all(&[50, 51, 55, 60], FusionStrategy::TrimmedMean, 10).0, Ok(53));
+    }
+
+    #[test]
+    fn outliers_are_rejected() {
+        let (fused, report) = fuse_all(&[50, 52, 900], FusionStrategy::TrimmedMean, 10);
+        assert_eq!(fused, Ok(51));
+        assert_eq!(report.rejected_readings(), 1);
+    }
+
+    #[test]
+    fn readings_that_are_all_outliers_are_not_fused() {
+        assert_eq!(fuse_all(&[50, 400], FusionStrategy::Median, 10).0, Err(Disagreement::Mass(350, 20)));
+        let (package, report) = fuse(&[dimensions(50, 40, 30), dimensions(400, 40, 30)], &[mass(10)], &SortPolicy::default());
+        assert_eq!(package.err(), Some(Implausibility::Irreconcilable(Disagreement::Side(Side::Longest, 350, 20))));
+        assert_eq!(report.disagreements(), &[Disagreement::Side(Side::Longest, 350, 0)]);
+    }
+
+    #[test]
+    fn readings_beyond_tolerance_are_reported() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1).with_mass(1));
+        let (_, report) = fuse(
+            &[dimensions(50, 40, 30), dimensions(51, 40, 34)],
+            &[mass(10), mass(12)],
+            &policy
+        );
+        assert_eq!(report.disagreements(), &[Disagreement::Side(Side::Shortest, 4, 2)]);
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn readings_within_tolerance_are_consistent() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1).with_mass(1));
+        let (package, report) = fuse(
+            &[dimensions(50, 40, 30), dimensions(52, 40, 31), dimensions(51, 41, 30)],
+            &[mass(10), mass(11)],
+            &policy
+        );
+        let package = package.unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.rejected_readings(), 0);
+        assert_eq!(package.dimensions.width(), &Cm::new(51).unwrap());
+        assert_eq!(package.mass.value(), &Kg::new(10).unwrap());
+    }
+
+    #[test]
+    fn sides_are_fused_by_rank() {
+        let (package, report) = fuse(&[dimensions(140, 5, 5), dimensions(5, 5, 140), dimensions(5, 140, 5)], &[mass(5)], &SortPolicy::default());
+        assert_eq!(package.unwrap().dimensions.sorted(), dimensions(140, 5, 5).sorted());
+        assert!(report.is_consistent());
+    }
+
+    // readings of a single package, each off by a few cm and in any orientation
+    #[quickcheck]
+    fn permuting_the_readings_never_changes_the_fused_package(dimensions: ParcelDimensions, readings: Vec<(u8, u8, u8, u8)>) -> bool {
+        if readings.is_empty() {
+            return true;
+        }
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_dimension(1));
+        let off = |side: &Cm, by: u8| Cm::new(side.value() + by as usize % 4).unwrap();
+        let measured: Vec<ParcelDimensions> = readings.iter()
+            .map(|(w, h, l, _)| ParcelDimensions::from(PackageDimensions::new(off(dimensions.width(), *w), off(dimensions.height(), *h), off(dimensions.length(), *l))))
+            .collect();
+        let permuted: Vec<PackageDimensions> = measured.iter().zip(&readings)
+            .map(|(reading, (_, _, _, orientation))| reading.permutations()[*orientation as usize % 6].clone())
+            .collect();
+        let measured: Vec<PackageDimensions> = measured.iter().map(|reading| (**reading).clone()).collect();
+
+        let fused = |readings: &[PackageDimensions]| {
+            let (package, report) = fuse(readings, &[mass(10)], &policy);
+            (package.map(|package| (package.dimensions.sorted(), package.sort(&policy))), report)
+        };
+        fused(&measured) == fused(&permuted)
+    }
+
+    #[test]
+    fn display_disagreements() {
+        assert_eq!(
+            Disagreement::Side(Side::Longest, 4, 2).to_string(),
+            "Readings of the longest side disagree: expecting a spread of at most 2 cm, but got 4 cm"
+        );
+        assert_eq!(
+            Disagreement::Side(Side::Shortest, 4, 2).to_string(),
+            "Readings of the shortest side disagree: expecting a spread of at most 2 cm, but got 4 cm"
+        );
+        assert_eq!(Disagreement::Mass(3, 1).to_string(), "Mass readings disagree: expecting a spread of at most 1 kg, but got 3 kg");
+    }
+}
@@ -1,9 +1,41 @@
 use crate::measurements::kg::Kg;
+use crate::package::dimensions::PackageDimensions;
+use crate::sort_policy::tolerance::Band;
+use crate::sort_policy::SortPolicy;
 
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum MassClass {
+pub enum MassClass {
     HEAVY,
-    STANDARD
+    STANDARD,
+    UNCERTAIN
+}
+
+impl std::fmt::Display for MassClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MassClass::HEAVY => write!(f, "heavy"),
+            MassClass::STANDARD => write!(f, "standard"),
+            MassClass::UNCERTAIN => write!(f, "uncertain")
+        }
+    }
+}
+
+impl MassClass {
+    // uncertain packages are treated as standard
+    pub(crate) fn lenient(&self) -> MassClass {
+        match self {
+            MassClass::HEAVY => MassClass::HEAVY,
+            _ => MassClass::STANDARD
+        }
+    }
+
+    // uncertain packages are treated as heavy
+    pub(crate) fn conservative(&self) -> MassClass {
+        match self {
+            MassClass::STANDARD => MassClass::STANDARD,
+            _ => MassClass::HEAVY
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -18,13 +50,42 @@ impl Mass {
         }
     }
 
-    pub(crate) fn classify(&self) -> MassClass {
-        if self.value >= Kg::new(20).unwrap() {
-            MassClass::HEAVY
-        } else {
-            MassClass::STANDARD
+    pub(crate) fn value(&self) -> &Kg {
+        &self.value
+    }
+
+    /**
+     * The mass a carrier charges for, in kg
+     *
+     * When the policy prices on dimensional weight, this is the largest
+     * of the actual mass and the dimensional weight of the package.
+     */
+    pub(crate) fn billable(&self, dimensions: &PackageDimensions, policy: &SortPolicy) -> usize {
+        match policy.dimensional_divisor() {
+            Some(divisor) => self.value.value().max(dimensions.dimensional_weight(divisor)),
+            None => self.value.value()
         }
     }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(mass = self.value.value(), billable = tracing::field::Empty, mass_class = tracing::field::Empty)
+    ))]
+    pub(crate) fn classify(&self, dimensions: &PackageDimensions, policy: &SortPolicy) -> MassClass {
+        let billable = self.billable(dimensions, policy);
+        let class = match Band::of(billable, policy.heavy_threshold().value(), policy.tolerance().mass()) {
+            Band::Below => MassClass::STANDARD,
+            Band::Uncertain => MassClass::UNCERTAIN,
+            Band::AtOrAbove => MassClass::HEAVY
+        };
+        #[cfg(feature = "tracing")]
+        tracing::Span::current()
+            .record("billable", billable)
+            .record("mass_class", tracing::field::display(&class));
+
+        class
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +157,13 @@ pub(crate) mod test_dependencies {
 mod tests {
     use super::*;
     use crate::package::mass::test_dependencies::{HeavyMass, StandardMass};
+    use crate::measurements::cm::Cm;
+    use crate::package::dimensional_weight::DimensionalDivisor;
+    use crate::sort_policy::tolerance::Tolerance;
+
+    fn dimensions(width: usize, height: usize, length: usize) -> PackageDimensions {
+        PackageDimensions::new(Cm::new(width).unwrap(), Cm::new(height).unwrap(), Cm::new(length).unwrap())
+    }
 
     #[quickcheck]
     fn arbitrary_standard_mass_should_be_less_than_20(mass: StandardMass) -> bool {
@@ -109,11 +177,58 @@ mod tests {
 
     #[quickcheck]
     fn a_heavy_package_is_classified_as_heavy(mass: HeavyMass) -> bool {
-        mass.classify() == MassClass::HEAVY
+        mass.classify(&dimensions(1, 1, 1), &SortPolicy::default()) == MassClass::HEAVY
     }
 
     #[quickcheck]
     fn a_standard_package_is_classified_as_standard(mass: StandardMass) -> bool {
-        mass.classify() == MassClass::STANDARD
+        mass.classify(&dimensions(1, 1, 1), &SortPolicy::default()) == MassClass::STANDARD
+    }
+
+    #[test]
+    fn masses_near_the_threshold_are_uncertain() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_mass(1));
+        let classify = |kg| Mass::new(Kg::new(kg).unwrap()).classify(&dimensions(1, 1, 1), &policy);
+        assert_eq!(classify(18), MassClass::STANDARD);
+        assert_eq!(classify(19), MassClass::UNCERTAIN);
+        assert_eq!(classify(20), MassClass::UNCERTAIN);
+        assert_eq!(classify(21), MassClass::HEAVY);
+    }
+
+    #[test]
+    fn uncertain_masses_resolve_both_ways() {
+        assert_eq!(MassClass::UNCERTAIN.lenient(), MassClass::STANDARD);
+        assert_eq!(MassClass::UNCERTAIN.conservative(), MassClass::HEAVY);
+        assert_eq!(MassClass::STANDARD.conservative(), MassClass::STANDARD);
+        assert_eq!(MassClass::HEAVY.lenient(), MassClass::HEAVY);
+    }
+
+    #[test]
+    fn the_actual_mass_is_billed_by_default() {
+        let mass = Mass::new(Kg::new(5).unwrap());
+        assert_eq!(mass.billable(&dimensions(100, 100, 100), &SortPolicy::default()), 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn the_largest_of_the_actual_and_dimensional_weight_is_billed() {
+        let policy = SortPolicy::default().with_dimensional_weight(DimensionalDivisor::EXPRESS);
+        let mass = Mass::new(Kg::new(5).unwrap());
+        assert_eq!(mass.billable(&dimensions(60, 40, 40), &policy), 20);
+        assert_eq!(mass.billable(&dimensions(10, 10, 10), &policy), 5);
+    }
+
+    #[test]
+    fn light_but_voluminous_packages_can_be_heavy() {
+        let mass = Mass::new(Kg::new(5).unwrap());
+        let voluminous = dimensions(60, 40, 40);
+        assert_eq!(mass.classify(&voluminous, &SortPolicy::default()), MassClass::STANDARD);
+        assert_eq!(mass.classify(&voluminous, &SortPolicy::default().with_dimensional_weight(DimensionalDivisor::EXPRESS)), MassClass::HEAVY);
+        assert_eq!(mass.classify(&voluminous, &SortPolicy::default().with_dimensional_weight(DimensionalDivisor::ROAD)), MassClass::STANDARD);
+    }
+
+    #[quickcheck]
+    fn dimensional_weight_never_lightens_a_package(mass: HeavyMass) -> bool {
+        let policy = SortPolicy::default().with_dimensional_weight(DimensionalDivisor::US_DOMESTIC);
+        mass.classify(&dimensions(1, 1, 1), &policy) == MassClass::HEAVY
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/**
+ * ServiceLevel
+ *
+ * Documents how fast a package is to be delivered:
+ * - economy, at the convenience of the network
+ * - standard, within the usual delivery time
+ * - express, ahead of every other package
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ServiceLevel {
+    Economy, Standard, Express
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServiceLevelError {
+    Unknown(String)
+}
+
+impl ServiceLevel {
+    pub fn all() -> [Self; 3] {
+        [ServiceLevel::Economy, ServiceLevel::Standard, ServiceLevel::Express]
+    }
+}
+
+impl std::fmt::Display for ServiceLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceLevel::Economy => write!(f, "economy"),
+            ServiceLevel::Standard => write!(f, "standard"),
+            ServiceLevel::Express => write!(f, "express")
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceLevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceLevelError::Unknown(name) => write!(f, "Unknown service level: expecting economy, standard or express, but got '{}'", name)
+        }
+    }
+}
+
+impl FromStr for ServiceLevel {
+    type Err = ServiceLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ServiceLevel::all().into_iter()
+            .find(|level| level.to_string() == s)
+            .ok_or_else(|| ServiceLevelError::Unknown(s.to_string()))
+    }
+}
+
+impl Serialize for ServiceLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips() {
+        for level in ServiceLevel::all() {
+            assert_eq!(level.to_string().parse::<ServiceLevel>(), Ok(level));
+        }
+    }
+
+    #[test]
+    fn unknown_service_levels_are_not_parsed() {
+        assert_eq!("overnight".parse::<ServiceLevel>(), Err(ServiceLevelError::Unknown("overnight".to_string())));
+        assert_eq!(
+            ServiceLevelError::Unknown("overnight".to_string()).to_string(),
+            "Unknown service level: expecting economy, standard or express, but got 'overnight'"
+        );
+    }
+}
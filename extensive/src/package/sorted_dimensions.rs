@@ -0,0 +1,139 @@
+use crate::measurements::cm::Cm;
+use crate::package::dimensions::PackageDimensions;
+
+/**
+ * Side
+ *
+ * Names a side of a package by its rank rather than by its position,
+ * as dimensioners report the sides of a package in arbitrary order.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Side {
+    Longest, Middle, Shortest
+}
+
+/**
+ * SortedDimensions
+ *
+ * The canonical representation of the dimensions of a package, with its
+ * sides ordered from longest to shortest. Every measure derived from it
+ * is independent of the orientation in which the package was measured.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SortedDimensions {
+    longest: Cm,
+    middle: Cm,
+    shortest: Cm
+}
+
+impl From<&PackageDimensions> for SortedDimensions {
+    fn from(dimensions: &PackageDimensions) -> Self {
+        let mut sides = [dimensions.width().clone(), dimensions.height().clone(), dimensions.length().clone()];
+        sides.sort_unstable_by_key(|side| std::cmp::Reverse(side.value()));
+        let [longest, middle, shortest] = sides;
+
+        SortedDimensions {
+            longest,
+            middle,
+            shortest
+        }
+    }
+}
+
+impl SortedDimensions {
+    pub(crate) fn longest(&self) -> &Cm {
+        &self.longest
+    }
+
+    pub(crate) fn middle(&self) -> &Cm {
+        &self.middle
+    }
+
+    pub(crate) fn shortest(&self) -> &Cm {
+        &self.shortest
+    }
+
+    pub(crate) fn side(&self, side: &Side) -> &Cm {
+        match side {
+            Side::Longest => self.longest(),
+            Side::Middle => self.middle(),
+            Side::Shortest => self.shortest()
+        }
+    }
+
+    pub(crate) fn sum(&self) -> Cm {
+        self.longest.clone() + self.middle.clone() + self.shortest.clone()
+    }
+
+    // the longest side as length, plus the girth around the two other sides
+    pub(crate) fn length_plus_girth(&self) -> usize {
+        self.longest.value().saturating_add(
+            self.middle.value().saturating_add(self.shortest.value()).saturating_mul(2)
+        )
+    }
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Side::Longest => write!(f, "longest side"),
+            Side::Middle => write!(f, "middle side"),
+            Side::Shortest => write!(f, "shortest side")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::test_dependencies::ParcelDimensions;
+
+    fn sorted(width: usize, height: usize, length: usize) -> SortedDimensions {
+        let cm = |value| Cm::new(value).unwrap();
+        SortedDimensions::from(&PackageDimensions::new(cm(width), cm(height), cm(length)))
+    }
+
+    #[test]
+    fn sides_are_ranked_from_longest_to_shortest() {
+        let dimensions = sorted(30, 100, 20);
+        assert_eq!(dimensions.longest().value(), 100);
+        assert_eq!(dimensions.middle().value(), 30);
+        assert_eq!(dimensions.shortest().value(), 20);
+        assert_eq!(dimensions.side(&Side::Middle).value(), 30);
+    }
+
+    #[test]
+    fn equal_sides_keep_their_length() {
+        let dimensions = sorted(100, 20, 100);
+        assert_eq!(dimensions.longest().value(), 100);
+        assert_eq!(dimensions.middle().value(), 100);
+        assert_eq!(dimensions.shortest().value(), 20);
+    }
+
+    #[test]
+    fn measures_are_derived_from_the_ranked_sides() {
+        let dimensions = sorted(30, 100, 20);
+        assert_eq!(dimensions.sum().value(), 150);
+        assert_eq!(dimensions.length_plus_girth(), 200);
+        assert_eq!(sorted(usize::MAX, usize::MAX, 1).length_plus_girth(), usize::MAX);
+    }
+
+    #[quickcheck]
+    fn every_orientation_has_the_same_canonical_representation(dimensions: ParcelDimensions) -> bool {
+        let expected = SortedDimensions::from(&*dimensions);
+        dimensions.permutations().iter().all(|permutation| SortedDimensions::from(permutation) == expected)
+    }
+
+    #[quickcheck]
+    fn sides_are_ordered(dimensions: ParcelDimensions) -> bool {
+        let sorted = SortedDimensions::from(&*dimensions);
+        sorted.longest() >= sorted.middle() && sorted.middle() >= sorted.shortest()
+    }
+
+    #[test]
+    fn display_sides() {
+        assert_eq!(Side::Longest.to_string(), "longest side");
+        assert_eq!(Side::Middle.to_string(), "middle side");
+        assert_eq!(Side::Shortest.to_string(), "shortest side");
+    }
+}
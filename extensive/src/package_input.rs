@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::service_level::ServiceLevel;
+
+/**
+ * PackageInput
+ *
+ * The raw, unvalidated description of a package as reported by the
+ * sorting line: its measurements in cm and kg, the attributes it is
+ * labelled with, such as fragile or hazmat, and optionally its
+ * destination and service level.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackageInput {
+    width: usize,
+    height: usize,
+    length: usize,
+    mass: usize,
+    attributes: BTreeSet<Attribute>,
+    destination: Option<Destination>,
+    service_level: Option<ServiceLevel>
+}
+
+impl PackageInput {
+    pub fn new(width: usize, height: usize, length: usize, mass: usize) -> Self {
+        PackageInput {
+            width,
+            height,
+            length,
+            mass,
+            attributes: BTreeSet::new(),
+            destination: None,
+            service_level: None
+        }
+    }
+
+    pub fn with_attribute(mut self, attribute: Attribute) -> Self {
+        self.attributes.insert(attribute);
+        self
+    }
+
+    pub fn with_destination(mut self, destination: Destination) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
+    pub fn with_service_level(mut self, service_level: ServiceLevel) -> Self {
+        self.service_level = Some(service_level);
+        self
+    }
+
+    /**
+     * The same package with other measurements, as when part of its
+     * contents is packed separately
+     */
+    pub(crate) fn resized(&self, width: usize, height: usize, length: usize, mass: usize) -> Self {
+        PackageInput {
+            width,
+            height,
+            length,
+            mass,
+            ..self.clone()
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn mass(&self) -> usize {
+        self.mass
+    }
+
+    pub fn attributes(&self) -> &BTreeSet<Attribute> {
+        &self.attributes
+    }
+
+    pub fn destination(&self) -> Option<&Destination> {
+        self.destination.as_ref()
+    }
+
+    pub fn service_level(&self) -> Option<&ServiceLevel> {
+        self.service_level.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_are_deduplicated() {
+        let input = PackageInput::new(1, 2, 3, 4)
+            .with_attribute(Attribute::Fragile)
+            .with_attribute(Attribute::Hazmat)
+            .with_attribute(Attribute::Fragile);
+        assert_eq!(input.attributes().iter().collect::<Vec<_>>(), vec![&Attribute::Fragile, &Attribute::Hazmat]);
+    }
+
+    #[test]
+    fn resizing_keeps_everything_but_the_measurements() {
+        let input = PackageInput::new(1, 2, 3, 4)
+            .with_attribute(Attribute::Liquid)
+            .with_destination(Destination::new("DE").unwrap())
+            .with_service_level(ServiceLevel::Express);
+        let expected = PackageInput::new(5, 6, 7, 8)
+            .with_attribute(Attribute::Liquid)
+            .with_destination(Destination::new("DE").unwrap())
+            .with_service_level(ServiceLevel::Express);
+        assert_eq!(input.resized(5, 6, 7, 8), expected);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::measurements::cm::Cm;
+use crate::package::dimensions::PackageDimensions;
+use crate::package::Package;
+use crate::package_input::PackageInput;
+use crate::sort_decision::SortOutcome;
+use crate::sort_error::SortError;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::SortResult;
+
+/**
+ * BoxSize
+ *
+ * A box from the catalogue of a packing station, described by its
+ * outer dimensions in cm.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoxSize {
+    name: String,
+    dimensions: PackageDimensions
+}
+
+/**
+ * BoxSizeError
+ *
+ * Documents why a box could not be added to the catalogue: one of its
+ * dimensions is invalid.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoxSizeError {
+    InvalidDimension(String, usize)
+}
+
+/**
+ * Recommendation
+ *
+ * Documents how a package can be packed to be sorted as standard:
+ * - its contents can be repacked into a single, smaller box
+ * - its contents must be split by mass over several boxes of the same size
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recommendation {
+    Repack(BoxSize),
+    Split(BoxSize, usize)
+}
+
+impl BoxSize {
+    pub fn new<T: Into<String>>(name: T, width: usize, height: usize, length: usize) -> Result<Self, BoxSizeError> {
+        let name = name.into();
+        let cm = |value| Cm::new(value).map_err(|_| BoxSizeError::InvalidDimension(name.clone(), value));
+
+        Ok(BoxSize {
+            dimensions: PackageDimensions::new(cm(width)?, cm(height)?, cm(length)?),
+            name
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn width(&self) -> usize {
+        self.dimensions.width().value()
+    }
+
+    pub fn height(&self) -> usize {
+        self.dimensions.height().value()
+    }
+
+    pub fn length(&self) -> usize {
+        self.dimensions.length().value()
+    }
+
+    // boxes are turned as needed, so their sides are compared by rank
+    fn holds(&self, dimensions: &PackageDimensions) -> bool {
+        let (inside, outside) = (dimensions.sorted(), self.dimensions.sorted());
+        inside.longest() <= outside.longest()
+            && inside.middle() <= outside.middle()
+            && inside.shortest() <= outside.shortest()
+    }
+
+    // sorts the package packed into this box, so that its rules apply as well
+    fn is_standard(&self, input: &PackageInput, kg: usize, policy: &SortPolicy) -> bool {
+        let boxed = input.resized(self.width(), self.height(), self.length(), kg.max(1));
+        match crate::sort_package(&boxed, policy).expect("boxes hold valid measurements").outcome() {
+            SortOutcome::Sorted(result) => policy.rules().built_in(result) == SortResult::Standard,
+            _ => false
+        }
+    }
+}
+
+/**
+ * Recommends the smallest box of the catalogue that holds the package
+ * and keeps it standard, with certainty, under the given policy. Every
+ * box is sorted with the attributes, destination and service level of
+ * the package, so the rules of the policy apply to it as well.
+ *
+ * When no single box will do, the contents are assumed to be divisible
+ * and a split over the fewest boxes of a single size is recommended,
+ * dividing the mass evenly and requiring as much volume as the package.
+ * Smaller boxes are preferred when several splits need as many boxes.
+ */
+pub(crate) fn recommend(input: &PackageInput, catalogue: &[BoxSize], policy: &SortPolicy) -> Result<Option<Recommendation>, SortError> {
+    // validates the package itself before recommending
+    let package = crate::package(input)?;
+
+    let mut boxes: Vec<&BoxSize> = catalogue.iter().collect();
+    boxes.sort_by_key(|candidate| (candidate.dimensions.volume(), candidate.dimensions.sum().value()));
+
+    let repack = boxes.iter()
+        .find(|candidate| candidate.holds(package.dimensions()) && candidate.is_standard(input, input.mass(), policy));
+    if let Some(candidate) = repack {
+        return Ok(Some(Recommendation::Repack((*candidate).clone())));
+    }
+
+    Ok(
+        boxes.iter()
+            .filter_map(|candidate| split(candidate, input, &package, policy).map(|count| (count, *candidate)))
+            .min_by_key(|(count, _)| *count)
+            .map(|(count, candidate)| Recommendation::Split(candidate.clone(), count))
+    )
+}
+
+// the fewest boxes of the given size that the package can be split over
+fn split(candidate: &BoxSize, input: &PackageInput, package: &Package, policy: &SortPolicy) -> Option<usize> {
+    let mass = input.mass();
+
+    // the mass per box only decreases as boxes are added
+    let (mut fewest, mut most) = (2, mass.max(2));
+    if !candidate.is_standard(input, mass.div_ceil(most), policy) {
+        return None;
+    }
+    while fewest < most {
+        let count = fewest + (most - fewest) / 2;
+        match candidate.is_standard(input, mass.div_ceil(count), policy) {
+            true => most = count,
+            false => fewest = count + 1
+        }
+    }
+
+    let by_volume = package.dimensions().volume().div_ceil(candidate.dimensions.volume());
+    Some(most.max(usize::try_from(by_volume).unwrap_or(usize::MAX)))
+}
+
+impl std::fmt::Display for BoxSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({}x{}x{} cm)", self.name, self.width(), self.height(), self.length())
+    }
+}
+
+impl std::fmt::Display for Recommendation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Recommendation::Repack(candidate) => write!(f, "repack into {}", candidate),
+            Recommendation::Split(candidate, count) => write!(f, "split over {} boxes {}", count, candidate)
+        }
+    }
+}
+
+impl std::fmt::Display for BoxSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BoxSizeError::InvalidDimension(name, value) => write!(f, "Invalid dimension for box {}: expecting a value of 1 or more, but got {}", name, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::attributes::Attribute;
+    use crate::package::dimensional_weight::DimensionalDivisor;
+    use crate::package::sorted_dimensions::Side;
+    use crate::sort_policy::rules::{EvaluationMode, Predicate, Rule, RuleSet};
+    use crate::sort_policy::tolerance::Tolerance;
+
+    fn package(width: usize, height: usize, length: usize, mass: usize) -> PackageInput {
+        PackageInput::new(width, height, length, mass)
+    }
+
+    fn catalogue() -> Vec<BoxSize> {
+        vec![
+            BoxSize::new("large", 60, 40, 40).unwrap(),
+            BoxSize::new("small", 30, 20, 10).unwrap(),
+            BoxSize::new("medium", 40, 30, 30).unwrap(),
+            BoxSize::new("oversize", 100, 60, 60).unwrap()
+        ]
+    }
+
+    #[test]
+    fn packages_are_repacked_into_the_smallest_box_holding_them() {
+        let contents = package(35, 25, 20, 5);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &SortPolicy::default()),
+            Ok(Some(Recommendation::Repack(BoxSize::new("medium", 40, 30, 30).unwrap())))
+        );
+    }
+
+    #[test]
+    fn boxes_are_turned_to_hold_the_contents() {
+        let contents = package(10, 30, 20, 1);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &SortPolicy::default()),
+            Ok(Some(Recommendation::Repack(BoxSize::new("small", 30, 20, 10).unwrap())))
+        );
+    }
+
+    #[test]
+    fn bulky_packages_are_split_over_the_fewest_boxes() {
+        // only the oversize box holds it, and it is bulky itself
+        let contents = package(90, 50, 50, 5);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &SortPolicy::default()),
+            Ok(Some(Recommendation::Split(BoxSize::new("large", 60, 40, 40).unwrap(), 3)))
+        );
+    }
+
+    #[test]
+    fn heavy_packages_are_split_by_mass() {
+        let contents = package(30, 20, 10, 50);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &SortPolicy::default()),
+            Ok(Some(Recommendation::Split(BoxSize::new("small", 30, 20, 10).unwrap(), 3)))
+        );
+    }
+
+    #[test]
+    fn boxes_within_tolerance_of_a_threshold_are_avoided() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_mass(1));
+        let contents = package(30, 20, 10, 19);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &policy),
+            Ok(Some(Recommendation::Split(BoxSize::new("small", 30, 20, 10).unwrap(), 2)))
+        );
+    }
+
+    #[test]
+    fn dimensional_weight_is_considered() {
+        // the large box weighs 20 kg dimensionally, the medium one 8 kg
+        let policy = SortPolicy::default().with_dimensional_weight(DimensionalDivisor::EXPRESS);
+        let contents = package(50, 35, 35, 5);
+        assert_eq!(
+            recommend(&contents, &catalogue(), &policy),
+            Ok(Some(Recommendation::Split(BoxSize::new("medium", 40, 30, 30).unwrap(), 2)))
+        );
+    }
+
+    #[test]
+    fn the_rules_of_the_policy_are_applied_to_every_box() {
+        // the medium box would hold the contents, but is sorted as special
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("long", SortResult::Special).when(Predicate::SideAtLeast(Side::Longest, 40)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules);
+        assert_eq!(
+            recommend(&package(35, 25, 20, 5), &catalogue(), &policy),
+            Ok(Some(Recommendation::Split(BoxSize::new("small", 30, 20, 10).unwrap(), 3)))
+        );
+    }
+
+    #[test]
+    fn the_attributes_of_the_package_are_kept_in_every_box() {
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("hazmat", SortResult::Special).when(Predicate::HasAttribute(Attribute::Hazmat)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules);
+        assert_eq!(recommend(&package(35, 25, 20, 5).with_attribute(Attribute::Hazmat), &catalogue(), &policy), Ok(None));
+    }
+
+    #[test]
+    fn nothing_is_recommended_without_a_standard_box() {
+        let catalogue = vec![BoxSize::new("oversize", 100, 60, 60).unwrap()];
+        assert_eq!(recommend(&package(90, 50, 50, 5), &catalogue, &SortPolicy::default()), Ok(None));
+    }
+
+    #[test]
+    fn invalid_packages_are_refused() {
+        assert_eq!(recommend(&package(30, 20, 0, 5), &catalogue(), &SortPolicy::default()), Err(SortError::InvalidLength(0)));
+    }
+
+    #[test]
+    fn invalid_boxes_are_refused() {
+        assert_eq!(BoxSize::new("flat", 30, 0, 10), Err(BoxSizeError::InvalidDimension("flat".to_string(), 0)));
+    }
+
+    #[test]
+    fn display_recommendations() {
+        let small = BoxSize::new("small", 30, 20, 10).unwrap();
+        assert_eq!(Recommendation::Repack(small.clone()).to_string(), "repack into small (30x20x10 cm)");
+        assert_eq!(Recommendation::Split(small, 3).to_string(), "split over 3 boxes small (30x20x10 cm)");
+        assert_eq!(
+            BoxSizeError::InvalidDimension("flat".to_string(), 0).to_string(),
+            "Invalid dimension for box flat: expecting a value of 1 or more, but got 0"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use crate::sort_policy::policy_file::{PolicyFileError, PolicyFormat};
+use crate::sort_policy::schedule::PolicySchedule;
+
+/**
+ * PolicyHolder
+ *
+ * Holds the policy loaded from a file for a long-running sorter. The
+ * file holds either a single policy or several versions of it, of which
+ * packages are sorted with the version in effect at the time. A
+ * reloaded policy is only swapped in once it has been validated, and
+ * sorts that started on the previous policy finish on it, as they hold
+ * their own reference to it.
+ */
+pub struct PolicyHolder {
+    path: PathBuf,
+    format: PolicyFormat,
+    current: RwLock<Arc<PolicySchedule>>,
+    source: Mutex<String>
+}
+
+/**
+ * PolicyEvent
+ *
+ * Documents what happened when a watched policy file changed:
+ * - the new version of the policy was swapped in
+ * - the file was invalid, and the current policy was kept
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyEvent {
+    Reloaded(String),
+    Rejected(PolicyFileError)
+}
+
+/**
+ * PolicyWatcher
+ *
+ * Polls a policy file for changes until it is dropped.
+ */
+pub struct PolicyWatcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl PolicyHolder {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PolicyFileError> {
+        let path = path.as_ref().to_path_buf();
+        let format = PolicyFormat::from_path(&path)
+            .ok_or_else(|| PolicyFileError::UnknownFormat(path.display().to_string()))?;
+        let source = read(&path)?;
+        let schedule = PolicySchedule::from_any_source(&source, format.clone())?;
+
+        Ok(PolicyHolder {
+            path,
+            format,
+            current: RwLock::new(Arc::new(schedule)),
+            source: Mutex::new(source)
+        })
+    }
+
+    /**
+     * The versions of the policy to sort with
+     *
+     * Callers should sort a package with a single snapshot, so that a
+     * reload halfway through doesn't mix two versions of the file.
+     */
+    pub fn current(&self) -> Arc<PolicySchedule> {
+        self.current.read().unwrap().clone()
+    }
+
+    /**
+     * Reloads the policy when the contents of its file changed
+     *
+     * Returns the versions of the new policy, or nothing when the file
+     * didn't change. An invalid file leaves the current policy in place.
+     */
+    pub fn reload_if_changed(&self) -> Result<Option<String>, PolicyFileError> {
+        let mut source = self.source.lock().unwrap();
+        let latest = read(&self.path)?;
+        if *source == latest {
+            return Ok(None);
+        }
+
+        let schedule = PolicySchedule::from_any_source(&latest, self.format.clone())?;
+        let versions = versions(&schedule);
+        *self.current.write().unwrap() = Arc::new(schedule);
+        *source = latest;
+
+        Ok(Some(versions))
+    }
+
+    /**
+     * Polls the policy file at the given interval, reporting every
+     * reload or rejected change to the listener
+     */
+    pub fn watch<F>(self: &Arc<Self>, interval: Duration, listener: F) -> PolicyWatcher
+    where
+        F: Fn(PolicyEvent) + Send + 'static
+    {
+        let holder = Arc::clone(self);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        let thread = std::thread::spawn(move || {
+            // a rejected file is only reported once, until it changes again
+            let mut rejected: Option<PolicyFileError> = None;
+            while !stopped.load(Ordering::Relaxed) {
+                match holder.reload_if_changed() {
+                    Ok(Some(version)) => {
+                        rejected = None;
+                        listener(PolicyEvent::Reloaded(version));
+                    },
+                    Ok(None) => {},
+                    Err(error) => {
+                        if rejected.as_ref() != Some(&error) {
+                            listener(PolicyEvent::Rejected(error.clone()));
+                            rejected = Some(error);
+                        }
+                    }
+                }
+                std::thread::sleep(interval);
+            }
+        });
+
+        PolicyWatcher {
+            stop,
+            thread: Some(thread)
+        }
+    }
+}
+
+impl Drop for PolicyWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn versions(schedule: &PolicySchedule) -> String {
+    schedule.versions().iter().map(|(_, policy)| policy.version()).collect::<Vec<_>>().join(", ")
+}
+
+fn read(path: &Path) -> Result<String, PolicyFileError> {
+    std::fs::read_to_string(path)
+        .map_err(|e| PolicyFileError::Unreadable(format!("{}: {}", path.display(), e)))
+}
+
+impl std::fmt::Display for PolicyEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyEvent::Reloaded(version) => write!(f, "Reloaded policy version {}", version),
+            PolicyEvent::Rejected(error) => write!(f, "Rejected policy change: {}", error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dependencies::scratch::ScratchFile;
+    use chrono::Utc;
+    use std::sync::mpsc;
+
+    fn policy_file(name: &str, version: &str, heavy_kg: usize) -> ScratchFile {
+        let path = ScratchFile::new(&format!("holder-{}.toml", name));
+        write(&path, version, heavy_kg);
+        path
+    }
+
+    // renamed into place, so that the watcher never reads a partially written file
+    fn write(path: &Path, version: &str, heavy_kg: usize) {
+        let source = format!("version = \"{}\"\n\n[thresholds]\nbulky_cm = 150\nheavy_kg = {}\n", version, heavy_kg);
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, source).unwrap();
+        std::fs::rename(&partial, path).unwrap();
+    }
+
+    fn version_in_effect(holder: &PolicyHolder) -> String {
+        holder.current().policy_at(Utc::now()).unwrap().version().to_string()
+    }
+
+    #[test]
+    fn the_policy_is_loaded_from_its_file() {
+        let path = policy_file("load", "v1", 20);
+        let holder = PolicyHolder::load(&path).unwrap();
+        assert_eq!(version_in_effect(&holder), "v1");
+    }
+
+    #[test]
+    fn the_version_in_effect_is_picked_from_several() {
+        let path = ScratchFile::new("holder-versions.toml");
+        let versions = "[[versions]]\nversion = \"v1\"\neffective_from = 2025-01-01T00:00:00Z\n\n[[versions]]\nversion = \"v2\"\neffective_from = 2999-01-01T00:00:00Z\n";
+        std::fs::write(&path, versions).unwrap();
+        let holder = PolicyHolder::load(&path).unwrap();
+        assert_eq!(version_in_effect(&holder), "v1");
+
+        write(&path, "v3", 18);
+        assert_eq!(holder.reload_if_changed(), Ok(Some("v3".to_string())));
+        std::fs::write(&path, versions).unwrap();
+        assert_eq!(holder.reload_if_changed(), Ok(Some("v1, v2".to_string())));
+    }
+
+    #[test]
+    fn unchanged_files_are_not_reloaded() {
+        let path = policy_file("unchanged", "v1", 20);
+        let holder = PolicyHolder::load(&path).unwrap();
+        assert_eq!(holder.reload_if_changed(), Ok(None));
+    }
+
+    #[test]
+    fn changed_files_are_swapped_in_while_snapshots_keep_their_version() {
+        let path = policy_file("changed", "v1", 20);
+        let holder = PolicyHolder::load(&path).unwrap();
+        let in_flight = holder.current();
+
+        write(&path, "v2", 18);
+        assert_eq!(holder.reload_if_changed(), Ok(Some("v2".to_string())));
+        assert_eq!(version_in_effect(&holder), "v2");
+        assert_eq!(in_flight.versions()[0].1.version(), "v1");
+    }
+
+    #[test]
+    fn invalid_files_keep_the_current_policy() {
+        let path = policy_file("invalid", "v1", 20);
+        let holder = PolicyHolder::load(&path).unwrap();
+
+        write(&path, "v2", 0);
+        assert!(matches!(holder.reload_if_changed(), Err(PolicyFileError::Invalid { line: Some(5), .. })));
+        assert_eq!(version_in_effect(&holder), "v1");
+    }
+
+    #[test]
+    fn watched_files_are_reloaded_in_the_background() {
+        let path = policy_file("watched", "v1", 20);
+        let holder = Arc::new(PolicyHolder::load(&path).unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let _watcher = holder.watch(Duration::from_millis(10), move |event| { let _ = sender.send(event); });
+
+        write(&path, "v2", 0);
+        assert!(matches!(receiver.recv_timeout(Duration::from_secs(5)), Ok(PolicyEvent::Rejected(_))));
+
+        write(&path, "v3", 18);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(PolicyEvent::Reloaded("v3".to_string())));
+        assert_eq!(version_in_effect(&holder), "v3");
+    }
+
+    #[test]
+    fn display_events() {
+        assert_eq!(PolicyEvent::Reloaded("v2".to_string()).to_string(), "Reloaded policy version v2");
+        assert_eq!(
+            PolicyEvent::Rejected(PolicyFileError::UnknownFormat("policy.ini".to_string())).to_string(),
+            "Rejected policy change: Unknown policy format: expecting a .toml, .yaml, .yml or .json file, but got policy.ini"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::io::Write;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::sort_decision::SortDecision;
+use crate::sort_policy::schedule::format_timestamp;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::{SortResult, StackId};
+
+/**
+ * Reclassification
+ *
+ * A supervisor overruling the automatic decision for a package,
+ * documenting who sent it onto which stack, when and why.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reclassification {
+    package: String,
+    result: SortResult,
+    supervisor: String,
+    reason: String,
+    at: DateTime<Utc>
+}
+
+/**
+ * OverriddenDecision
+ *
+ * An automatic decision that was overruled, keeping both the automatic
+ * decision and the reclassification that replaced its result.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverriddenDecision {
+    automatic: SortDecision,
+    reclassification: Reclassification
+}
+
+/**
+ * ReclassificationError
+ *
+ * Documents why a decision could not be overruled:
+ * - the supervisor or the reason is missing
+ * - the package is sent onto a custom stack the policy doesn't declare
+ * - the audit sink failed to record it
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReclassificationError {
+    MissingSupervisor,
+    MissingReason,
+    UndeclaredStack(StackId),
+    Unrecorded(String)
+}
+
+/**
+ * AuditSink
+ *
+ * Records overridden decisions, so that every manual intervention can
+ * be traced back. A decision is only overridden once it was recorded.
+ */
+pub trait AuditSink {
+    fn record(&mut self, decision: &OverriddenDecision) -> std::io::Result<()>;
+}
+
+/**
+ * JsonLinesAuditSink
+ *
+ * Appends every overridden decision as a single line of JSON to a
+ * writer, typically a file opened in append mode.
+ */
+pub struct JsonLinesAuditSink<W: Write> {
+    writer: W
+}
+
+// the line written for every overridden decision
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    package: &'a str,
+    policy_version: &'a str,
+    automatic: String,
+    result: &'a SortResult,
+    supervisor: &'a str,
+    reason: &'a str,
+    at: String
+}
+
+impl Reclassification {
+    pub fn new<P, S, R>(package: P, result: SortResult, supervisor: S, reason: R, at: DateTime<Utc>) -> Self
+    where
+        P: Into<String>,
+        S: Into<String>,
+        R: Into<String>
+    {
+        Reclassification {
+            package: package.into(),
+            result,
+            supervisor: supervisor.into(),
+            reason: reason.into(),
+            at
+        }
+    }
+
+    /**
+     * Overrules the automatic decision, recording it with the sink
+     */
+    pub(crate) fn apply(self, automatic: &SortDecision, policy: &SortPolicy, sink: &mut dyn AuditSink) -> Result<OverriddenDecision, ReclassificationError> {
+        if self.supervisor.trim().is_empty() {
+            return Err(ReclassificationError::MissingSupervisor);
+        }
+        if self.reason.trim().is_empty() {
+            return Err(ReclassificationError::MissingReason);
+        }
+        if let SortResult::Custom(id) = &self.result {
+            if !policy.rules().declares(id) {
+                return Err(ReclassificationError::UndeclaredStack(id.clone()));
+            }
+        }
+
+        let decision = OverriddenDecision {
+            automatic: automatic.clone(),
+            reclassification: self
+        };
+        sink.record(&decision).map_err(|e| ReclassificationError::Unrecorded(e.to_string()))?;
+
+        Ok(decision)
+    }
+}
+
+impl OverriddenDecision {
+    pub fn automatic(&self) -> &SortDecision {
+        &self.automatic
+    }
+
+    pub fn result(&self) -> &SortResult {
+        &self.reclassification.result
+    }
+
+    pub fn package(&self) -> &str {
+        &self.reclassification.package
+    }
+
+    pub fn supervisor(&self) -> &str {
+        &self.reclassification.supervisor
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reclassification.reason
+    }
+
+    pub fn at(&self) -> &DateTime<Utc> {
+        &self.reclassification.at
+    }
+}
+
+impl AuditSink for Vec<OverriddenDecision> {
+    fn record(&mut self, decision: &OverriddenDecision) -> std::io::Result<()> {
+        self.push(decision.clone());
+        Ok(())
+    }
+}
+
+impl<W: Write> JsonLinesAuditSink<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesAuditSink { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> AuditSink for JsonLinesAuditSink<W> {
+    fn record(&mut self, decision: &OverriddenDecision) -> std::io::Result<()> {
+        let record = AuditRecord {
+            package: decision.package(),
+            policy_version: decision.automatic.policy_version(),
+            automatic: decision.automatic.outcome().to_string(),
+            result: decision.result(),
+            supervisor: decision.supervisor(),
+            reason: decision.reason(),
+            at: format_timestamp(decision.at())
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+impl std::fmt::Display for OverriddenDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (overridden by {}, automatically {})", self.result(), self.supervisor(), self.automatic)
+    }
+}
+
+impl std::fmt::Display for ReclassificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReclassificationError::MissingSupervisor => write!(f, "Missing supervisor: expecting the name of whoever overrides the decision"),
+            ReclassificationError::MissingReason => write!(f, "Missing reason: expecting why the decision is overridden"),
+            ReclassificationError::UndeclaredStack(id) => write!(f, "Undeclared stack: expecting a stack declared by the policy, but got {}", id),
+            ReclassificationError::Unrecorded(reason) => write!(f, "Unrecorded override: {}", reason)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort_decision::SortOutcome;
+
+    fn rejected() -> SortDecision {
+        SortDecision::new(SortOutcome::Sorted(SortResult::Rejected), vec![], "2026-10".to_string())
+    }
+
+    fn at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-19T14:30:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    fn inspected(result: SortResult) -> Reclassification {
+        Reclassification::new("PKG-1", result, "alex", "inspected, contents can be lifted", at())
+    }
+
+    struct FailingSink;
+
+    impl AuditSink for FailingSink {
+        fn record(&mut self, _decision: &OverriddenDecision) -> std::io::Result<()> {
+            Err(std::io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn overridden_decisions_keep_the_automatic_decision() {
+        let mut sink = vec![];
+        let decision = inspected(SortResult::Special).apply(&rejected(), &SortPolicy::default(), &mut sink).unwrap();
+
+        assert_eq!(decision.automatic(), &rejected());
+        assert_eq!(decision.result(), &SortResult::Special);
+        assert_eq!((decision.package(), decision.supervisor(), decision.at()), ("PKG-1", "alex", &at()));
+        assert_eq!(decision.reason(), "inspected, contents can be lifted");
+        assert_eq!(sink, vec![decision]);
+    }
+
+    #[test]
+    fn overrides_must_be_accounted_for() {
+        let mut sink = vec![];
+        let anonymous = Reclassification::new("PKG-1", SortResult::Special, " ", "inspected", at());
+        assert_eq!(anonymous.apply(&rejected(), &SortPolicy::default(), &mut sink), Err(ReclassificationError::MissingSupervisor));
+        let unexplained = Reclassification::new("PKG-1", SortResult::Special, "alex", "", at());
+        assert_eq!(unexplained.apply(&rejected(), &SortPolicy::default(), &mut sink), Err(ReclassificationError::MissingReason));
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn overrides_must_target_a_declared_stack() {
+        let lift = StackId::new("two-person-lift").unwrap();
+        assert_eq!(
+            inspected(SortResult::Custom(lift.clone())).apply(&rejected(), &SortPolicy::default(), &mut vec![]),
+            Err(ReclassificationError::UndeclaredStack(lift))
+        );
+    }
+
+    #[test]
+    fn unrecorded_overrides_are_not_applied() {
+        assert_eq!(
+            inspected(SortResult::Special).apply(&rejected(), &SortPolicy::default(), &mut FailingSink),
+            Err(ReclassificationError::Unrecorded("disk full".to_string()))
+        );
+    }
+
+    #[test]
+    fn overrides_are_appended_as_json_lines() {
+        let mut sink = JsonLinesAuditSink::new(Vec::new());
+        inspected(SortResult::Special).apply(&rejected(), &SortPolicy::default(), &mut sink).unwrap();
+        inspected(SortResult::Standard).apply(&rejected(), &SortPolicy::default(), &mut sink).unwrap();
+
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "\
+{\"package\":\"PKG-1\",\"policy_version\":\"2026-10\",\"automatic\":\"rejected\",\"result\":\"special\",\"supervisor\":\"alex\",\"reason\":\"inspected, contents can be lifted\",\"at\":\"2026-10-19T14:30:00Z\"}
+{\"package\":\"PKG-1\",\"policy_version\":\"2026-10\",\"automatic\":\"rejected\",\"result\":\"standard\",\"supervisor\":\"alex\",\"reason\":\"inspected, contents can be lifted\",\"at\":\"2026-10-19T14:30:00Z\"}
+");
+    }
+
+    #[test]
+    fn display_overridden_decision() {
+        let decision = inspected(SortResult::Special).apply(&rejected(), &SortPolicy::default(), &mut vec![]).unwrap();
+        assert_eq!(decision.to_string(), "special (overridden by alex, automatically rejected)");
+    }
+
+    #[test]
+    fn display_reclassification_errors() {
+        assert_eq!(ReclassificationError::MissingReason.to_string(), "Missing reason: expecting why the decision is overridden");
+        assert_eq!(ReclassificationError::Unrecorded("disk full".to_string()).to_string(), "Unrecorded override: disk full");
+    }
+}
@@ -0,0 +1,344 @@
+use crate::package_input::PackageInput;
+use crate::sort_decision::{SortDecision, SortOutcome};
+use crate::sort_error::SortError;
+use crate::sort_policy::rules::RuleSet;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::SortResult;
+
+/**
+ * Shipment
+ *
+ * A handling unit made up of several pieces, such as a multi-piece
+ * shipment or a pallet, which is sorted as a whole.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shipment {
+    pieces: Vec<PackageInput>
+}
+
+/**
+ * RollUp
+ *
+ * Configures how the results of the pieces of a shipment are rolled
+ * up into the result of the shipment. A shipment goes onto the most
+ * severe stack of its pieces, so that a single rejected piece rejects
+ * the whole shipment. Shipments whose total mass reaches one of the
+ * configured thresholds go at least onto the matching stack.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RollUp {
+    total_mass: Vec<(usize, SortResult)>
+}
+
+/**
+ * ShipmentDecision
+ *
+ * The outcome of sorting a shipment, along with the decision taken
+ * for every one of its pieces, in the order they were added.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipmentDecision {
+    outcome: SortOutcome,
+    pieces: Vec<SortDecision>,
+    total_mass: usize,
+    policy_version: String
+}
+
+impl Shipment {
+    pub fn new() -> Self {
+        Shipment::default()
+    }
+
+    pub fn with_piece(mut self, piece: PackageInput) -> Self {
+        self.pieces.push(piece);
+        self
+    }
+
+    pub fn pieces(&self) -> &[PackageInput] {
+        &self.pieces
+    }
+
+    pub fn total_mass(&self) -> usize {
+        self.pieces.iter().fold(0, |total, piece| total.saturating_add(piece.mass()))
+    }
+
+    /**
+     * Sorts every piece under the given policy, and rolls their results
+     * up into the result of the shipment
+     *
+     * Pieces that need remeasuring hold up the whole shipment. When any
+     * piece is uncertain, the shipment is uncertain as soon as its
+     * lenient and conservative results differ.
+     */
+    pub(crate) fn sort(&self, policy: &SortPolicy, roll_up: &RollUp) -> Result<ShipmentDecision, SortError> {
+        if self.pieces.is_empty() {
+            return Err(SortError::MissingPieces);
+        }
+        roll_up.check(policy.rules())?;
+
+        let pieces = self.pieces.iter()
+            .enumerate()
+            .map(|(index, piece)| crate::sort_package(piece, policy)
+                .map_err(|error| SortError::InvalidPiece(index + 1, Box::new(error))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let total_mass = self.total_mass();
+
+        Ok(ShipmentDecision {
+            outcome: roll_up.outcome(&pieces, total_mass, policy.rules()),
+            pieces,
+            total_mass,
+            policy_version: policy.version().to_string()
+        })
+    }
+}
+
+impl RollUp {
+    /**
+     * Sends shipments weighing `kg` or more in total at least onto the
+     * given stack
+     */
+    pub fn with_total_mass_at_least(mut self, kg: usize, result: SortResult) -> Self {
+        self.total_mass.push((kg, result));
+        self
+    }
+
+    // custom stacks are ranked by the policy, which must declare them
+    fn check(&self, rules: &RuleSet) -> Result<(), SortError> {
+        match self.total_mass.iter().find_map(|(_, result)| match result {
+            SortResult::Custom(id) if !rules.declares(id) => Some(id),
+            _ => None
+        }) {
+            Some(id) => Err(SortError::UndeclaredStack(id.clone())),
+            None => Ok(())
+        }
+    }
+
+    fn outcome(&self, pieces: &[SortDecision], total_mass: usize, rules: &RuleSet) -> SortOutcome {
+        let remeasure = pieces.iter().find_map(|piece| match piece.outcome() {
+            SortOutcome::NeedsRemeasure(reason) => Some(reason.clone()),
+            _ => None
+        });
+        if let Some(reason) = remeasure {
+            return SortOutcome::NeedsRemeasure(reason);
+        }
+
+        let lenient = self.result(pieces.iter().map(|piece| lenient(piece.outcome())), total_mass, rules);
+        let conservative = self.result(pieces.iter().map(|piece| conservative(piece.outcome())), total_mass, rules);
+
+        match lenient == conservative {
+            true => SortOutcome::Sorted(conservative),
+            false => SortOutcome::Uncertain { lenient, conservative }
+        }
+    }
+
+    fn result<'a>(&'a self, pieces: impl Iterator<Item = &'a SortResult>, total_mass: usize, rules: &RuleSet) -> SortResult {
+        let escalations = self.total_mass.iter()
+            .filter(|(kg, _)| total_mass >= *kg)
+            .map(|(_, result)| result);
+
+        pieces.chain(escalations)
+            .max_by_key(|result| rules.severity(result))
+            .cloned()
+            .expect("shipments hold at least one piece")
+    }
+}
+
+fn lenient(outcome: &SortOutcome) -> &SortResult {
+    match outcome {
+        SortOutcome::Sorted(result) => result,
+        SortOutcome::Uncertain { lenient, .. } => lenient,
+        SortOutcome::NeedsRemeasure(_) => unreachable!("pieces that need remeasuring hold up the shipment")
+    }
+}
+
+fn conservative(outcome: &SortOutcome) -> &SortResult {
+    match outcome {
+        SortOutcome::Sorted(result) => result,
+        SortOutcome::Uncertain { conservative, .. } => conservative,
+        SortOutcome::NeedsRemeasure(_) => unreachable!("pieces that need remeasuring hold up the shipment")
+    }
+}
+
+impl ShipmentDecision {
+    pub fn outcome(&self) -> &SortOutcome {
+        &self.outcome
+    }
+
+    pub fn pieces(&self) -> &[SortDecision] {
+        &self.pieces
+    }
+
+    pub fn total_mass(&self) -> usize {
+        self.total_mass
+    }
+
+    pub fn policy_version(&self) -> &str {
+        &self.policy_version
+    }
+}
+
+impl std::fmt::Display for ShipmentDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let pieces: Vec<String> = self.pieces.iter().map(|piece| piece.to_string()).collect();
+        write!(f, "{} (pieces: {})", self.outcome, pieces.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::DimensionClass;
+    use crate::sort_policy::plausibility::{Implausibility, PlausibilityLimits};
+    use crate::sort_policy::rules::{CustomStack, EvaluationMode, Predicate, Rule};
+    use crate::sort_policy::tolerance::Tolerance;
+    use crate::sort_result::StackId;
+    use quickcheck::{Arbitrary, Gen};
+
+    fn standard() -> PackageInput {
+        PackageInput::new(30, 20, 10, 5)
+    }
+
+    fn special() -> PackageInput {
+        PackageInput::new(100, 40, 40, 5)
+    }
+
+    fn rejected() -> PackageInput {
+        PackageInput::new(100, 40, 40, 25)
+    }
+
+    fn sort(shipment: &Shipment, roll_up: &RollUp) -> Result<ShipmentDecision, SortError> {
+        shipment.sort(&SortPolicy::default(), roll_up)
+    }
+
+    #[test]
+    fn shipments_go_onto_the_most_severe_stack_of_their_pieces() {
+        let shipment = Shipment::new().with_piece(standard()).with_piece(special()).with_piece(standard());
+        let decision = sort(&shipment, &RollUp::default()).unwrap();
+
+        assert_eq!(decision.outcome(), &SortOutcome::Sorted(SortResult::Special));
+        let pieces: Vec<_> = decision.pieces().iter().map(|piece| piece.outcome().clone()).collect();
+        assert_eq!(pieces, vec![
+            SortOutcome::Sorted(SortResult::Standard),
+            SortOutcome::Sorted(SortResult::Special),
+            SortOutcome::Sorted(SortResult::Standard)
+        ]);
+        assert_eq!(decision.policy_version(), "default");
+    }
+
+    #[quickcheck]
+    fn any_rejected_piece_rejects_the_shipment(pieces: Pieces, position: usize) -> bool {
+        let mut pieces = pieces.0;
+        pieces.insert(position % (pieces.len() + 1), rejected());
+        let shipment = pieces.into_iter().fold(Shipment::new(), Shipment::with_piece);
+
+        sort(&shipment, &RollUp::default()).unwrap().outcome() == &SortOutcome::Sorted(SortResult::Rejected)
+    }
+
+    #[test]
+    fn heavy_shipments_are_escalated() {
+        let roll_up = RollUp::default()
+            .with_total_mass_at_least(15, SortResult::Special)
+            .with_total_mass_at_least(40, SortResult::Rejected);
+
+        let light = Shipment::new().with_piece(standard()).with_piece(standard());
+        let heavy = light.clone().with_piece(standard());
+        let very_heavy = (0..8).fold(Shipment::new(), |shipment, _| shipment.with_piece(standard()));
+
+        assert_eq!(sort(&light, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Standard));
+        assert_eq!(sort(&heavy, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Special));
+        assert_eq!(sort(&very_heavy, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Rejected));
+        assert_eq!(very_heavy.total_mass(), 40);
+    }
+
+    #[test]
+    fn escalations_never_lower_the_result() {
+        let roll_up = RollUp::default().with_total_mass_at_least(1, SortResult::Standard);
+        let shipment = Shipment::new().with_piece(rejected());
+        assert_eq!(sort(&shipment, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Rejected));
+    }
+
+    #[test]
+    fn custom_stacks_are_ranked_by_the_policy() {
+        let lift = SortResult::Custom(StackId::new("two-person-lift").unwrap());
+        let rules = RuleSet::with_stacks(EvaluationMode::FirstMatch, vec![CustomStack::new(StackId::new("two-person-lift").unwrap(), SortResult::Special)], vec![
+            Rule::new("bulky", SortResult::Special).when(Predicate::DimensionClass(DimensionClass::BULKY)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules);
+        let shipment = Shipment::new().with_piece(standard()).with_piece(special());
+
+        let roll_up = RollUp::default().with_total_mass_at_least(10, lift.clone());
+        assert_eq!(shipment.sort(&policy, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(lift));
+
+        let roll_up = RollUp::default().with_total_mass_at_least(10, SortResult::Rejected);
+        assert_eq!(shipment.sort(&policy, &roll_up).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Rejected));
+    }
+
+    #[test]
+    fn escalations_onto_undeclared_stacks_are_refused() {
+        let lift = StackId::new("two-person-lift").unwrap();
+        let roll_up = RollUp::default().with_total_mass_at_least(10, SortResult::Custom(lift.clone()));
+        assert_eq!(sort(&Shipment::new().with_piece(standard()), &roll_up), Err(SortError::UndeclaredStack(lift)));
+    }
+
+    #[test]
+    fn uncertain_pieces_make_the_shipment_uncertain() {
+        let policy = SortPolicy::default().with_tolerance(Tolerance::default().with_mass(1));
+        let borderline = PackageInput::new(30, 20, 10, 20);
+
+        let shipment = Shipment::new().with_piece(standard()).with_piece(borderline.clone());
+        assert_eq!(
+            shipment.sort(&policy, &RollUp::default()).unwrap().outcome(),
+            &SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special }
+        );
+
+        let settled = Shipment::new().with_piece(special()).with_piece(borderline);
+        assert_eq!(settled.sort(&policy, &RollUp::default()).unwrap().outcome(), &SortOutcome::Sorted(SortResult::Special));
+    }
+
+    #[test]
+    fn pieces_that_need_remeasuring_hold_up_the_shipment() {
+        let policy = SortPolicy::default().with_plausibility(PlausibilityLimits::default());
+        let shipment = Shipment::new().with_piece(rejected()).with_piece(PackageInput::new(30, 20, 10, 2_000));
+
+        assert_eq!(
+            shipment.sort(&policy, &RollUp::default()).unwrap().outcome(),
+            &SortOutcome::NeedsRemeasure(Implausibility::TooHeavy(2_000, 1_000))
+        );
+    }
+
+    #[test]
+    fn invalid_pieces_are_reported_by_position() {
+        let shipment = Shipment::new().with_piece(standard()).with_piece(PackageInput::new(30, 0, 10, 5));
+        assert_eq!(sort(&shipment, &RollUp::default()), Err(SortError::InvalidPiece(2, Box::new(SortError::InvalidHeight(0)))));
+    }
+
+    #[test]
+    fn shipments_hold_at_least_one_piece() {
+        assert_eq!(sort(&Shipment::new(), &RollUp::default()), Err(SortError::MissingPieces));
+    }
+
+    #[test]
+    fn total_mass_saturates() {
+        let shipment = Shipment::new().with_piece(PackageInput::new(1, 1, 1, usize::MAX)).with_piece(standard());
+        assert_eq!(shipment.total_mass(), usize::MAX);
+    }
+
+    #[test]
+    fn display_shipment_decisions() {
+        let shipment = Shipment::new().with_piece(standard()).with_piece(rejected());
+        assert_eq!(sort(&shipment, &RollUp::default()).unwrap().to_string(), "rejected (pieces: standard, rejected)");
+    }
+
+    #[derive(Clone, Debug)]
+    struct Pieces(Vec<PackageInput>);
+
+    impl Arbitrary for Pieces {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let pieces = Vec::<bool>::arbitrary(g).into_iter()
+                .map(|is_special| if is_special { special() } else { standard() })
+                .collect();
+            Pieces(pieces)
+        }
+    }
+}
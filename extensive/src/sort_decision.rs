@@ -0,0 +1,179 @@
+use std::fmt::Formatter;
+use crate::package::attributes::Attribute;
+use crate::package::dimensions::DimensionClass;
+use crate::package::mass::MassClass;
+use crate::sort_policy::bulky_strategy::BulkyStrategy;
+use crate::sort_policy::plausibility::Implausibility;
+use crate::sort_result::SortResult;
+
+/**
+ * SortOutcome
+ *
+ * Documents the outcome of sorting a package under a policy:
+ * - the package was sorted onto one of the stacks
+ * - the measurements were implausible and the package must be remeasured
+ * - the measurements were within tolerance of a threshold, in which case
+ *   both the lenient and the conservative stack are documented so the
+ *   caller can pick one, or send the package for remeasurement
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortOutcome {
+    Sorted(SortResult),
+    NeedsRemeasure(Implausibility),
+    Uncertain { lenient: SortResult, conservative: SortResult }
+}
+
+/**
+ * TraceStep
+ *
+ * A single step taken while sorting a package, used to explain
+ * how the outcome was reached.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceStep {
+    DimensionMeasure(BulkyStrategy, usize),
+    DimensionClass(DimensionClass),
+    MassClass(MassClass),
+    AttributeConsulted(Attribute),
+    RuleMatched(String, SortResult)
+}
+
+/**
+ * SortDecision
+ *
+ * The outcome of sorting a package, along with the trace of the
+ * steps that led to it and the version of the policy that produced it.
+ * When the rules hand one out, the decision also carries the routing
+ * key used to sub-sort the packages within their stack; uncertain
+ * packages carry the key of their conservative stack.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortDecision {
+    outcome: SortOutcome,
+    trace: Vec<TraceStep>,
+    policy_version: String,
+    routing_key: Option<String>
+}
+
+impl SortOutcome {
+    /**
+     * The stack the package is sent to, routing uncertain packages
+     * conservatively. Packages that must be remeasured aren't sent to
+     * any stack.
+     */
+    pub fn stack(&self) -> Option<&SortResult> {
+        match self {
+            SortOutcome::Sorted(result) => Some(result),
+            SortOutcome::Uncertain { conservative, .. } => Some(conservative),
+            SortOutcome::NeedsRemeasure(_) => None
+        }
+    }
+}
+
+impl SortDecision {
+    pub(crate) fn new(outcome: SortOutcome, trace: Vec<TraceStep>, policy_version: String) -> Self {
+        SortDecision {
+            outcome,
+            trace,
+            policy_version,
+            routing_key: None
+        }
+    }
+
+    pub(crate) fn with_routing_key(mut self, routing_key: Option<String>) -> Self {
+        self.routing_key = routing_key;
+        self
+    }
+
+    pub fn policy_version(&self) -> &str {
+        &self.policy_version
+    }
+
+    pub fn outcome(&self) -> &SortOutcome {
+        &self.outcome
+    }
+
+    pub fn trace(&self) -> &[TraceStep] {
+        &self.trace
+    }
+
+    pub fn routing_key(&self) -> Option<&str> {
+        self.routing_key.as_deref()
+    }
+}
+
+impl std::fmt::Display for SortOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOutcome::Sorted(result) => write!(f, "{}", result),
+            SortOutcome::NeedsRemeasure(reason) => write!(f, "needs remeasure ({})", reason),
+            SortOutcome::Uncertain { lenient, conservative } => write!(f, "uncertain ({} or {})", lenient, conservative)
+        }
+    }
+}
+
+impl std::fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceStep::DimensionMeasure(strategy, cm) => write!(f, "{} is {} cm", strategy, cm),
+            TraceStep::DimensionClass(class) => write!(f, "dimensions are {}", class),
+            TraceStep::MassClass(class) => write!(f, "mass is {}", class),
+            TraceStep::AttributeConsulted(attribute) => write!(f, "package is labelled {}", attribute),
+            TraceStep::RuleMatched(rule, result) => write!(f, "rule '{}' matched: {}", rule, result)
+        }
+    }
+}
+
+impl std::fmt::Display for SortDecision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uncertain_packages_are_sent_to_their_conservative_stack() {
+        assert_eq!(SortOutcome::Sorted(SortResult::Special).stack(), Some(&SortResult::Special));
+        assert_eq!(SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special }.stack(), Some(&SortResult::Special));
+        assert_eq!(SortOutcome::NeedsRemeasure(Implausibility::TooHeavy(2_000, 1_000)).stack(), None);
+    }
+
+    #[test]
+    fn display_sorted() {
+        assert_eq!(SortOutcome::Sorted(SortResult::Special).to_string(), "special");
+    }
+
+    #[test]
+    fn display_needs_remeasure() {
+        assert_eq!(
+            SortOutcome::NeedsRemeasure(Implausibility::TooHeavy(2_000, 1_000)).to_string(),
+            "needs remeasure (Implausible mass: expecting at most 1000 kg, but got 2000 kg)"
+        );
+    }
+
+    #[test]
+    fn display_uncertain() {
+        assert_eq!(
+            SortOutcome::Uncertain { lenient: SortResult::Standard, conservative: SortResult::Special }.to_string(),
+            "uncertain (standard or special)"
+        );
+    }
+
+    #[test]
+    fn display_trace_steps() {
+        assert_eq!(TraceStep::DimensionMeasure(BulkyStrategy::LengthPlusGirth, 330).to_string(), "length plus girth is 330 cm");
+        assert_eq!(TraceStep::DimensionClass(DimensionClass::BULKY).to_string(), "dimensions are bulky");
+        assert_eq!(TraceStep::MassClass(MassClass::UNCERTAIN).to_string(), "mass is uncertain");
+        assert_eq!(TraceStep::AttributeConsulted(Attribute::Hazmat).to_string(), "package is labelled hazmat");
+        assert_eq!(TraceStep::RuleMatched("heavy".to_string(), SortResult::Special).to_string(), "rule 'heavy' matched: special");
+    }
+
+    #[test]
+    fn a_decision_displays_its_outcome() {
+        let decision = SortDecision::new(SortOutcome::Sorted(SortResult::Rejected), vec![], "default".to_string());
+        assert_eq!(decision.to_string(), "rejected");
+    }
+}
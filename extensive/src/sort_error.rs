@@ -1,17 +1,55 @@
+use chrono::{DateTime, Utc};
 use crate::measurements::kg::KgError;
+use crate::sort_policy::schedule::format_timestamp;
+use crate::sort_result::StackId;
 
 /**
  * SortError
  *
  * Documents the potential error states that can occur when
- * sorting a package. At this point, all of these are input errors.
+ * sorting a package or a shipment. Apart from sorting at a moment for
+ * which no policy was in effect, for a site that isn't registered, or
+ * rolling a shipment up onto a stack the policy doesn't declare, all
+ * of these are input errors.
  */
 #[derive(Clone, Debug, PartialEq)]
 pub enum SortError {
     InvalidHeight(usize),
     InvalidWidth(usize),
     InvalidLength(usize),
-    InvalidMass(usize)
+    InvalidMass(usize),
+    MissingDimensionReadings,
+    MissingMassReadings,
+    NoPolicyInEffect(DateTime<Utc>, DateTime<Utc>),
+    UnknownSite(String),
+    MissingPieces,
+    InvalidPiece(usize, Box<SortError>),
+    InvalidPackage(String, Box<SortError>),
+    UndeclaredStack(StackId),
+    InvalidUnitCount(usize)
+}
+
+impl SortError {
+    /**
+     * The name of the error state, regardless of the values it holds
+     */
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SortError::InvalidHeight(_) => "invalid_height",
+            SortError::InvalidWidth(_) => "invalid_width",
+            SortError::InvalidLength(_) => "invalid_length",
+            SortError::InvalidMass(_) => "invalid_mass",
+            SortError::MissingDimensionReadings => "missing_dimension_readings",
+            SortError::MissingMassReadings => "missing_mass_readings",
+            SortError::NoPolicyInEffect(_, _) => "no_policy_in_effect",
+            SortError::UnknownSite(_) => "unknown_site",
+            SortError::MissingPieces => "missing_pieces",
+            SortError::InvalidPiece(_, _) => "invalid_piece",
+            SortError::InvalidPackage(_, _) => "invalid_package",
+            SortError::UndeclaredStack(_) => "undeclared_stack",
+            SortError::InvalidUnitCount(_) => "invalid_unit_count"
+        }
+    }
 }
 
 impl std::fmt::Display for SortError {
@@ -20,7 +58,16 @@ impl std::fmt::Display for SortError {
             SortError::InvalidHeight(height) => write!(f, "Invalid height: expecting a value of 1 or more, but got {}", height),
             SortError::InvalidWidth(width) => write!(f, "Invalid width: expecting a value of 1 or more, but got {}", width),
             SortError::InvalidLength(length) => write!(f, "Invalid length: expecting a value of 1 or more, but got {}", length),
-            SortError::InvalidMass(mass) => write!(f, "Invalid mass: expecting a value of 1 or more, but got {}", mass)
+            SortError::InvalidMass(mass) => write!(f, "Invalid mass: expecting a value of 1 or more, but got {}", mass),
+            SortError::MissingDimensionReadings => write!(f, "Missing dimension readings: expecting at least one reading"),
+            SortError::MissingMassReadings => write!(f, "Missing mass readings: expecting at least one reading"),
+            SortError::NoPolicyInEffect(timestamp, earliest) => write!(f, "No policy in effect: expecting a moment from {} onwards, but got {}", format_timestamp(earliest), format_timestamp(timestamp)),
+            SortError::UnknownSite(site) => write!(f, "Unknown site: expecting a registered site or tenant, but got {}", site),
+            SortError::MissingPieces => write!(f, "Missing pieces: expecting at least one piece"),
+            SortError::InvalidPiece(position, error) => write!(f, "Invalid piece {}: {}", position, error),
+            SortError::InvalidPackage(id, error) => write!(f, "Invalid package {}: {}", id, error),
+            SortError::UndeclaredStack(id) => write!(f, "Undeclared stack: expecting a stack declared by the policy, but got {}", id),
+            SortError::InvalidUnitCount(count) => write!(f, "Invalid unit count: expecting a value of 1 or more, but got {}", count)
         }
     }
 }
@@ -37,6 +84,16 @@ impl From<KgError> for SortError {
 mod tests {
     use super::*;
 
+    #[quickcheck]
+    fn the_kind_ignores_the_values(height: usize) {
+        assert_eq!(SortError::InvalidHeight(height).kind(), "invalid_height");
+    }
+
+    #[test]
+    fn nested_errors_are_of_the_outer_kind() {
+        assert_eq!(SortError::InvalidPiece(2, Box::new(SortError::InvalidWidth(0))).kind(), "invalid_piece");
+    }
+
     #[quickcheck]
     fn display_invalid_height(height: usize) {
         assert_eq!(SortError::InvalidHeight(height).to_string(), format!("Invalid height: expecting a value of 1 or more, but got {}", height));
@@ -56,4 +113,59 @@ mod tests {
     fn display_invalid_mass(mass: usize) {
         assert_eq!(SortError::InvalidMass(mass).to_string(), format!("Invalid mass: expecting a value of 1 or more, but got {}", mass));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn display_missing_dimension_readings() {
+        assert_eq!(SortError::MissingDimensionReadings.to_string(), "Missing dimension readings: expecting at least one reading");
+    }
+
+    #[test]
+    fn display_missing_mass_readings() {
+        assert_eq!(SortError::MissingMassReadings.to_string(), "Missing mass readings: expecting at least one reading");
+    }
+
+    #[test]
+    fn display_no_policy_in_effect() {
+        let error = SortError::NoPolicyInEffect("2024-01-01T00:00:00Z".parse().unwrap(), "2025-01-01T00:00:00Z".parse().unwrap());
+        assert_eq!(error.to_string(), "No policy in effect: expecting a moment from 2025-01-01T00:00:00Z onwards, but got 2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn display_unknown_site() {
+        assert_eq!(SortError::UnknownSite("cdg".to_string()).to_string(), "Unknown site: expecting a registered site or tenant, but got cdg");
+    }
+
+    #[test]
+    fn display_missing_pieces() {
+        assert_eq!(SortError::MissingPieces.to_string(), "Missing pieces: expecting at least one piece");
+    }
+
+    #[test]
+    fn display_invalid_piece() {
+        assert_eq!(
+            SortError::InvalidPiece(2, Box::new(SortError::InvalidWidth(0))).to_string(),
+            "Invalid piece 2: Invalid width: expecting a value of 1 or more, but got 0"
+        );
+    }
+
+    #[test]
+    fn display_invalid_package() {
+        assert_eq!(
+            SortError::InvalidPackage("PKG-1".to_string(), Box::new(SortError::InvalidMass(0))).to_string(),
+            "Invalid package PKG-1: Invalid mass: expecting a value of 1 or more, but got 0"
+        );
+    }
+
+    #[test]
+    fn display_undeclared_stack() {
+        assert_eq!(
+            SortError::UndeclaredStack(StackId::new("two-person-lift").unwrap()).to_string(),
+            "Undeclared stack: expecting a stack declared by the policy, but got two-person-lift"
+        );
+    }
+
+    #[test]
+    fn display_invalid_unit_count() {
+        assert_eq!(SortError::InvalidUnitCount(0).to_string(), "Invalid unit count: expecting a value of 1 or more, but got 0");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::measurements::cm::Cm;
+use crate::measurements::kg::Kg;
+use crate::package::dimensional_weight::DimensionalDivisor;
+use crate::sort_policy::bulky_strategy::BulkyStrategy;
+use crate::package::dimensions::PackageDimensions;
+use crate::package::fusion::Fusion;
+use crate::package::mass::Mass;
+use crate::sort_policy::plausibility::{Implausibility, PlausibilityLimits};
+use crate::sort_policy::rules::RuleSet;
+use crate::sort_policy::tolerance::Tolerance;
+
+pub(crate) mod bulky_strategy;
+pub(crate) mod plausibility;
+pub(crate) mod policy_file;
+pub(crate) mod registry;
+pub(crate) mod rules;
+pub(crate) mod schedule;
+pub(crate) mod tolerance;
+
+/**
+ * SortPolicy
+ *
+ * Groups the parameters used when sorting a package: the
+ * thresholds at which a package becomes bulky or heavy, how its size
+ * is measured and the bulky threshold of every measure, the rules
+ * mapping the facts about a package onto a stack, the
+ * tolerance of the measuring equipment, how multiple readings of a
+ * package are fused, the optional plausibility limits used to
+ * detect measurement glitches, and the optional divisor used to
+ * classify packages on their billable rather than their actual mass.
+ *
+ * The default policy honors the original specification: it assumes
+ * exact measurements and does not perform any plausibility checks.
+ *
+ * Every policy carries a version, which is recorded in the decisions
+ * it produces.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortPolicy {
+    version: String,
+    bulky_threshold: Cm,
+    length_plus_girth_threshold: Cm,
+    bulky_strategy: BulkyStrategy,
+    heavy_threshold: Kg,
+    rules: RuleSet,
+    tolerance: Tolerance,
+    fusion: Fusion,
+    plausibility: Option<PlausibilityLimits>,
+    dimensional_divisor: Option<DimensionalDivisor>
+}
+
+impl Default for SortPolicy {
+    fn default() -> Self {
+        SortPolicy {
+            version: "default".to_string(),
+            bulky_threshold: Cm::new(150).unwrap(),
+            length_plus_girth_threshold: Cm::new(300).unwrap(),
+            bulky_strategy: BulkyStrategy::default(),
+            heavy_threshold: Kg::new(20).unwrap(),
+            rules: RuleSet::default(),
+            tolerance: Tolerance::default(),
+            fusion: Fusion::default(),
+            plausibility: None,
+            dimensional_divisor: None
+        }
+    }
+}
+
+impl SortPolicy {
+    pub fn with_version<T: Into<String>>(mut self, version: T) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /**
+     * Enables plausibility checks
+     *
+     * Packages whose measurements fall outside of the given limits
+     * are flagged for remeasurement instead of being sorted.
+     */
+    pub fn with_plausibility(mut self, limits: PlausibilityLimits) -> Self {
+        self.plausibility = Some(limits);
+        self
+    }
+
+    pub(crate) fn with_thresholds(mut self, bulky: Cm, heavy: Kg) -> Self {
+        self.bulky_threshold = bulky;
+        self.heavy_threshold = heavy;
+        self
+    }
+
+    /**
+     * Sets the bulky threshold used when measuring packages on their
+     * length plus girth, which is larger than the sum of their sides
+     */
+    pub(crate) fn with_length_plus_girth_threshold(mut self, bulky: Cm) -> Self {
+        self.length_plus_girth_threshold = bulky;
+        self
+    }
+
+    /**
+     * Sets how the size of a package is measured against the bulky threshold
+     */
+    pub fn with_bulky_strategy(mut self, strategy: BulkyStrategy) -> Self {
+        self.bulky_strategy = strategy;
+        self
+    }
+
+    /**
+     * Replaces the rules used to determine the stack of a package
+     */
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /**
+     * Sets the accuracy of the measuring equipment
+     *
+     * Packages measured within the tolerance of a threshold are
+     * classified as uncertain.
+     */
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /**
+     * Sets how multiple readings of a single package are fused
+     */
+    pub fn with_fusion(mut self, fusion: Fusion) -> Self {
+        self.fusion = fusion;
+        self
+    }
+
+    /**
+     * Classifies packages on their billable weight, the largest of their
+     * actual mass and their dimensional weight under the given divisor
+     */
+    pub fn with_dimensional_weight(mut self, divisor: DimensionalDivisor) -> Self {
+        self.dimensional_divisor = Some(divisor);
+        self
+    }
+
+    pub(crate) fn bulky_threshold(&self) -> &Cm {
+        &self.bulky_threshold
+    }
+
+    pub(crate) fn length_plus_girth_threshold(&self) -> &Cm {
+        &self.length_plus_girth_threshold
+    }
+
+    pub(crate) fn bulky_strategy(&self) -> &BulkyStrategy {
+        &self.bulky_strategy
+    }
+
+    pub(crate) fn heavy_threshold(&self) -> &Kg {
+        &self.heavy_threshold
+    }
+
+    pub(crate) fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub(crate) fn tolerance(&self) -> &Tolerance {
+        &self.tolerance
+    }
+
+    pub(crate) fn fusion(&self) -> &Fusion {
+        &self.fusion
+    }
+
+    pub(crate) fn dimensional_divisor(&self) -> Option<&DimensionalDivisor> {
+        self.dimensional_divisor.as_ref()
+    }
+
+    pub(crate) fn check_plausibility(&self, dimensions: &PackageDimensions, mass: &Mass) -> Result<(), Implausibility> {
+        match &self.plausibility {
+            Some(limits) => limits.check(dimensions, mass),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::test_dependencies::BulkyPackageDimensions;
+    use crate::package::mass::test_dependencies::HeavyMass;
+
+    #[test]
+    fn the_default_policy_uses_the_original_thresholds() {
+        let policy = SortPolicy::default();
+        assert_eq!(policy.bulky_threshold(), &Cm::new(150).unwrap());
+        assert_eq!(policy.length_plus_girth_threshold(), &Cm::new(300).unwrap());
+        assert_eq!(policy.heavy_threshold(), &Kg::new(20).unwrap());
+    }
+
+    #[quickcheck]
+    fn the_default_policy_considers_any_measurement_plausible(dimensions: BulkyPackageDimensions, mass: HeavyMass) -> bool {
+        SortPolicy::default().check_plausibility(&dimensions, &mass).is_ok()
+    }
+}
@@ -0,0 +1,98 @@
+use crate::measurements::cm::Cm;
+use crate::package::sorted_dimensions::SortedDimensions;
+use crate::sort_policy::SortPolicy;
+
+/**
+ * BulkyStrategy
+ *
+ * Documents how the size of a package is measured against the bulky
+ * threshold:
+ * - sum of sides: width + height + length, as in the original specification
+ * - length plus girth: the longest side plus twice the sum of the two
+ *   other sides, as many carriers define oversize packages
+ *
+ * Both measures are independent of the order in which the sides are given.
+ * As length plus girth always exceeds the sum of sides, every measure is
+ * compared with a threshold of its own.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum BulkyStrategy {
+    #[default]
+    SumOfSides,
+    LengthPlusGirth
+}
+
+impl BulkyStrategy {
+    // expressed in cm
+    pub(crate) fn measure(&self, dimensions: &SortedDimensions) -> usize {
+        match self {
+            BulkyStrategy::SumOfSides => dimensions.sum().value(),
+            BulkyStrategy::LengthPlusGirth => dimensions.length_plus_girth()
+        }
+    }
+
+    pub(crate) fn threshold<'a>(&self, policy: &'a SortPolicy) -> &'a Cm {
+        match self {
+            BulkyStrategy::SumOfSides => policy.bulky_threshold(),
+            BulkyStrategy::LengthPlusGirth => policy.length_plus_girth_threshold()
+        }
+    }
+
+    // how far off the measure may be when every side may be off by the given tolerance
+    pub(crate) fn tolerance(&self, side_tolerance: usize) -> usize {
+        match self {
+            BulkyStrategy::SumOfSides => side_tolerance.saturating_mul(3),
+            BulkyStrategy::LengthPlusGirth => side_tolerance.saturating_mul(5)
+        }
+    }
+}
+
+impl std::fmt::Display for BulkyStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BulkyStrategy::SumOfSides => write!(f, "sum of sides"),
+            BulkyStrategy::LengthPlusGirth => write!(f, "length plus girth")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::PackageDimensions;
+
+    fn dimensions(width: usize, height: usize, length: usize) -> SortedDimensions {
+        PackageDimensions::new(Cm::new(width).unwrap(), Cm::new(height).unwrap(), Cm::new(length).unwrap()).sorted()
+    }
+
+    #[test]
+    fn the_sum_of_sides_is_measured() {
+        assert_eq!(BulkyStrategy::SumOfSides.measure(&dimensions(30, 20, 100)), 150);
+    }
+
+    #[test]
+    fn length_plus_girth_uses_the_longest_side_as_length() {
+        assert_eq!(BulkyStrategy::LengthPlusGirth.measure(&dimensions(30, 20, 100)), 200);
+        assert_eq!(BulkyStrategy::LengthPlusGirth.measure(&dimensions(100, 30, 20)), 200);
+    }
+
+    #[test]
+    fn every_strategy_has_a_threshold_of_its_own() {
+        let policy = SortPolicy::default();
+        assert_eq!(BulkyStrategy::SumOfSides.threshold(&policy), &Cm::new(150).unwrap());
+        assert_eq!(BulkyStrategy::LengthPlusGirth.threshold(&policy), &Cm::new(300).unwrap());
+    }
+
+    #[test]
+    fn the_tolerance_grows_with_the_sides_measured() {
+        assert_eq!(BulkyStrategy::SumOfSides.tolerance(1), 3);
+        assert_eq!(BulkyStrategy::LengthPlusGirth.tolerance(1), 5);
+        assert_eq!(BulkyStrategy::LengthPlusGirth.tolerance(usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn display_strategies() {
+        assert_eq!(BulkyStrategy::SumOfSides.to_string(), "sum of sides");
+        assert_eq!(BulkyStrategy::LengthPlusGirth.to_string(), "length plus girth");
+    }
+}
@@ -0,0 +1,238 @@
+use crate::package::dimensions::PackageDimensions;
+use crate::package::fusion::Disagreement;
+use crate::package::mass::Mass;
+
+// one cubic metre expressed in cubic centimetres
+const CM3_PER_M3: u128 = 1_000_000;
+
+/**
+ * PlausibilityLimits
+ *
+ * Describes the range of measurements that can physically occur on
+ * the sorting line. Measurements outside of these limits are most
+ * likely caused by a glitching dimensioner or scale.
+ *
+ * Densities are expressed in kg/m³.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlausibilityLimits {
+    max_side: usize,
+    max_mass: usize,
+    min_density: usize,
+    max_density: usize
+}
+
+/**
+ * Implausibility
+ *
+ * Documents why a measurement was considered implausible. Every
+ * variant holds the measured value followed by the limit it broke,
+ * apart from readings too far apart to be fused into a measurement.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Implausibility {
+    SideTooLong(usize, usize),
+    TooHeavy(usize, usize),
+    DensityTooLow(usize, usize),
+    DensityTooHigh(usize, usize),
+    Irreconcilable(Disagreement)
+}
+
+/**
+ * PlausibilityError
+ *
+ * Documents why limits can't be set: the minimum density is above the
+ * maximum density.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlausibilityError {
+    InvalidDensityRange(usize, usize)
+}
+
+impl Default for PlausibilityLimits {
+    fn default() -> Self {
+        PlausibilityLimits {
+            max_side: 500,
+            max_mass: 1_000,
+            min_density: 1,
+            max_density: 20_000
+        }
+    }
+}
+
+impl PlausibilityLimits {
+    pub fn with_max_side(mut self, cm: usize) -> Self {
+        self.max_side = cm;
+        self
+    }
+
+    pub fn with_max_mass(mut self, kg: usize) -> Self {
+        self.max_mass = kg;
+        self
+    }
+
+    pub fn with_density_range(mut self, min_kg_per_m3: usize, max_kg_per_m3: usize) -> Result<Self, PlausibilityError> {
+        if min_kg_per_m3 > max_kg_per_m3 {
+            return Err(PlausibilityError::InvalidDensityRange(min_kg_per_m3, max_kg_per_m3));
+        }
+
+        self.min_density = min_kg_per_m3;
+        self.max_density = max_kg_per_m3;
+        Ok(self)
+    }
+
+    pub(crate) fn max_side(&self) -> usize {
+        self.max_side
+    }
+
+    pub(crate) fn max_mass(&self) -> usize {
+        self.max_mass
+    }
+
+    pub(crate) fn min_density(&self) -> usize {
+        self.min_density
+    }
+
+    pub(crate) fn max_density(&self) -> usize {
+        self.max_density
+    }
+
+    pub(crate) fn check(&self, dimensions: &PackageDimensions, mass: &Mass) -> Result<(), Implausibility> {
+        let longest_side = dimensions.sorted().longest().value();
+        if longest_side > self.max_side {
+            return Err(Implausibility::SideTooLong(longest_side, self.max_side));
+        }
+
+        let kg = mass.value().value();
+        if kg > self.max_mass {
+            return Err(Implausibility::TooHeavy(kg, self.max_mass));
+        }
+
+        // the volume can't be zero as every side is at least 1 cm
+        let density = (kg as u128).saturating_mul(CM3_PER_M3) / dimensions.volume();
+        let density = usize::try_from(density).unwrap_or(usize::MAX);
+        if density < self.min_density {
+            return Err(Implausibility::DensityTooLow(density, self.min_density));
+        }
+        if density > self.max_density {
+            return Err(Implausibility::DensityTooHigh(density, self.max_density));
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Implausibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Implausibility::SideTooLong(side, limit) => write!(f, "Implausible side: expecting at most {} cm, but got {} cm", limit, side),
+            Implausibility::TooHeavy(mass, limit) => write!(f, "Implausible mass: expecting at most {} kg, but got {} kg", limit, mass),
+            Implausibility::DensityTooLow(density, limit) => write!(f, "Implausible density: expecting at least {} kg/m³, but got {} kg/m³", limit, density),
+            Implausibility::DensityTooHigh(density, limit) => write!(f, "Implausible density: expecting at most {} kg/m³, but got {} kg/m³", limit, density),
+            Implausibility::Irreconcilable(disagreement) => write!(f, "{}", disagreement)
+        }
+    }
+}
+
+impl std::fmt::Display for PlausibilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PlausibilityError::InvalidDensityRange(min, max) => write!(f, "Invalid density range: expecting a minimum of at most {}, but got {}", max, min)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurements::cm::Cm;
+    use crate::measurements::kg::Kg;
+
+    fn check(width: usize, height: usize, length: usize, mass: usize) -> Result<(), Implausibility> {
+        PlausibilityLimits::default().check(
+            &PackageDimensions::new(Cm::new(width).unwrap(), Cm::new(height).unwrap(), Cm::new(length).unwrap()),
+            &Mass::new(Kg::new(mass).unwrap())
+        )
+    }
+
+    #[test]
+    fn a_regular_parcel_is_plausible() {
+        assert_eq!(check(40, 30, 20, 5), Ok(()))
+    }
+
+    #[test]
+    fn a_glitching_dimensioner_is_detected() {
+        assert_eq!(check(9_000_000, 30, 20, 5), Err(Implausibility::SideTooLong(9_000_000, 500)))
+    }
+
+    #[test]
+    fn a_glitching_scale_is_detected() {
+        assert_eq!(check(40, 30, 20, 5_000), Err(Implausibility::TooHeavy(5_000, 1_000)))
+    }
+
+    #[test]
+    fn a_parcel_lighter_than_air_is_detected() {
+        // 1 kg for 4 m³
+        assert_eq!(check(200, 200, 100, 1), Err(Implausibility::DensityTooLow(0, 1)))
+    }
+
+    #[test]
+    fn a_parcel_denser_than_lead_is_detected() {
+        // 50 kg for a single litre
+        assert_eq!(check(10, 10, 10, 50), Err(Implausibility::DensityTooHigh(50_000, 20_000)))
+    }
+
+    #[test]
+    fn limits_are_configurable() {
+        let limits = PlausibilityLimits::default()
+            .with_max_side(1_000)
+            .with_max_mass(10)
+            .with_density_range(0, usize::MAX)
+            .unwrap();
+        let dimensions = PackageDimensions::new(Cm::new(900).unwrap(), Cm::new(1).unwrap(), Cm::new(1).unwrap());
+        assert_eq!(limits.check(&dimensions, &Mass::new(Kg::new(10).unwrap())), Ok(()));
+        assert_eq!(limits.check(&dimensions, &Mass::new(Kg::new(11).unwrap())), Err(Implausibility::TooHeavy(11, 10)));
+    }
+
+    #[test]
+    fn density_ranges_must_be_ordered() {
+        assert_eq!(PlausibilityLimits::default().with_density_range(100, 10), Err(PlausibilityError::InvalidDensityRange(100, 10)));
+        assert!(PlausibilityLimits::default().with_density_range(10, 10).is_ok());
+    }
+
+    #[test]
+    fn display_invalid_density_range() {
+        assert_eq!(
+            PlausibilityError::InvalidDensityRange(100, 10).to_string(),
+            "Invalid density range: expecting a minimum of at most 10, but got 100"
+        );
+    }
+
+    #[test]
+    fn display_side_too_long() {
+        assert_eq!(Implausibility::SideTooLong(600, 500).to_string(), "Implausible side: expecting at most 500 cm, but got 600 cm");
+    }
+
+    #[test]
+    fn display_too_heavy() {
+        assert_eq!(Implausibility::TooHeavy(2_000, 1_000).to_string(), "Implausible mass: expecting at most 1000 kg, but got 2000 kg");
+    }
+
+    #[test]
+    fn display_density_too_low() {
+        assert_eq!(Implausibility::DensityTooLow(0, 1).to_string(), "Implausible density: expecting at least 1 kg/m³, but got 0 kg/m³");
+    }
+
+    #[test]
+    fn display_density_too_high() {
+        assert_eq!(Implausibility::DensityTooHigh(50_000, 20_000).to_string(), "Implausible density: expecting at most 20000 kg/m³, but got 50000 kg/m³");
+    }
+
+    #[test]
+    fn display_irreconcilable() {
+        assert_eq!(
+            Implausibility::Irreconcilable(Disagreement::Mass(350, 20)).to_string(),
+            "Mass readings disagree: expecting a spread of at most 20 kg, but got 350 kg"
+        );
+    }
+}
@@ -0,0 +1,1111 @@
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use crate::measurements::cm::Cm;
+use crate::measurements::kg::Kg;
+use crate::package::attributes::Attribute;
+use crate::package::dimensional_weight::DimensionalDivisor;
+use crate::package::dimensions::DimensionClass;
+use crate::package::fusion::{Fusion, FusionStrategy};
+use crate::package::mass::MassClass;
+use crate::package::service_level::ServiceLevel;
+use crate::package::sorted_dimensions::Side;
+use crate::sort_policy::bulky_strategy::BulkyStrategy;
+use crate::sort_policy::plausibility::PlausibilityLimits;
+use crate::sort_policy::rules::{CustomStack, EvaluationMode, Predicate, Rule, RuleSet};
+use crate::sort_policy::schedule::PolicySchedule;
+use crate::sort_policy::tolerance::Tolerance;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::{SortResult, StackId};
+
+/**
+ * PolicyFormat
+ *
+ * The file formats a policy can be written in.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyFormat {
+    Toml, Yaml, Json
+}
+
+/**
+ * PolicyFileError
+ *
+ * Documents why a policy file could not be loaded. Invalid policies
+ * report the line and column of the offending value when the format
+ * makes it available.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyFileError {
+    Unreadable(String),
+    UnknownFormat(String),
+    Invalid { line: Option<usize>, column: Option<usize>, message: String }
+}
+
+impl PolicyFormat {
+    pub fn from_path(path: &Path) -> Option<PolicyFormat> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(PolicyFormat::Toml),
+            "yaml" | "yml" => Some(PolicyFormat::Yaml),
+            "json" => Some(PolicyFormat::Json),
+            _ => None
+        }
+    }
+}
+
+impl SortPolicy {
+    /**
+     * Loads a policy from a TOML, YAML or JSON file
+     *
+     * The format is derived from the extension of the file. Omitted
+     * sections fall back to the values of the default policy, and an
+     * omitted version to "default".
+     */
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SortPolicy, PolicyFileError> {
+        let (source, format) = read(path.as_ref())?;
+        SortPolicy::from_source(&source, format)
+    }
+
+    /**
+     * Loads a policy from its textual representation
+     */
+    pub fn from_source(source: &str, format: PolicyFormat) -> Result<SortPolicy, PolicyFileError> {
+        let document = parse::<PolicyDocument>(source, format)?;
+        if document.effective_from.is_some() {
+            return Err(PolicyFileError::Invalid {
+                line: None,
+                column: None,
+                message: "Unexpected effective date: expecting effective_from only within the versions of a schedule".to_string()
+            });
+        }
+
+        Ok(SortPolicy::from(document))
+    }
+}
+
+impl PolicySchedule {
+    /**
+     * Loads the versions of a policy from a TOML, YAML or JSON file
+     *
+     * The file holds a list of `versions`, each written as a policy
+     * along with the `effective_from` date at which it takes effect.
+     */
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<PolicySchedule, PolicyFileError> {
+        let (source, format) = read(path.as_ref())?;
+        PolicySchedule::from_source(&source, format)
+    }
+
+    /**
+     * Loads the versions of a policy from their textual representation
+     */
+    pub fn from_source(source: &str, format: PolicyFormat) -> Result<PolicySchedule, PolicyFileError> {
+        parse::<ScheduleDocument>(source, format).map(|ScheduleDocument(schedule)| schedule)
+    }
+
+    /**
+     * Loads a file holding either the versions of a policy, or a single
+     * policy, which is then in effect at any moment
+     */
+    pub fn from_any_file<P: AsRef<Path>>(path: P) -> Result<PolicySchedule, PolicyFileError> {
+        let (source, format) = read(path.as_ref())?;
+        PolicySchedule::from_any_source(&source, format)
+    }
+
+    /**
+     * Loads either the versions of a policy or a single policy from
+     * their textual representation
+     */
+    pub fn from_any_source(source: &str, format: PolicyFormat) -> Result<PolicySchedule, PolicyFileError> {
+        match parse::<VersionsProbe>(source, format.clone()) {
+            Ok(VersionsProbe { versions: Some(_) }) => PolicySchedule::from_source(source, format),
+            _ => SortPolicy::from_source(source, format).map(PolicySchedule::from_policy)
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<(String, PolicyFormat), PolicyFileError> {
+    let format = PolicyFormat::from_path(path)
+        .ok_or_else(|| PolicyFileError::UnknownFormat(path.display().to_string()))?;
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| PolicyFileError::Unreadable(format!("{}: {}", path.display(), e)))?;
+
+    Ok((source, format))
+}
+
+pub(crate) fn parse<T: serde::de::DeserializeOwned>(source: &str, format: PolicyFormat) -> Result<T, PolicyFileError> {
+    match format {
+        PolicyFormat::Toml => toml::from_str(source).map_err(|e| {
+            let position = e.span().map(|span| line_and_column(source, span.start));
+            PolicyFileError::Invalid {
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column),
+                message: e.message().to_string()
+            }
+        }),
+        PolicyFormat::Yaml => serde_yaml::from_str(source).map_err(|e| {
+            let location = e.location();
+            PolicyFileError::Invalid {
+                line: location.as_ref().map(|l| l.line()),
+                column: location.as_ref().map(|l| l.column()),
+                message: without_location(&e.to_string())
+            }
+        }),
+        PolicyFormat::Json => serde_json::from_str(source).map_err(|e| PolicyFileError::Invalid {
+            line: Some(e.line()),
+            column: Some(e.column()),
+            message: without_location(&e.to_string())
+        })
+    }
+}
+
+// both are 1-based, the column counts characters rather than bytes
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+// the YAML and JSON messages end with their own rendering of the location
+fn without_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message.to_string()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PolicyDocument {
+    version: Option<String>,
+    effective_from: Option<EffectiveFrom>,
+    thresholds: Option<Thresholds>,
+    tolerance: Option<ToleranceSection>,
+    plausibility: Option<PlausibilitySection>,
+    fusion: Option<FusionSection>,
+    dimensional_weight: Option<DimensionalWeightSection>,
+    rules: Option<RulesSection>
+}
+
+// tells the versions of a policy from a single policy, ignoring every other field
+#[derive(Deserialize)]
+struct VersionsProbe {
+    versions: Option<serde::de::IgnoredAny>
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "ScheduleFields")]
+struct ScheduleDocument(PolicySchedule);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScheduleFields {
+    versions: Vec<ScheduledVersion>
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "PolicyDocument")]
+struct ScheduledVersion(DateTime<Utc>, SortPolicy);
+
+// written as an RFC 3339 string, or as a TOML offset date-time
+struct EffectiveFrom(DateTime<Utc>);
+
+#[derive(Deserialize)]
+#[serde(try_from = "ThresholdFields")]
+struct Thresholds {
+    bulky: Cm,
+    bulky_strategy: BulkyStrategy,
+    length_plus_girth: Option<Cm>,
+    heavy: Kg
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThresholdFields {
+    bulky_cm: BulkyThreshold,
+    #[serde(default)]
+    bulky_measure: BulkyMeasureName,
+    length_plus_girth_cm: Option<BulkyThreshold>,
+    heavy_kg: HeavyThreshold
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BulkyMeasureName {
+    #[default]
+    SumOfSides,
+    LengthPlusGirth
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "usize")]
+struct BulkyThreshold(Cm);
+
+#[derive(Deserialize)]
+#[serde(try_from = "usize")]
+struct HeavyThreshold(Kg);
+
+#[derive(Deserialize)]
+#[serde(try_from = "usize")]
+struct Positive(usize);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToleranceSection {
+    #[serde(default)]
+    dimension_cm: usize,
+    #[serde(default)]
+    mass_kg: usize
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "PlausibilityFields")]
+struct PlausibilitySection(PlausibilityLimits);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlausibilityFields {
+    max_side_cm: Option<Positive>,
+    max_mass_kg: Option<Positive>,
+    min_density_kg_m3: Option<usize>,
+    max_density_kg_m3: Option<usize>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FusionSection {
+    strategy: Option<StrategyName>,
+    dimension_outlier_cm: Option<usize>,
+    mass_outlier_kg: Option<usize>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StrategyName {
+    Median, TrimmedMean
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "DimensionalWeightFields")]
+struct DimensionalWeightSection(DimensionalDivisor);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DimensionalWeightFields {
+    preset: Option<PresetName>,
+    cm3_per_kg: Option<Divisor>,
+    in3_per_lb: Option<Divisor>
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "usize")]
+struct Divisor(usize);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PresetName {
+    Express, Road, UsDomestic
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "RulesFields")]
+struct RulesSection(RuleSet);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFields {
+    #[serde(default)]
+    evaluation: EvaluationName,
+    #[serde(default)]
+    stack: Vec<StackFields>,
+    rule: Vec<RuleFields>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StackFields {
+    name: StackName,
+    refines: SortResult
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct StackName(StackId);
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EvaluationName {
+    #[default]
+    FirstMatch,
+    AllMatch
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFields {
+    name: String,
+    result: SortResult,
+    routing_key: Option<String>,
+    #[serde(default)]
+    when: WhenFields
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WhenFields {
+    dimension_class: Option<DimensionClassName>,
+    mass_class: Option<MassClassName>,
+    sum_of_sides_at_least_cm: Option<usize>,
+    length_plus_girth_at_least_cm: Option<usize>,
+    longest_side_at_least_cm: Option<usize>,
+    middle_side_at_least_cm: Option<usize>,
+    shortest_side_at_least_cm: Option<usize>,
+    mass_at_least_kg: Option<usize>,
+    #[serde(default)]
+    attributes: Vec<Attribute>,
+    destination_country: Option<String>,
+    postcode_prefix: Option<String>,
+    service_level: Option<ServiceLevel>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DimensionClassName {
+    Standard, Bulky
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MassClassName {
+    Standard, Heavy
+}
+
+impl TryFrom<usize> for BulkyThreshold {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        Cm::new(value)
+            .map(BulkyThreshold)
+            .map_err(|_| format!("Invalid bulky threshold: expecting a value of 1 or more, but got {}", value))
+    }
+}
+
+impl TryFrom<ThresholdFields> for Thresholds {
+    type Error = String;
+
+    fn try_from(fields: ThresholdFields) -> Result<Self, Self::Error> {
+        let bulky_strategy = match fields.bulky_measure {
+            BulkyMeasureName::SumOfSides => BulkyStrategy::SumOfSides,
+            BulkyMeasureName::LengthPlusGirth => BulkyStrategy::LengthPlusGirth
+        };
+        let length_plus_girth = fields.length_plus_girth_cm.map(|BulkyThreshold(cm)| cm);
+        // the sum of sides threshold would make many small parcels bulky
+        if bulky_strategy == BulkyStrategy::LengthPlusGirth && length_plus_girth.is_none() {
+            return Err("Missing length plus girth threshold: expecting length_plus_girth_cm along with the length_plus_girth measure, but got none".to_string());
+        }
+
+        Ok(Thresholds {
+            bulky: fields.bulky_cm.0,
+            bulky_strategy,
+            length_plus_girth,
+            heavy: fields.heavy_kg.0
+        })
+    }
+}
+
+impl TryFrom<usize> for HeavyThreshold {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        Kg::new(value)
+            .map(HeavyThreshold)
+            .map_err(|_| format!("Invalid heavy threshold: expecting a value of 1 or more, but got {}", value))
+    }
+}
+
+impl TryFrom<usize> for Positive {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Err("Invalid limit: expecting a value of 1 or more, but got 0".to_string()),
+            _ => Ok(Positive(value))
+        }
+    }
+}
+
+impl TryFrom<String> for StackName {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        StackId::new(name)
+            .map(StackName)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<usize> for Divisor {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Err("Invalid divisor: expecting a value of 1 or more, but got 0".to_string()),
+            _ => Ok(Divisor(value))
+        }
+    }
+}
+
+impl TryFrom<PlausibilityFields> for PlausibilitySection {
+    type Error = String;
+
+    fn try_from(fields: PlausibilityFields) -> Result<Self, Self::Error> {
+        let defaults = PlausibilityLimits::default();
+        let max_side = fields.max_side_cm.map(|Positive(cm)| cm).unwrap_or(defaults.max_side());
+        let max_mass = fields.max_mass_kg.map(|Positive(kg)| kg).unwrap_or(defaults.max_mass());
+        let min_density = fields.min_density_kg_m3.unwrap_or(defaults.min_density());
+        let max_density = fields.max_density_kg_m3.unwrap_or(defaults.max_density());
+
+        let limits = defaults
+            .with_max_side(max_side)
+            .with_max_mass(max_mass)
+            .with_density_range(min_density, max_density)
+            .map_err(|e| e.to_string())?;
+
+        Ok(PlausibilitySection(limits))
+    }
+}
+
+impl TryFrom<ScheduleFields> for ScheduleDocument {
+    type Error = String;
+
+    fn try_from(fields: ScheduleFields) -> Result<Self, Self::Error> {
+        let versions = fields.versions.into_iter()
+            .map(|ScheduledVersion(effective_from, policy)| (effective_from, policy))
+            .collect();
+
+        PolicySchedule::new(versions)
+            .map(ScheduleDocument)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<PolicyDocument> for ScheduledVersion {
+    type Error = String;
+
+    fn try_from(mut document: PolicyDocument) -> Result<Self, Self::Error> {
+        match document.effective_from.take() {
+            Some(EffectiveFrom(effective_from)) => Ok(ScheduledVersion(effective_from, SortPolicy::from(document))),
+            None => Err(format!(
+                "Missing effective date: expecting effective_from for every version, but got none for version {}",
+                document.version.as_deref().unwrap_or("default")
+            ))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EffectiveFrom {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = EffectiveFrom;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an RFC 3339 date and time")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                DateTime::parse_from_rfc3339(value)
+                    .map(|date| EffectiveFrom(date.with_timezone(&Utc)))
+                    .map_err(|_| E::custom(format!("Invalid effective date: expecting an RFC 3339 date and time with an offset, but got {}", value)))
+            }
+
+            // TOML hands its date-times over as a map holding their textual form
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                match map.next_entry::<String, String>()? {
+                    Some((_, value)) => self.visit_str(&value),
+                    None => Err(serde::de::Error::invalid_length(0, &self))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl TryFrom<DimensionalWeightFields> for DimensionalWeightSection {
+    type Error = String;
+
+    fn try_from(fields: DimensionalWeightFields) -> Result<Self, Self::Error> {
+        let divisor = match (fields.preset, fields.cm3_per_kg, fields.in3_per_lb) {
+            (Some(PresetName::Express), None, None) => DimensionalDivisor::EXPRESS,
+            (Some(PresetName::Road), None, None) => DimensionalDivisor::ROAD,
+            (Some(PresetName::UsDomestic), None, None) => DimensionalDivisor::US_DOMESTIC,
+            (None, Some(Divisor(divisor)), None) => DimensionalDivisor::CubicCmPerKg(divisor),
+            (None, None, Some(Divisor(divisor))) => DimensionalDivisor::CubicInchPerLb(divisor),
+            _ => return Err("Invalid dimensional weight: expecting exactly one of preset, cm3_per_kg or in3_per_lb".to_string())
+        };
+
+        Ok(DimensionalWeightSection(divisor))
+    }
+}
+
+impl TryFrom<RulesFields> for RulesSection {
+    type Error = String;
+
+    fn try_from(fields: RulesFields) -> Result<Self, Self::Error> {
+        let mode = match fields.evaluation {
+            EvaluationName::FirstMatch => EvaluationMode::FirstMatch,
+            EvaluationName::AllMatch => EvaluationMode::AllMatch
+        };
+        let stacks = fields.stack.into_iter()
+            .map(|StackFields { name: StackName(id), refines }| CustomStack::new(id, refines))
+            .collect();
+        let rules = fields.rule.into_iter().map(Rule::from).collect();
+
+        RuleSet::with_stacks(mode, stacks, rules)
+            .map(RulesSection)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl From<RuleFields> for Rule {
+    fn from(fields: RuleFields) -> Self {
+        let when = fields.when;
+
+        let mut rule = Rule::new(fields.name, fields.result);
+        if let Some(class) = when.dimension_class {
+            rule = rule.when(Predicate::DimensionClass(match class {
+                DimensionClassName::Standard => DimensionClass::STANDARD,
+                DimensionClassName::Bulky => DimensionClass::BULKY
+            }));
+        }
+        if let Some(class) = when.mass_class {
+            rule = rule.when(Predicate::MassClass(match class {
+                MassClassName::Standard => MassClass::STANDARD,
+                MassClassName::Heavy => MassClass::HEAVY
+            }));
+        }
+        if let Some(cm) = when.sum_of_sides_at_least_cm {
+            rule = rule.when(Predicate::SumOfSidesAtLeast(cm));
+        }
+        if let Some(cm) = when.length_plus_girth_at_least_cm {
+            rule = rule.when(Predicate::LengthPlusGirthAtLeast(cm));
+        }
+        for (side, cm) in [
+            (Side::Longest, when.longest_side_at_least_cm),
+            (Side::Middle, when.middle_side_at_least_cm),
+            (Side::Shortest, when.shortest_side_at_least_cm)
+        ] {
+            if let Some(cm) = cm {
+                rule = rule.when(Predicate::SideAtLeast(side, cm));
+            }
+        }
+        if let Some(kg) = when.mass_at_least_kg {
+            rule = rule.when(Predicate::MassAtLeast(kg));
+        }
+        for attribute in when.attributes {
+            rule = rule.when(Predicate::HasAttribute(attribute));
+        }
+        if let Some(country) = when.destination_country {
+            rule = rule.when(Predicate::DestinationCountry(country));
+        }
+        if let Some(prefix) = when.postcode_prefix {
+            rule = rule.when(Predicate::PostcodePrefix(prefix));
+        }
+        if let Some(level) = when.service_level {
+            rule = rule.when(Predicate::ServiceLevel(level));
+        }
+        if let Some(routing_key) = fields.routing_key {
+            rule = rule.with_routing_key(routing_key);
+        }
+        rule
+    }
+}
+
+impl From<PolicyDocument> for SortPolicy {
+    fn from(document: PolicyDocument) -> Self {
+        let mut policy = SortPolicy::default();
+
+        if let Some(version) = document.version {
+            policy = policy.with_version(version);
+        }
+        if let Some(Thresholds { bulky, bulky_strategy, length_plus_girth, heavy }) = document.thresholds {
+            policy = policy
+                .with_thresholds(bulky, heavy)
+                .with_bulky_strategy(bulky_strategy);
+            if let Some(length_plus_girth) = length_plus_girth {
+                policy = policy.with_length_plus_girth_threshold(length_plus_girth);
+            }
+        }
+        if let Some(tolerance) = document.tolerance {
+            policy = policy.with_tolerance(
+                Tolerance::default()
+                    .with_dimension(tolerance.dimension_cm)
+                    .with_mass(tolerance.mass_kg)
+            );
+        }
+        if let Some(PlausibilitySection(limits)) = document.plausibility {
+            policy = policy.with_plausibility(limits);
+        }
+        if let Some(fusion) = document.fusion {
+            let defaults = Fusion::default();
+            let mut configured = defaults.clone();
+            if let Some(strategy) = fusion.strategy {
+                configured = configured.with_strategy(match strategy {
+                    StrategyName::Median => FusionStrategy::Median,
+                    StrategyName::TrimmedMean => FusionStrategy::TrimmedMean
+                });
+            }
+            if let Some(cm) = fusion.dimension_outlier_cm {
+                configured = configured.with_dimension_outlier(cm);
+            }
+            if let Some(kg) = fusion.mass_outlier_kg {
+                configured = configured.with_mass_outlier(kg);
+            }
+            policy = policy.with_fusion(configured);
+        }
+        if let Some(DimensionalWeightSection(divisor)) = document.dimensional_weight {
+            policy = policy.with_dimensional_weight(divisor);
+        }
+        if let Some(RulesSection(rules)) = document.rules {
+            policy = policy.with_rules(rules);
+        }
+
+        policy
+    }
+}
+
+impl std::fmt::Display for PolicyFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyFileError::Unreadable(reason) => write!(f, "Unreadable policy: {}", reason),
+            PolicyFileError::UnknownFormat(path) => write!(f, "Unknown policy format: expecting a .toml, .yaml, .yml or .json file, but got {}", path),
+            PolicyFileError::Invalid { line: Some(line), column: Some(column), message } => write!(f, "Invalid policy at line {}, column {}: {}", line, column, message),
+            PolicyFileError::Invalid { line: Some(line), column: None, message } => write!(f, "Invalid policy at line {}: {}", line, message),
+            PolicyFileError::Invalid { line: None, message, .. } => write!(f, "Invalid policy: {}", message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT_TOML: &str = include_str!("../../policies/default.toml");
+
+    const DEFAULT_YAML: &str = r#"
+thresholds:
+  bulky_cm: 150
+  heavy_kg: 20
+rules:
+  evaluation: first_match
+  rule:
+    - name: bulky and heavy
+      result: rejected
+      when: { dimension_class: bulky, mass_class: heavy }
+    - name: bulky
+      result: special
+      when: { dimension_class: bulky }
+    - name: heavy
+      result: special
+      when: { mass_class: heavy }
+    - name: standard
+      result: standard
+"#;
+
+    const DEFAULT_JSON: &str = r#"{
+  "thresholds": { "bulky_cm": 150, "heavy_kg": 20 },
+  "rules": {
+    "evaluation": "first_match",
+    "rule": [
+      { "name": "bulky and heavy", "result": "rejected", "when": { "dimension_class": "bulky", "mass_class": "heavy" } },
+      { "name": "bulky", "result": "special", "when": { "dimension_class": "bulky" } },
+      { "name": "heavy", "result": "special", "when": { "mass_class": "heavy" } },
+      { "name": "standard", "result": "standard" }
+    ]
+  }
+}"#;
+
+    fn position(error: PolicyFileError) -> (Option<usize>, Option<usize>) {
+        match error {
+            PolicyFileError::Invalid { line, column, .. } => (line, column),
+            other => panic!("expected an invalid policy, but got {:?}", other)
+        }
+    }
+
+    fn message(error: PolicyFileError) -> String {
+        match error {
+            PolicyFileError::Invalid { message, .. } => message,
+            other => panic!("expected an invalid policy, but got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn the_default_policy_can_be_written_in_every_format() {
+        assert_eq!(SortPolicy::from_source(DEFAULT_TOML, PolicyFormat::Toml), Ok(SortPolicy::default()));
+        assert_eq!(SortPolicy::from_source(DEFAULT_YAML, PolicyFormat::Yaml), Ok(SortPolicy::default()));
+        assert_eq!(SortPolicy::from_source(DEFAULT_JSON, PolicyFormat::Json), Ok(SortPolicy::default()));
+    }
+
+    #[test]
+    fn omitted_sections_fall_back_to_the_default_policy() {
+        assert_eq!(SortPolicy::from_source("", PolicyFormat::Toml), Ok(SortPolicy::default()));
+        assert_eq!(SortPolicy::from_source("{}", PolicyFormat::Json), Ok(SortPolicy::default()));
+    }
+
+    #[test]
+    fn every_section_is_loaded() {
+        let source = r#"
+version = "2026-10"
+
+[thresholds]
+bulky_cm = 120
+heavy_kg = 18
+
+[tolerance]
+dimension_cm = 1
+mass_kg = 1
+
+[plausibility]
+max_side_cm = 300
+
+[fusion]
+strategy = "trimmed_mean"
+"#;
+        let expected = SortPolicy::default()
+            .with_version("2026-10")
+            .with_thresholds(Cm::new(120).unwrap(), Kg::new(18).unwrap())
+            .with_tolerance(Tolerance::default().with_dimension(1).with_mass(1))
+            .with_plausibility(PlausibilityLimits::default().with_max_side(300))
+            .with_fusion(Fusion::default().with_strategy(FusionStrategy::TrimmedMean));
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(expected));
+    }
+
+    #[test]
+    fn the_bulky_measure_can_be_length_plus_girth() {
+        let source = "[thresholds]\nbulky_cm = 150\nbulky_measure = \"length_plus_girth\"\nlength_plus_girth_cm = 330\nheavy_kg = 20\n";
+        let expected = SortPolicy::default()
+            .with_bulky_strategy(BulkyStrategy::LengthPlusGirth)
+            .with_length_plus_girth_threshold(Cm::new(330).unwrap());
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(expected));
+    }
+
+    #[test]
+    fn length_plus_girth_needs_a_threshold_of_its_own() {
+        let source = "[thresholds]\nbulky_cm = 150\nbulky_measure = \"length_plus_girth\"\nheavy_kg = 20\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(
+            message(error),
+            "Missing length plus girth threshold: expecting length_plus_girth_cm along with the length_plus_girth measure, but got none"
+        );
+
+        let source = "[thresholds]\nbulky_cm = 150\nbulky_measure = \"length_plus_girth\"\nlength_plus_girth_cm = 0\nheavy_kg = 20\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Invalid bulky threshold: expecting a value of 1 or more, but got 0");
+    }
+
+    #[test]
+    fn rules_can_test_length_plus_girth() {
+        let source = "[[rules.rule]]\nname = \"oversize\"\nresult = \"special\"\nwhen = { length_plus_girth_at_least_cm = 300 }\n\n[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\n";
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("oversize", SortResult::Special).when(Predicate::LengthPlusGirthAtLeast(300)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(SortPolicy::default().with_rules(rules)));
+    }
+
+    #[test]
+    fn rules_can_test_a_specific_side() {
+        let source = "[[rules.rule]]\nname = \"chute\"\nresult = \"special\"\nwhen = { shortest_side_at_least_cm = 61, longest_side_at_least_cm = 100 }\n\n[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\n";
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("chute", SortResult::Special)
+                .when(Predicate::SideAtLeast(Side::Longest, 100))
+                .when(Predicate::SideAtLeast(Side::Shortest, 61)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(SortPolicy::default().with_rules(rules)));
+    }
+
+    #[test]
+    fn rules_can_test_attributes() {
+        let source = r#"
+[[rules.rule]]
+name = "lithium batteries"
+result = "rejected"
+when = { attributes = ["lithium_batteries"], mass_at_least_kg = 5 }
+
+[[rules.rule]]
+name = "hazmat"
+result = "special"
+when = { attributes = ["hazmat"] }
+
+[[rules.rule]]
+name = "standard"
+result = "standard"
+"#;
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("lithium batteries", SortResult::Rejected)
+                .when(Predicate::MassAtLeast(5))
+                .when(Predicate::HasAttribute(Attribute::LithiumBatteries)),
+            Rule::new("hazmat", SortResult::Special).when(Predicate::HasAttribute(Attribute::Hazmat)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(SortPolicy::default().with_rules(rules)));
+
+        let source = "[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\nwhen = { attributes = [\"irregular\"] }\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert!(message(error).starts_with("Unknown attribute"));
+    }
+
+    #[test]
+    fn rules_can_test_the_destination_and_service_level() {
+        let source = r#"{
+  "rules": {
+    "rule": [
+      {
+        "name": "express to central london",
+        "result": "special",
+        "routing_key": "london-ec",
+        "when": { "service_level": "express", "destination_country": "GB", "postcode_prefix": "EC" }
+      },
+      { "name": "standard", "result": "standard" }
+    ]
+  }
+}"#;
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("express to central london", SortResult::Special)
+                .when(Predicate::DestinationCountry("GB".to_string()))
+                .when(Predicate::PostcodePrefix("EC".to_string()))
+                .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                .with_routing_key("london-ec"),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Json), Ok(SortPolicy::default().with_rules(rules)));
+
+        let source = "[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\nwhen = { service_level = \"overnight\" }\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert!(message(error).starts_with("Unknown service level"));
+    }
+
+    #[test]
+    fn dimensional_weight_is_configured_by_preset_or_divisor() {
+        let policy = |source| SortPolicy::from_source(source, PolicyFormat::Toml);
+        assert_eq!(policy("[dimensional_weight]\npreset = \"express\"\n"), Ok(SortPolicy::default().with_dimensional_weight(DimensionalDivisor::EXPRESS)));
+        assert_eq!(policy("[dimensional_weight]\npreset = \"road\"\n"), Ok(SortPolicy::default().with_dimensional_weight(DimensionalDivisor::ROAD)));
+        assert_eq!(policy("[dimensional_weight]\npreset = \"us_domestic\"\n"), Ok(SortPolicy::default().with_dimensional_weight(DimensionalDivisor::US_DOMESTIC)));
+        assert_eq!(policy("[dimensional_weight]\ncm3_per_kg = 4000\n"), Ok(SortPolicy::default().with_dimensional_weight(DimensionalDivisor::CubicCmPerKg(4000))));
+        assert_eq!(policy("[dimensional_weight]\nin3_per_lb = 166\n"), Ok(SortPolicy::default().with_dimensional_weight(DimensionalDivisor::CubicInchPerLb(166))));
+    }
+
+    #[test]
+    fn dimensional_weight_needs_a_single_divisor() {
+        let error = SortPolicy::from_source("[dimensional_weight]\npreset = \"road\"\ncm3_per_kg = 4000\n", PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Invalid dimensional weight: expecting exactly one of preset, cm3_per_kg or in3_per_lb");
+
+        let error = SortPolicy::from_source("[dimensional_weight]\ncm3_per_kg = 0\n", PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(2), Some(14)));
+        assert_eq!(message(error), "Invalid divisor: expecting a value of 1 or more, but got 0");
+    }
+
+    #[test]
+    fn thresholds_must_be_positive() {
+        let source = "[thresholds]\nbulky_cm = 0\nheavy_kg = 20\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(2), Some(12)));
+        assert_eq!(message(error), "Invalid bulky threshold: expecting a value of 1 or more, but got 0");
+
+        let source = "thresholds:\n  bulky_cm: 150\n  heavy_kg: 0\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Yaml).unwrap_err();
+        // YAML locates the mapping holding the value
+        assert_eq!(position(error.clone()), (Some(2), Some(3)));
+        assert_eq!(message(error), "thresholds: Invalid heavy threshold: expecting a value of 1 or more, but got 0");
+
+        let source = "[plausibility]\nmax_mass_kg = 0\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(2), Some(15)));
+        assert_eq!(message(error), "Invalid limit: expecting a value of 1 or more, but got 0");
+    }
+
+    #[test]
+    fn rules_must_be_exhaustive() {
+        let source = r#"{
+  "rules": {
+    "rule": [
+      { "name": "bulky", "result": "special", "when": { "dimension_class": "bulky" } }
+    ]
+  }
+}"#;
+        // JSON locates the end of the rules
+        let error = SortPolicy::from_source(source, PolicyFormat::Json).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(7), Some(1)));
+        assert_eq!(message(error), "Rules are not exhaustive: expecting a rule for standard dimensions and standard mass, but got none");
+
+        let source = "[thresholds]\nbulky_cm = 150\nheavy_kg = 20\n\n[rules]\nevaluation = \"all_match\"\n\n[[rules.rule]]\nname = \"bulky\"\nresult = \"special\"\nwhen = { dimension_class = \"bulky\" }\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(5), Some(1)));
+        assert_eq!(message(error), "Rules are not exhaustive: expecting a rule for standard dimensions and standard mass, but got none");
+    }
+
+    #[test]
+    fn unknown_values_are_located() {
+        let source = "[[rules.rule]]\nname = \"standard\"\nresult = \"Express\"\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(3), Some(10)));
+        assert!(message(error).starts_with("Invalid stack name"));
+    }
+
+    #[test]
+    fn rules_can_sort_onto_declared_stacks() {
+        let source = r#"
+[[rules.stack]]
+name = "two-person-lift"
+refines = "special"
+
+[[rules.rule]]
+name = "two person lift"
+result = "two-person-lift"
+when = { mass_at_least_kg = 25 }
+
+[[rules.rule]]
+name = "standard"
+result = "standard"
+"#;
+        let lift = StackId::new("two-person-lift").unwrap();
+        let rules = RuleSet::with_stacks(EvaluationMode::FirstMatch, vec![CustomStack::new(lift.clone(), SortResult::Special)], vec![
+            Rule::new("two person lift", SortResult::Custom(lift)).when(Predicate::MassAtLeast(25)),
+            Rule::new("standard", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(SortPolicy::from_source(source, PolicyFormat::Toml), Ok(SortPolicy::default().with_rules(rules)));
+    }
+
+    #[test]
+    fn custom_stacks_must_be_declared() {
+        let source = "[[rules.rule]]\nname = \"lift\"\nresult = \"two-person-lift\"\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Undeclared stack: expecting rule 'lift' to sort onto a declared stack, but got two-person-lift");
+
+        let source = "[[rules.stack]]\nname = \"Lift\"\nrefines = \"special\"\n\n[[rules.rule]]\nname = \"standard\"\nresult = \"standard\"\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(2), Some(8)));
+        assert!(message(error).starts_with("Invalid stack name"));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let source = "[thresholds]\nbulky_cm = 150\nheavy_kg = 20\nlight_kg = 1\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()).0, Some(4));
+        assert!(message(error).starts_with("unknown field `light_kg`"));
+    }
+
+    #[test]
+    fn density_ranges_must_be_ordered() {
+        let source = "plausibility:\n  min_density_kg_m3: 100\n  max_density_kg_m3: 10\n";
+        let error = SortPolicy::from_source(source, PolicyFormat::Yaml).unwrap_err();
+        assert!(message(error).ends_with("Invalid density range: expecting a minimum of at most 10, but got 100"));
+    }
+
+    #[test]
+    fn schedules_hold_several_versions() {
+        let source = r#"
+[[versions]]
+version = "2025"
+effective_from = 2025-01-01T00:00:00Z
+
+[[versions]]
+version = "2026"
+effective_from = "2026-01-01T00:00:00+01:00"
+
+[versions.thresholds]
+bulky_cm = 150
+heavy_kg = 18
+"#;
+        let expected = PolicySchedule::new(vec![
+            ("2025-01-01T00:00:00Z".parse().unwrap(), SortPolicy::default().with_version("2025")),
+            ("2025-12-31T23:00:00Z".parse().unwrap(), SortPolicy::default().with_version("2026").with_thresholds(Cm::new(150).unwrap(), Kg::new(18).unwrap()))
+        ]);
+        assert_eq!(PolicySchedule::from_source(source, PolicyFormat::Toml), Ok(expected.clone().unwrap()));
+
+        let source = r#"{
+  "versions": [
+    { "version": "2025", "effective_from": "2025-01-01T00:00:00Z" },
+    { "version": "2026", "effective_from": "2026-01-01T00:00:00+01:00", "thresholds": { "bulky_cm": 150, "heavy_kg": 18 } }
+  ]
+}"#;
+        assert_eq!(PolicySchedule::from_source(source, PolicyFormat::Json), Ok(expected.unwrap()));
+    }
+
+    #[test]
+    fn scheduled_versions_are_validated() {
+        let source = "[[versions]]\neffective_from = 2025-01-01T00:00:00Z\n\n[versions.thresholds]\nbulky_cm = 0\nheavy_kg = 20\n";
+        let error = PolicySchedule::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(5), Some(12)));
+        assert_eq!(message(error), "Invalid bulky threshold: expecting a value of 1 or more, but got 0");
+
+        let source = "[[versions]]\neffective_from = 2025-01-01T00:00:00\n";
+        let error = PolicySchedule::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(position(error.clone()), (Some(2), Some(18)));
+        assert_eq!(message(error), "Invalid effective date: expecting an RFC 3339 date and time with an offset, but got 2025-01-01T00:00:00");
+
+        let source = "[[versions]]\nversion = \"2025\"\nexpires = 2026-01-01T00:00:00Z\n";
+        let error = PolicySchedule::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert!(message(error).starts_with("unknown field `expires`"));
+
+        let source = "[[versions]]\nversion = \"2025\"\n";
+        let error = PolicySchedule::from_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Missing effective date: expecting effective_from for every version, but got none for version 2025");
+
+        let source = "versions:\n  - effective_from: 2025-01-01T00:00:00Z\n  - effective_from: 2025-01-01T00:00:00Z\n";
+        let error = PolicySchedule::from_source(source, PolicyFormat::Yaml).unwrap_err();
+        assert_eq!(message(error), "Invalid schedule: expecting one policy version per effective date, but got several from 2025-01-01T00:00:00Z");
+
+        let error = PolicySchedule::from_source("{ \"versions\": [] }", PolicyFormat::Json).unwrap_err();
+        assert_eq!(message(error), "Invalid schedule: expecting at least one policy version, but got none");
+    }
+
+    #[test]
+    fn either_kind_of_file_can_be_scheduled() {
+        let source = "[[versions]]\nversion = \"2025\"\neffective_from = 2025-01-01T00:00:00Z\n";
+        assert_eq!(PolicySchedule::from_any_source(source, PolicyFormat::Toml), PolicySchedule::from_source(source, PolicyFormat::Toml));
+
+        let source = "version = \"2026\"\n";
+        assert_eq!(
+            PolicySchedule::from_any_source(source, PolicyFormat::Toml),
+            Ok(PolicySchedule::from_policy(SortPolicy::default().with_version("2026")))
+        );
+
+        let source = "[[versions]]\nversion = \"2025\"\n";
+        let error = PolicySchedule::from_any_source(source, PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Missing effective date: expecting effective_from for every version, but got none for version 2025");
+        let error = PolicySchedule::from_any_source("{ \"thresholds\": 1 }", PolicyFormat::Json).unwrap_err();
+        assert!(message(error).starts_with("invalid type"));
+    }
+
+    #[test]
+    fn single_policies_have_no_effective_date() {
+        let error = SortPolicy::from_source("effective_from = \"2025-01-01T00:00:00Z\"\n", PolicyFormat::Toml).unwrap_err();
+        assert_eq!(message(error), "Unexpected effective date: expecting effective_from only within the versions of a schedule");
+    }
+
+    #[test]
+    fn the_format_is_derived_from_the_extension() {
+        assert_eq!(PolicyFormat::from_path(Path::new("policy.toml")), Some(PolicyFormat::Toml));
+        assert_eq!(PolicyFormat::from_path(Path::new("policy.yml")), Some(PolicyFormat::Yaml));
+        assert_eq!(PolicyFormat::from_path(Path::new("policy.yaml")), Some(PolicyFormat::Yaml));
+        assert_eq!(PolicyFormat::from_path(Path::new("policy.json")), Some(PolicyFormat::Json));
+        assert_eq!(PolicyFormat::from_path(Path::new("policy.ini")), None);
+        assert_eq!(
+            SortPolicy::from_file("policy.ini"),
+            Err(PolicyFileError::UnknownFormat("policy.ini".to_string()))
+        );
+    }
+
+    #[test]
+    fn display_invalid_policy() {
+        let error = PolicyFileError::Invalid { line: Some(3), column: Some(10), message: "unknown variant".to_string() };
+        assert_eq!(error.to_string(), "Invalid policy at line 3, column 10: unknown variant");
+        let error = PolicyFileError::Invalid { line: None, column: None, message: "unknown variant".to_string() };
+        assert_eq!(error.to_string(), "Invalid policy: unknown variant");
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use crate::measurements::cm::Cm;
+use crate::measurements::kg::Kg;
+use crate::sort_policy::SortPolicy;
+
+/**
+ * PolicyRegistry
+ *
+ * Holds the policy of every site or tenant. Each of them inherits the
+ * base policy, optionally overriding its bulky and heavy thresholds.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyRegistry {
+    base: SortPolicy,
+    sites: BTreeMap<String, SortPolicy>
+}
+
+/**
+ * ThresholdOverrides
+ *
+ * The thresholds a site or tenant sets differently from the base
+ * policy. Thresholds that aren't overridden are inherited. The bulky
+ * threshold is the one compared with the sum of the sides, and the
+ * length plus girth threshold the one compared with length plus girth.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThresholdOverrides {
+    bulky: Option<usize>,
+    length_plus_girth: Option<usize>,
+    heavy: Option<usize>
+}
+
+/**
+ * PolicyRegistryError
+ *
+ * Documents why a site or tenant could not be registered: one of its
+ * overridden thresholds is invalid.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyRegistryError {
+    InvalidBulkyThreshold(String, usize),
+    InvalidLengthPlusGirthThreshold(String, usize),
+    InvalidHeavyThreshold(String, usize)
+}
+
+impl ThresholdOverrides {
+    pub fn with_bulky_threshold(mut self, cm: usize) -> Self {
+        self.bulky = Some(cm);
+        self
+    }
+
+    pub fn with_length_plus_girth_threshold(mut self, cm: usize) -> Self {
+        self.length_plus_girth = Some(cm);
+        self
+    }
+
+    pub fn with_heavy_threshold(mut self, kg: usize) -> Self {
+        self.heavy = Some(kg);
+        self
+    }
+}
+
+impl PolicyRegistry {
+    pub fn new(base: SortPolicy) -> Self {
+        PolicyRegistry {
+            base,
+            sites: BTreeMap::new()
+        }
+    }
+
+    /**
+     * Registers a site or tenant, replacing any earlier registration
+     *
+     * The policy of the site records its own version, made up of the
+     * version of the base policy and the id of the site.
+     */
+    pub fn with_site<T: Into<String>>(mut self, site: T, overrides: ThresholdOverrides) -> Result<Self, PolicyRegistryError> {
+        let site = site.into();
+
+        let bulky = match overrides.bulky {
+            Some(cm) => Cm::new(cm).map_err(|_| PolicyRegistryError::InvalidBulkyThreshold(site.clone(), cm))?,
+            None => self.base.bulky_threshold().clone()
+        };
+        let length_plus_girth = match overrides.length_plus_girth {
+            Some(cm) => Cm::new(cm).map_err(|_| PolicyRegistryError::InvalidLengthPlusGirthThreshold(site.clone(), cm))?,
+            None => self.base.length_plus_girth_threshold().clone()
+        };
+        let heavy = match overrides.heavy {
+            Some(kg) => Kg::new(kg).map_err(|_| PolicyRegistryError::InvalidHeavyThreshold(site.clone(), kg))?,
+            None => self.base.heavy_threshold().clone()
+        };
+        let policy = self.base.clone()
+            .with_version(format!("{}/{}", self.base.version(), site))
+            .with_thresholds(bulky, heavy)
+            .with_length_plus_girth_threshold(length_plus_girth);
+
+        self.sites.insert(site, policy);
+        Ok(self)
+    }
+
+    pub fn base(&self) -> &SortPolicy {
+        &self.base
+    }
+
+    pub fn policy_for(&self, site: &str) -> Option<&SortPolicy> {
+        self.sites.get(site)
+    }
+
+    pub fn sites(&self) -> impl Iterator<Item = &str> {
+        self.sites.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Display for PolicyRegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyRegistryError::InvalidBulkyThreshold(site, cm) => write!(f, "Invalid bulky threshold for {}: expecting a value of 1 or more, but got {}", site, cm),
+            PolicyRegistryError::InvalidLengthPlusGirthThreshold(site, cm) => write!(f, "Invalid length plus girth threshold for {}: expecting a value of 1 or more, but got {}", site, cm),
+            PolicyRegistryError::InvalidHeavyThreshold(site, kg) => write!(f, "Invalid heavy threshold for {}: expecting a value of 1 or more, but got {}", site, kg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::dimensions::{DimensionClass, PackageDimensions};
+    use crate::sort_policy::bulky_strategy::BulkyStrategy;
+    use crate::sort_policy::tolerance::Tolerance;
+
+    fn base() -> SortPolicy {
+        SortPolicy::default()
+            .with_version("2026")
+            .with_tolerance(Tolerance::default().with_mass(1))
+    }
+
+    #[test]
+    fn sites_inherit_the_base_policy() {
+        let registry = PolicyRegistry::new(base()).with_site("ams", ThresholdOverrides::default()).unwrap();
+        let policy = registry.policy_for("ams").unwrap();
+
+        assert_eq!(policy.bulky_threshold(), base().bulky_threshold());
+        assert_eq!(policy.length_plus_girth_threshold(), base().length_plus_girth_threshold());
+        assert_eq!(policy.heavy_threshold(), base().heavy_threshold());
+        assert_eq!(policy.tolerance(), base().tolerance());
+        assert_eq!(policy.version(), "2026/ams");
+    }
+
+    #[test]
+    fn sites_override_thresholds() {
+        let registry = PolicyRegistry::new(base())
+            .with_site("lhr", ThresholdOverrides::default().with_heavy_threshold(18)).unwrap()
+            .with_site("3pl-acme", ThresholdOverrides::default().with_bulky_threshold(120).with_heavy_threshold(25)).unwrap();
+
+        let lhr = registry.policy_for("lhr").unwrap();
+        assert_eq!(lhr.bulky_threshold(), &Cm::new(150).unwrap());
+        assert_eq!(lhr.heavy_threshold(), &Kg::new(18).unwrap());
+
+        let acme = registry.policy_for("3pl-acme").unwrap();
+        assert_eq!(acme.bulky_threshold(), &Cm::new(120).unwrap());
+        assert_eq!(acme.heavy_threshold(), &Kg::new(25).unwrap());
+
+        assert_eq!(registry.sites().collect::<Vec<_>>(), vec!["3pl-acme", "lhr"]);
+        assert_eq!(registry.base(), &base());
+    }
+
+    #[test]
+    fn sites_override_the_threshold_of_a_girth_based_policy() {
+        let cm = |value| Cm::new(value).unwrap();
+        let registry = PolicyRegistry::new(base().with_bulky_strategy(BulkyStrategy::LengthPlusGirth))
+            .with_site("lhr", ThresholdOverrides::default().with_length_plus_girth_threshold(250)).unwrap();
+        let lhr = registry.policy_for("lhr").unwrap();
+
+        // a length plus girth of 260 cm
+        let dimensions = PackageDimensions::new(cm(100), cm(40), cm(40));
+        assert_eq!(lhr.length_plus_girth_threshold(), &cm(250));
+        assert_eq!(dimensions.classify(lhr), DimensionClass::BULKY);
+        assert_eq!(dimensions.classify(registry.base()), DimensionClass::STANDARD);
+    }
+
+    #[test]
+    fn unknown_sites_have_no_policy() {
+        assert_eq!(PolicyRegistry::new(base()).policy_for("ams"), None);
+    }
+
+    #[test]
+    fn overridden_thresholds_must_be_positive() {
+        assert_eq!(
+            PolicyRegistry::new(base()).with_site("ams", ThresholdOverrides::default().with_bulky_threshold(0)),
+            Err(PolicyRegistryError::InvalidBulkyThreshold("ams".to_string(), 0))
+        );
+        assert_eq!(
+            PolicyRegistry::new(base()).with_site("ams", ThresholdOverrides::default().with_length_plus_girth_threshold(0)),
+            Err(PolicyRegistryError::InvalidLengthPlusGirthThreshold("ams".to_string(), 0))
+        );
+        assert_eq!(
+            PolicyRegistry::new(base()).with_site("ams", ThresholdOverrides::default().with_heavy_threshold(0)),
+            Err(PolicyRegistryError::InvalidHeavyThreshold("ams".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn display_registry_errors() {
+        assert_eq!(
+            PolicyRegistryError::InvalidBulkyThreshold("ams".to_string(), 0).to_string(),
+            "Invalid bulky threshold for ams: expecting a value of 1 or more, but got 0"
+        );
+        assert_eq!(
+            PolicyRegistryError::InvalidLengthPlusGirthThreshold("ams".to_string(), 0).to_string(),
+            "Invalid length plus girth threshold for ams: expecting a value of 1 or more, but got 0"
+        );
+        assert_eq!(
+            PolicyRegistryError::InvalidHeavyThreshold("ams".to_string(), 0).to_string(),
+            "Invalid heavy threshold for ams: expecting a value of 1 or more, but got 0"
+        );
+    }
+}
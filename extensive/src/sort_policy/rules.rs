@@ -0,0 +1,604 @@
+use std::collections::BTreeSet;
+use crate::package::attributes::Attribute;
+use crate::package::destination::Destination;
+use crate::package::dimensions::{DimensionClass, PackageDimensions};
+use crate::package::mass::MassClass;
+use crate::package::service_level::ServiceLevel;
+use crate::package::sorted_dimensions::Side;
+use crate::sort_result::{SortResult, StackId};
+
+/**
+ * Predicate
+ *
+ * A condition over the facts known about a package. Predicates only
+ * ever test for the presence of a property, so a rule that matches a
+ * package without attributes also matches it with any attributes.
+ * Predicates over the destination or service level never hold for
+ * packages without one. Masses are compared by their billable weight,
+ * as when classifying them, which is their dimensional weight when
+ * that is larger and the policy enables it.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    DimensionClass(DimensionClass),
+    MassClass(MassClass),
+    SumOfSidesAtLeast(usize),
+    LengthPlusGirthAtLeast(usize),
+    SideAtLeast(Side, usize),
+    MassAtLeast(usize),
+    HasAttribute(Attribute),
+    DestinationCountry(String),
+    PostcodePrefix(String),
+    ServiceLevel(ServiceLevel)
+}
+
+/**
+ * Rule
+ *
+ * Sorts packages matching all of its predicates onto a stack. A rule
+ * without predicates matches every package. A rule can also hand out
+ * a routing key, which downstream systems use to sub-sort the packages
+ * of a stack.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    name: String,
+    predicates: Vec<Predicate>,
+    result: SortResult,
+    routing_key: Option<String>
+}
+
+/**
+ * EvaluationMode
+ *
+ * Documents how the rules of a rule set are evaluated:
+ * - first match: the first matching rule determines the stack
+ * - all match: every matching rule is considered, and the most
+ *   restrictive stack among them is used
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvaluationMode {
+    FirstMatch, AllMatch
+}
+
+/**
+ * CustomStack
+ *
+ * A stack defined by a policy, refining one of the built-in stacks: a
+ * two-person-lift stack refines the special stack, for example. When
+ * every matching rule is considered, a custom stack is more restrictive
+ * than the stack it refines, but less restrictive than the next one.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomStack {
+    id: StackId,
+    refines: SortResult
+}
+
+/**
+ * RuleSet
+ *
+ * An ordered list of rules that is guaranteed to sort every package:
+ * each combination of dimension and mass class is matched by at least
+ * one rule that doesn't depend on any other fact. Rules can only sort
+ * packages onto the built-in stacks, or onto the custom stacks declared
+ * along with them.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSet {
+    mode: EvaluationMode,
+    stacks: Vec<CustomStack>,
+    rules: Vec<Rule>
+}
+
+/**
+ * RuleSetError
+ *
+ * Documents why rules can't form a rule set:
+ * - a combination of classes isn't matched by any rule
+ * - a rule sorts packages onto a custom stack that wasn't declared
+ * - a custom stack refines another custom stack
+ * - a custom stack is declared more than once
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleSetError {
+    NotExhaustive(DimensionClass, MassClass),
+    UndeclaredStack(String, StackId),
+    NestedStack(StackId),
+    DuplicateStack(StackId)
+}
+
+/**
+ * Facts known about a package when evaluating rules. The classes are
+ * always resolved, meaning they are never uncertain.
+ */
+pub(crate) struct Facts<'a> {
+    pub(crate) dimensions: &'a PackageDimensions,
+    // in kg, as classified by the policy
+    pub(crate) billable_mass: usize,
+    pub(crate) dimension_class: DimensionClass,
+    pub(crate) mass_class: MassClass,
+    pub(crate) attributes: &'a BTreeSet<Attribute>,
+    pub(crate) destination: Option<&'a Destination>,
+    pub(crate) service_level: Option<&'a ServiceLevel>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Evaluation {
+    pub(crate) result: SortResult,
+    pub(crate) matched: Vec<(String, SortResult)>,
+    // the attributes consulted by the matched rules, in the order they were consulted
+    pub(crate) attributes: Vec<Attribute>,
+    // handed out by the first matched rule sorting onto the resulting stack
+    pub(crate) routing_key: Option<String>
+}
+
+const CLASS_COMBINATIONS: [(DimensionClass, MassClass); 4] = [
+    (DimensionClass::STANDARD, MassClass::STANDARD),
+    (DimensionClass::STANDARD, MassClass::HEAVY),
+    (DimensionClass::BULKY, MassClass::STANDARD),
+    (DimensionClass::BULKY, MassClass::HEAVY)
+];
+
+impl Predicate {
+    fn holds(&self, facts: &Facts) -> bool {
+        match self {
+            Predicate::DimensionClass(class) => &facts.dimension_class == class,
+            Predicate::MassClass(class) => &facts.mass_class == class,
+            Predicate::SumOfSidesAtLeast(cm) => facts.dimensions.sum().value() >= *cm,
+            Predicate::LengthPlusGirthAtLeast(cm) => facts.dimensions.sorted().length_plus_girth() >= *cm,
+            Predicate::SideAtLeast(side, cm) => facts.dimensions.sorted().side(side).value() >= *cm,
+            Predicate::MassAtLeast(kg) => facts.billable_mass >= *kg,
+            Predicate::HasAttribute(attribute) => facts.attributes.contains(attribute),
+            Predicate::DestinationCountry(country) => facts.destination.is_some_and(|destination| destination.is_in_country(country)),
+            Predicate::PostcodePrefix(prefix) => facts.destination.is_some_and(|destination| destination.has_postcode_prefix(prefix)),
+            Predicate::ServiceLevel(level) => facts.service_level == Some(level)
+        }
+    }
+
+    // whether the predicate holds for every package of the given classes
+    fn always_holds_for(&self, dimension_class: &DimensionClass, mass_class: &MassClass) -> bool {
+        match self {
+            Predicate::DimensionClass(class) => class == dimension_class,
+            Predicate::MassClass(class) => class == mass_class,
+            _ => false
+        }
+    }
+}
+
+impl Rule {
+    pub fn new<T: Into<String>>(name: T, result: SortResult) -> Self {
+        Rule {
+            name: name.into(),
+            predicates: vec![],
+            result,
+            routing_key: None
+        }
+    }
+
+    pub fn when(mut self, predicate: Predicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    pub fn with_routing_key<T: Into<String>>(mut self, routing_key: T) -> Self {
+        self.routing_key = Some(routing_key.into());
+        self
+    }
+
+    fn matches(&self, facts: &Facts) -> bool {
+        self.predicates.iter().all(|predicate| predicate.holds(facts))
+    }
+
+    fn attributes(&self) -> impl Iterator<Item = &Attribute> {
+        self.predicates.iter().filter_map(|predicate| match predicate {
+            Predicate::HasAttribute(attribute) => Some(attribute),
+            _ => None
+        })
+    }
+}
+
+impl CustomStack {
+    pub fn new(id: StackId, refines: SortResult) -> Self {
+        CustomStack {
+            id,
+            refines
+        }
+    }
+
+    pub fn id(&self) -> &StackId {
+        &self.id
+    }
+
+    pub fn refines(&self) -> &SortResult {
+        &self.refines
+    }
+}
+
+impl Default for RuleSet {
+    /**
+     * The rules of the original specification
+     */
+    fn default() -> Self {
+        RuleSet {
+            mode: EvaluationMode::FirstMatch,
+            stacks: vec![],
+            rules: vec![
+                Rule::new("bulky and heavy", SortResult::Rejected)
+                    .when(Predicate::DimensionClass(DimensionClass::BULKY))
+                    .when(Predicate::MassClass(MassClass::HEAVY)),
+                Rule::new("bulky", SortResult::Special)
+                    .when(Predicate::DimensionClass(DimensionClass::BULKY)),
+                Rule::new("heavy", SortResult::Special)
+                    .when(Predicate::MassClass(MassClass::HEAVY)),
+                Rule::new("standard", SortResult::Standard)
+            ]
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn new(mode: EvaluationMode, rules: Vec<Rule>) -> Result<Self, RuleSetError> {
+        RuleSet::with_stacks(mode, vec![], rules)
+    }
+
+    /**
+     * Creates a rule set sorting packages onto custom stacks as well as
+     * onto the built-in ones
+     */
+    pub fn with_stacks(mode: EvaluationMode, stacks: Vec<CustomStack>, rules: Vec<Rule>) -> Result<Self, RuleSetError> {
+        for (index, stack) in stacks.iter().enumerate() {
+            if !stack.refines.is_built_in() {
+                return Err(RuleSetError::NestedStack(stack.id.clone()));
+            }
+            if stacks[..index].iter().any(|other| other.id == stack.id) {
+                return Err(RuleSetError::DuplicateStack(stack.id.clone()));
+            }
+        }
+        for rule in &rules {
+            if let SortResult::Custom(id) = &rule.result {
+                if !stacks.iter().any(|stack| &stack.id == id) {
+                    return Err(RuleSetError::UndeclaredStack(rule.name.clone(), id.clone()));
+                }
+            }
+        }
+
+        for (dimension_class, mass_class) in CLASS_COMBINATIONS {
+            let covered = rules.iter().any(|rule| {
+                rule.predicates.iter().all(|predicate| predicate.always_holds_for(&dimension_class, &mass_class))
+            });
+            if !covered {
+                return Err(RuleSetError::NotExhaustive(dimension_class, mass_class));
+            }
+        }
+
+        Ok(RuleSet { mode, stacks, rules })
+    }
+
+    pub fn stacks(&self) -> &[CustomStack] {
+        &self.stacks
+    }
+
+    /**
+     * The built-in stack a result refines, which is the result itself
+     * for the built-in stacks
+     */
+    pub(crate) fn built_in(&self, result: &SortResult) -> SortResult {
+        match result {
+            SortResult::Custom(id) => self.stacks.iter()
+                .find(|stack| &stack.id == id)
+                .map(|stack| stack.refines.clone())
+                .expect("custom stacks are declared"),
+            built_in => built_in.clone()
+        }
+    }
+
+    pub(crate) fn declares(&self, id: &StackId) -> bool {
+        self.stacks.iter().any(|stack| &stack.id == id)
+    }
+
+    // custom stacks rank right above the stack they refine
+    pub(crate) fn severity(&self, result: &SortResult) -> (u8, u8) {
+        let rank = match self.built_in(result) {
+            SortResult::Standard => 0,
+            SortResult::Special => 1,
+            _ => 2
+        };
+        (rank, !result.is_built_in() as u8)
+    }
+
+    pub(crate) fn evaluate(&self, facts: &Facts) -> Evaluation {
+        let mut matching = self.rules.iter().filter(|rule| rule.matches(facts));
+        let matched: Vec<&Rule> = match self.mode {
+            EvaluationMode::FirstMatch => matching.next().into_iter().collect(),
+            EvaluationMode::AllMatch => matching.collect()
+        };
+
+        let result = matched.iter()
+            .map(|rule| rule.result.clone())
+            .max_by_key(|result| self.severity(result))
+            .expect("rule sets are exhaustive");
+
+        let mut attributes: Vec<Attribute> = vec![];
+        for attribute in matched.iter().flat_map(|rule| rule.attributes()) {
+            if !attributes.contains(attribute) {
+                attributes.push(attribute.clone());
+            }
+        }
+
+        let routing_key = matched.iter()
+            .filter(|rule| rule.result == result)
+            .find_map(|rule| rule.routing_key.clone());
+
+        Evaluation {
+            result,
+            matched: matched.iter().map(|rule| (rule.name.clone(), rule.result.clone())).collect(),
+            attributes,
+            routing_key
+        }
+    }
+}
+
+impl std::fmt::Display for RuleSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RuleSetError::NotExhaustive(dimension_class, mass_class) => write!(
+                f,
+                "Rules are not exhaustive: expecting a rule for {} dimensions and {} mass, but got none",
+                dimension_class,
+                mass_class
+            ),
+            RuleSetError::UndeclaredStack(rule, stack) => write!(f, "Undeclared stack: expecting rule '{}' to sort onto a declared stack, but got {}", rule, stack),
+            RuleSetError::NestedStack(stack) => write!(f, "Invalid stack {}: expecting it to refine a built-in stack, but got a custom stack", stack),
+            RuleSetError::DuplicateStack(stack) => write!(f, "Duplicate stack: expecting a single declaration of {}, but got several", stack)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurements::cm::Cm;
+
+    fn evaluate(rules: &RuleSet, dimension_class: DimensionClass, mass_class: MassClass, attributes: &[Attribute]) -> Evaluation {
+        evaluate_routed(rules, dimension_class, mass_class, attributes, None, None)
+    }
+
+    fn evaluate_routed(
+        rules: &RuleSet,
+        dimension_class: DimensionClass,
+        mass_class: MassClass,
+        attributes: &[Attribute],
+        destination: Option<&Destination>,
+        service_level: Option<&ServiceLevel>
+    ) -> Evaluation {
+        let dimensions = PackageDimensions::new(Cm::new(60).unwrap(), Cm::new(50).unwrap(), Cm::new(40).unwrap());
+        let attributes = attributes.iter().cloned().collect();
+        rules.evaluate(&Facts {
+            dimensions: &dimensions,
+            billable_mass: 25,
+            dimension_class,
+            mass_class,
+            attributes: &attributes,
+            destination,
+            service_level
+        })
+    }
+
+    fn fragile_rules(mode: EvaluationMode) -> RuleSet {
+        RuleSet::new(mode, vec![
+            Rule::new("fragile", SortResult::Special).when(Predicate::HasAttribute(Attribute::Fragile)),
+            Rule::new("very heavy", SortResult::Rejected).when(Predicate::MassAtLeast(25)),
+            Rule::new("large", SortResult::Special).when(Predicate::SumOfSidesAtLeast(200)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap()
+    }
+
+    #[test]
+    fn the_default_rules_honor_the_original_specification() {
+        let rules = RuleSet::default();
+        assert_eq!(evaluate(&rules, DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Standard);
+        assert_eq!(evaluate(&rules, DimensionClass::BULKY, MassClass::STANDARD, &[]).result, SortResult::Special);
+        assert_eq!(evaluate(&rules, DimensionClass::STANDARD, MassClass::HEAVY, &[]).result, SortResult::Special);
+        assert_eq!(evaluate(&rules, DimensionClass::BULKY, MassClass::HEAVY, &[]).result, SortResult::Rejected);
+    }
+
+    #[test]
+    fn the_default_rules_are_exhaustive() {
+        let default = RuleSet::default();
+        assert_eq!(RuleSet::new(default.mode.clone(), default.rules.clone()), Ok(default));
+    }
+
+    #[test]
+    fn rules_can_test_length_plus_girth() {
+        // the evaluated package has a length plus girth of 60 + 2 × (50 + 40) = 240 cm
+        let oversize = |cm| RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("oversize", SortResult::Special).when(Predicate::LengthPlusGirthAtLeast(cm)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(evaluate(&oversize(240), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Special);
+        assert_eq!(evaluate(&oversize(241), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Standard);
+    }
+
+    #[test]
+    fn rules_can_test_a_specific_side() {
+        // the evaluated package measures 60 × 50 × 40 cm
+        let chute = |side, cm| RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("does not fit the chute", SortResult::Special).when(Predicate::SideAtLeast(side, cm)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        assert_eq!(evaluate(&chute(Side::Shortest, 40), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Special);
+        assert_eq!(evaluate(&chute(Side::Shortest, 41), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Standard);
+        assert_eq!(evaluate(&chute(Side::Middle, 50), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Special);
+        assert_eq!(evaluate(&chute(Side::Longest, 61), DimensionClass::STANDARD, MassClass::STANDARD, &[]).result, SortResult::Standard);
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let evaluation = evaluate(&fragile_rules(EvaluationMode::FirstMatch), DimensionClass::STANDARD, MassClass::HEAVY, &[Attribute::Fragile]);
+        assert_eq!(evaluation.result, SortResult::Special);
+        assert_eq!(evaluation.matched, vec![("fragile".to_string(), SortResult::Special)]);
+    }
+
+    #[test]
+    fn all_matching_rules_pick_the_most_restrictive_stack() {
+        let evaluation = evaluate(&fragile_rules(EvaluationMode::AllMatch), DimensionClass::STANDARD, MassClass::HEAVY, &[Attribute::Fragile]);
+        assert_eq!(evaluation.result, SortResult::Rejected);
+        assert_eq!(evaluation.matched, vec![
+            ("fragile".to_string(), SortResult::Special),
+            ("very heavy".to_string(), SortResult::Rejected),
+            ("anything else".to_string(), SortResult::Standard)
+        ]);
+    }
+
+    fn express_rules(mode: EvaluationMode) -> RuleSet {
+        RuleSet::new(mode, vec![
+            Rule::new("express to central london", SortResult::Special)
+                .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                .when(Predicate::DestinationCountry("GB".to_string()))
+                .when(Predicate::PostcodePrefix("EC".to_string()))
+                .with_routing_key("london-ec"),
+            Rule::new("express to germany", SortResult::Special)
+                .when(Predicate::ServiceLevel(ServiceLevel::Express))
+                .when(Predicate::DestinationCountry("de".to_string()))
+                .with_routing_key("germany"),
+            Rule::new("anything else", SortResult::Standard).with_routing_key("domestic")
+        ]).unwrap()
+    }
+
+    #[test]
+    fn rules_can_test_the_destination_and_service_level() {
+        let routed = |destination: Option<Destination>, level: Option<ServiceLevel>| {
+            let evaluation = evaluate_routed(&express_rules(EvaluationMode::FirstMatch), DimensionClass::STANDARD, MassClass::STANDARD, &[], destination.as_ref(), level.as_ref());
+            (evaluation.result, evaluation.routing_key)
+        };
+        let london = Destination::new("GB").unwrap().with_postcode("EC1A 1BB").unwrap();
+        let berlin = Destination::new("DE").unwrap().with_postcode("10115").unwrap();
+
+        assert_eq!(routed(Some(london.clone()), Some(ServiceLevel::Express)), (SortResult::Special, Some("london-ec".to_string())));
+        assert_eq!(routed(Some(berlin.clone()), Some(ServiceLevel::Express)), (SortResult::Special, Some("germany".to_string())));
+        assert_eq!(routed(Some(london), Some(ServiceLevel::Standard)), (SortResult::Standard, Some("domestic".to_string())));
+        assert_eq!(routed(Some(Destination::new("GB").unwrap()), Some(ServiceLevel::Express)), (SortResult::Standard, Some("domestic".to_string())));
+        assert_eq!(routed(None, Some(ServiceLevel::Express)), (SortResult::Standard, Some("domestic".to_string())));
+        assert_eq!(routed(Some(berlin), None), (SortResult::Standard, Some("domestic".to_string())));
+    }
+
+    #[test]
+    fn the_routing_key_comes_from_a_rule_sorting_onto_the_resulting_stack() {
+        let berlin = Destination::new("DE").unwrap();
+        let evaluation = evaluate_routed(&express_rules(EvaluationMode::AllMatch), DimensionClass::STANDARD, MassClass::STANDARD, &[], Some(&berlin), Some(&ServiceLevel::Express));
+        assert_eq!(evaluation.result, SortResult::Special);
+        assert_eq!(evaluation.routing_key, Some("germany".to_string()));
+
+        let evaluation = evaluate(&RuleSet::default(), DimensionClass::STANDARD, MassClass::STANDARD, &[]);
+        assert_eq!(evaluation.routing_key, None);
+    }
+
+    #[test]
+    fn rules_must_cover_every_combination_of_classes() {
+        let rules = vec![
+            Rule::new("bulky", SortResult::Special).when(Predicate::DimensionClass(DimensionClass::BULKY)),
+            Rule::new("standard", SortResult::Standard).when(Predicate::MassClass(MassClass::STANDARD)),
+            Rule::new("fragile", SortResult::Special).when(Predicate::HasAttribute(Attribute::Fragile))
+        ];
+        assert_eq!(
+            RuleSet::new(EvaluationMode::FirstMatch, rules),
+            Err(RuleSetError::NotExhaustive(DimensionClass::STANDARD, MassClass::HEAVY))
+        );
+    }
+
+    #[test]
+    fn display_not_exhaustive() {
+        assert_eq!(
+            RuleSetError::NotExhaustive(DimensionClass::STANDARD, MassClass::HEAVY).to_string(),
+            "Rules are not exhaustive: expecting a rule for standard dimensions and heavy mass, but got none"
+        );
+    }
+
+    fn stack(name: &str) -> StackId {
+        StackId::new(name).unwrap()
+    }
+
+    fn floor_rules(mode: EvaluationMode) -> Result<RuleSet, RuleSetError> {
+        RuleSet::with_stacks(mode, vec![
+            CustomStack::new(stack("two-person-lift"), SortResult::Special),
+            CustomStack::new(stack("liquids"), SortResult::Standard)
+        ], vec![
+            Rule::new("two person lift", SortResult::Custom(stack("two-person-lift"))).when(Predicate::MassAtLeast(25)),
+            Rule::new("liquid", SortResult::Custom(stack("liquids"))).when(Predicate::HasAttribute(Attribute::Liquid)),
+            Rule::new("bulky", SortResult::Special).when(Predicate::DimensionClass(DimensionClass::BULKY)),
+            Rule::new("very bulky", SortResult::Rejected).when(Predicate::SumOfSidesAtLeast(150)),
+            Rule::new("anything else", SortResult::Standard)
+        ])
+    }
+
+    #[test]
+    fn rules_can_sort_onto_custom_stacks() {
+        let evaluation = evaluate(&floor_rules(EvaluationMode::FirstMatch).unwrap(), DimensionClass::STANDARD, MassClass::HEAVY, &[]);
+        assert_eq!(evaluation.result, SortResult::Custom(stack("two-person-lift")));
+    }
+
+    #[test]
+    fn custom_stacks_rank_right_above_the_stack_they_refine() {
+        let rules = floor_rules(EvaluationMode::AllMatch).unwrap();
+        // the evaluated package weighs 25 kg, and measures 150 cm in total
+        let evaluation = evaluate(&rules, DimensionClass::BULKY, MassClass::HEAVY, &[Attribute::Liquid]);
+        assert_eq!(evaluation.result, SortResult::Rejected);
+
+        let without_very_bulky = RuleSet::with_stacks(EvaluationMode::AllMatch, rules.stacks().to_vec(), rules.rules.iter()
+            .filter(|rule| rule.name != "very bulky")
+            .cloned()
+            .collect()
+        ).unwrap();
+        let evaluation = evaluate(&without_very_bulky, DimensionClass::BULKY, MassClass::HEAVY, &[Attribute::Liquid]);
+        assert_eq!(evaluation.result, SortResult::Custom(stack("two-person-lift")));
+        assert_eq!(rules.built_in(&evaluation.result), SortResult::Special);
+        assert_eq!(rules.built_in(&SortResult::Rejected), SortResult::Rejected);
+    }
+
+    #[test]
+    fn custom_stacks_must_be_declared() {
+        let rules = vec![
+            Rule::new("two person lift", SortResult::Custom(stack("two-person-lift"))).when(Predicate::MassAtLeast(25)),
+            Rule::new("anything else", SortResult::Standard)
+        ];
+        assert_eq!(
+            RuleSet::new(EvaluationMode::FirstMatch, rules),
+            Err(RuleSetError::UndeclaredStack("two person lift".to_string(), stack("two-person-lift")))
+        );
+    }
+
+    #[test]
+    fn custom_stacks_refine_built_in_stacks_once() {
+        let anything = vec![Rule::new("anything", SortResult::Standard)];
+        assert_eq!(
+            RuleSet::with_stacks(EvaluationMode::FirstMatch, vec![
+                CustomStack::new(stack("a"), SortResult::Custom(stack("b")))
+            ], anything.clone()),
+            Err(RuleSetError::NestedStack(stack("a")))
+        );
+        assert_eq!(
+            RuleSet::with_stacks(EvaluationMode::FirstMatch, vec![
+                CustomStack::new(stack("a"), SortResult::Special),
+                CustomStack::new(stack("a"), SortResult::Rejected)
+            ], anything),
+            Err(RuleSetError::DuplicateStack(stack("a")))
+        );
+    }
+
+    #[test]
+    fn display_stack_errors() {
+        assert_eq!(
+            RuleSetError::UndeclaredStack("lift".to_string(), stack("two-person-lift")).to_string(),
+            "Undeclared stack: expecting rule 'lift' to sort onto a declared stack, but got two-person-lift"
+        );
+        assert_eq!(
+            RuleSetError::NestedStack(stack("a")).to_string(),
+            "Invalid stack a: expecting it to refine a built-in stack, but got a custom stack"
+        );
+        assert_eq!(
+            RuleSetError::DuplicateStack(stack("a")).to_string(),
+            "Duplicate stack: expecting a single declaration of a, but got several"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use crate::package_input::PackageInput;
+use crate::sort_decision::SortDecision;
+use crate::sort_error::SortError;
+use crate::sort_policy::SortPolicy;
+
+/**
+ * PolicySchedule
+ *
+ * Holds the successive versions of a policy, each taking effect at its
+ * own date. Sorting at a given moment uses the version that was in
+ * effect at that moment, so that re-sorting historical packages
+ * reproduces the decisions made at the time.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicySchedule {
+    // ordered by the date at which they take effect
+    versions: Vec<(DateTime<Utc>, SortPolicy)>
+}
+
+/**
+ * PolicyScheduleError
+ *
+ * Documents why versions of a policy can't be scheduled:
+ * - there are no versions
+ * - two versions take effect at the same date
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyScheduleError {
+    Empty,
+    DuplicateEffectiveDate(DateTime<Utc>)
+}
+
+impl PolicySchedule {
+    pub fn new(mut versions: Vec<(DateTime<Utc>, SortPolicy)>) -> Result<Self, PolicyScheduleError> {
+        if versions.is_empty() {
+            return Err(PolicyScheduleError::Empty);
+        }
+
+        versions.sort_by_key(|(effective_from, _)| *effective_from);
+        if let Some(pair) = versions.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(PolicyScheduleError::DuplicateEffectiveDate(pair[0].0));
+        }
+
+        Ok(PolicySchedule { versions })
+    }
+
+    /**
+     * Schedules a single policy, in effect at any moment
+     */
+    pub fn from_policy(policy: SortPolicy) -> Self {
+        PolicySchedule { versions: vec![(DateTime::<Utc>::MIN_UTC, policy)] }
+    }
+
+    /**
+     * Every version along with the date at which it takes effect, from
+     * the earliest to the latest
+     */
+    pub fn versions(&self) -> &[(DateTime<Utc>, SortPolicy)] {
+        &self.versions
+    }
+
+    /**
+     * The version of the policy in effect at the given moment
+     */
+    pub fn policy_at(&self, timestamp: DateTime<Utc>) -> Option<&SortPolicy> {
+        self.versions.iter()
+            .rev()
+            .find(|(effective_from, _)| *effective_from <= timestamp)
+            .map(|(_, policy)| policy)
+    }
+
+    /**
+     * Sorts a package as it would have been at the given moment
+     *
+     * Moments before the earliest version took effect can't be sorted.
+     */
+    pub fn sort_at(&self, input: &PackageInput, timestamp: DateTime<Utc>) -> Result<SortDecision, SortError> {
+        let policy = self.policy_at(timestamp)
+            .ok_or(SortError::NoPolicyInEffect(timestamp, self.versions[0].0))?;
+
+        crate::sort_package(input, policy)
+    }
+}
+
+pub(crate) fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl std::fmt::Display for PolicyScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyScheduleError::Empty => write!(f, "Invalid schedule: expecting at least one policy version, but got none"),
+            PolicyScheduleError::DuplicateEffectiveDate(date) => write!(f, "Invalid schedule: expecting one policy version per effective date, but got several from {}", format_timestamp(date))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurements::cm::Cm;
+    use crate::measurements::kg::Kg;
+    use crate::sort_decision::SortOutcome;
+    use crate::sort_result::SortResult;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        timestamp.parse().unwrap()
+    }
+
+    fn heavy_from(kg: usize, version: &str) -> SortPolicy {
+        SortPolicy::default()
+            .with_version(version)
+            .with_thresholds(Cm::new(150).unwrap(), Kg::new(kg).unwrap())
+    }
+
+    fn schedule() -> PolicySchedule {
+        PolicySchedule::new(vec![
+            (at("2026-01-01T00:00:00Z"), heavy_from(18, "2026")),
+            (at("2025-01-01T00:00:00Z"), heavy_from(20, "2025"))
+        ]).unwrap()
+    }
+
+    #[test]
+    fn the_version_in_effect_is_picked() {
+        let schedule = schedule();
+        assert_eq!(schedule.policy_at(at("2025-06-30T12:00:00Z")).map(SortPolicy::version), Some("2025"));
+        assert_eq!(schedule.policy_at(at("2025-12-31T23:59:59Z")).map(SortPolicy::version), Some("2025"));
+        assert_eq!(schedule.policy_at(at("2026-01-01T00:00:00Z")).map(SortPolicy::version), Some("2026"));
+        assert_eq!(schedule.policy_at(at("2024-12-31T23:59:59Z")), None);
+    }
+
+    #[test]
+    fn historical_sorts_reproduce_the_decision_made_at_the_time() {
+        let schedule = schedule();
+        let package = PackageInput::new(10, 10, 10, 19);
+
+        let before = schedule.sort_at(&package, at("2025-12-31T23:59:59Z")).unwrap();
+        assert_eq!(before.outcome(), &SortOutcome::Sorted(SortResult::Standard));
+        assert_eq!(before.policy_version(), "2025");
+
+        let after = schedule.sort_at(&package, at("2026-01-01T00:00:00Z")).unwrap();
+        assert_eq!(after.outcome(), &SortOutcome::Sorted(SortResult::Special));
+        assert_eq!(after.policy_version(), "2026");
+    }
+
+    #[test]
+    fn moments_before_the_earliest_version_can_not_be_sorted() {
+        assert_eq!(
+            schedule().sort_at(&PackageInput::new(10, 10, 10, 19), at("2024-01-01T00:00:00Z")),
+            Err(SortError::NoPolicyInEffect(at("2024-01-01T00:00:00Z"), at("2025-01-01T00:00:00Z")))
+        );
+    }
+
+    #[test]
+    fn invalid_packages_are_still_reported() {
+        assert_eq!(
+            schedule().sort_at(&PackageInput::new(10, 10, 10, 0), at("2026-01-01T00:00:00Z")),
+            Err(SortError::InvalidMass(0))
+        );
+    }
+
+    #[test]
+    fn a_single_policy_is_always_in_effect() {
+        let schedule = PolicySchedule::from_policy(heavy_from(18, "2026"));
+        assert_eq!(schedule.policy_at(DateTime::<Utc>::MIN_UTC).map(SortPolicy::version), Some("2026"));
+        assert_eq!(schedule.policy_at(at("2026-10-19T08:00:00Z")).map(SortPolicy::version), Some("2026"));
+        assert_eq!(schedule.versions().len(), 1);
+    }
+
+    #[test]
+    fn schedules_need_versions() {
+        assert_eq!(PolicySchedule::new(vec![]), Err(PolicyScheduleError::Empty));
+    }
+
+    #[test]
+    fn versions_take_effect_at_distinct_dates() {
+        let date = at("2026-01-01T00:00:00Z");
+        assert_eq!(
+            PolicySchedule::new(vec![(date, heavy_from(18, "a")), (date, heavy_from(20, "b"))]),
+            Err(PolicyScheduleError::DuplicateEffectiveDate(date))
+        );
+    }
+
+    #[test]
+    fn display_schedule_errors() {
+        assert_eq!(PolicyScheduleError::Empty.to_string(), "Invalid schedule: expecting at least one policy version, but got none");
+        assert_eq!(
+            PolicyScheduleError::DuplicateEffectiveDate(at("2026-01-01T00:00:00Z")).to_string(),
+            "Invalid schedule: expecting one policy version per effective date, but got several from 2026-01-01T00:00:00Z"
+        );
+    }
+}
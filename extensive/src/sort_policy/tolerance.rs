@@ -0,0 +1,85 @@
+/**
+ * Tolerance
+ *
+ * Documents the accuracy of the measuring equipment: every side
+ * reported by the dimensioner may be off by `dimension` cm, and the
+ * mass reported by the scale may be off by `mass` kg.
+ *
+ * Measurements that could fall on either side of a threshold within
+ * this tolerance are classified as uncertain.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    dimension: usize,
+    mass: usize
+}
+
+impl Tolerance {
+    pub fn with_dimension(mut self, cm: usize) -> Self {
+        self.dimension = cm;
+        self
+    }
+
+    pub fn with_mass(mut self, kg: usize) -> Self {
+        self.mass = kg;
+        self
+    }
+
+    pub(crate) fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub(crate) fn mass(&self) -> usize {
+        self.mass
+    }
+}
+
+/**
+ * Band
+ *
+ * Position of a measured value relative to a threshold, taking the
+ * tolerance of the measurement into account.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Band {
+    Below, Uncertain, AtOrAbove
+}
+
+impl Band {
+    pub(crate) fn of(value: usize, threshold: usize, tolerance: usize) -> Band {
+        if value.saturating_add(tolerance) < threshold {
+            Band::Below
+        } else if value.saturating_sub(tolerance) >= threshold {
+            Band::AtOrAbove
+        } else {
+            Band::Uncertain
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[quickcheck]
+    fn without_tolerance_nothing_is_uncertain(value: usize, threshold: usize) -> bool {
+        Band::of(value, threshold, 0) != Band::Uncertain
+    }
+
+    #[quickcheck]
+    fn certain_bands_agree_with_the_exact_comparison(value: usize, threshold: usize, tolerance: u8) -> bool {
+        match Band::of(value, threshold, tolerance as usize) {
+            Band::Below => value < threshold,
+            Band::AtOrAbove => value >= threshold,
+            Band::Uncertain => true
+        }
+    }
+
+    #[test]
+    fn values_within_the_tolerance_of_the_threshold_are_uncertain() {
+        assert_eq!(Band::of(18, 20, 1), Band::Below);
+        assert_eq!(Band::of(19, 20, 1), Band::Uncertain);
+        assert_eq!(Band::of(20, 20, 1), Band::Uncertain);
+        assert_eq!(Band::of(21, 20, 1), Band::AtOrAbove);
+    }
+}
@@ -1,4 +1,6 @@
 use std::fmt::Formatter;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /**
  * SortResult
@@ -8,10 +10,50 @@ use std::fmt::Formatter;
  * - standard
  * - special
  * - rejected
+ * - one of the custom stacks defined by a policy, such as
+ *   two-person-lift, each refining one of the built-in stacks
  */
 #[derive(Clone, Debug, PartialEq)]
 pub enum SortResult {
-    Standard, Special, Rejected
+    Standard, Special, Rejected, Custom(StackId)
+}
+
+/**
+ * StackId
+ *
+ * The name of a custom stack, made of lowercase letters, digits, '-'
+ * and '_'. The names of the built-in stacks can't be reused.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StackId(String);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StackIdError {
+    InvalidName(String)
+}
+
+const BUILT_IN: [&str; 3] = ["standard", "special", "rejected"];
+
+impl StackId {
+    pub fn new<T: Into<String>>(name: T) -> Result<Self, StackIdError> {
+        let name = name.into();
+        let valid_characters = name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if name.is_empty() || !valid_characters || BUILT_IN.contains(&name.as_str()) {
+            return Err(StackIdError::InvalidName(name));
+        }
+
+        Ok(StackId(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl SortResult {
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, SortResult::Custom(_))
+    }
 }
 
 #[cfg(test)]
@@ -26,15 +68,64 @@ impl std::fmt::Display for SortResult {
         match self {
             SortResult::Standard => write!(f, "standard"),
             SortResult::Special => write!(f, "special"),
-            SortResult::Rejected => write!(f, "rejected")
+            SortResult::Rejected => write!(f, "rejected"),
+            SortResult::Custom(stack) => write!(f, "{}", stack)
+        }
+    }
+}
+
+impl std::fmt::Display for StackId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for StackIdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StackIdError::InvalidName(name) => write!(
+                f,
+                "Invalid stack name: expecting lowercase letters, digits, '-' or '_' other than a built-in stack, but got '{}'",
+                name
+            )
+        }
+    }
+}
+
+impl FromStr for SortResult {
+    type Err = StackIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(SortResult::Standard),
+            "special" => Ok(SortResult::Special),
+            "rejected" => Ok(SortResult::Rejected),
+            name => StackId::new(name).map(SortResult::Custom)
         }
     }
 }
 
+impl Serialize for SortResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SortResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn custom(name: &str) -> SortResult {
+        SortResult::Custom(StackId::new(name).unwrap())
+    }
+
     #[test]
     fn test_any() {
         assert_eq!(SortResult::all(), [SortResult::Standard, SortResult::Special, SortResult::Rejected]);
@@ -54,4 +145,56 @@ mod tests {
     fn display_rejected() {
         assert_eq!(SortResult::Rejected.to_string(), "rejected");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn display_custom() {
+        assert_eq!(custom("two-person-lift").to_string(), "two-person-lift");
+    }
+
+    #[test]
+    fn stack_names_are_validated() {
+        assert!(StackId::new("oversize_light").is_ok());
+        assert!(StackId::new("lane-2").is_ok());
+        assert_eq!(StackId::new(""), Err(StackIdError::InvalidName("".to_string())));
+        assert_eq!(StackId::new("Two Person"), Err(StackIdError::InvalidName("Two Person".to_string())));
+        assert_eq!(StackId::new("special"), Err(StackIdError::InvalidName("special".to_string())));
+    }
+
+    #[test]
+    fn display_invalid_stack_names() {
+        assert_eq!(
+            StackIdError::InvalidName("Two Person".to_string()).to_string(),
+            "Invalid stack name: expecting lowercase letters, digits, '-' or '_' other than a built-in stack, but got 'Two Person'"
+        );
+    }
+
+    #[test]
+    fn only_custom_stacks_are_not_built_in() {
+        assert!(SortResult::all().iter().all(SortResult::is_built_in));
+        assert!(!custom("conveyable-irregular").is_built_in());
+    }
+
+    #[quickcheck]
+    fn display_round_trips(name: String) -> bool {
+        let results = match StackId::new(name) {
+            Ok(stack) => vec![SortResult::Custom(stack)],
+            Err(_) => SortResult::all().to_vec()
+        };
+        results.iter().all(|result| result.to_string().parse::<SortResult>() == Ok(result.clone()))
+    }
+
+    #[test]
+    fn serde_round_trips() {
+        for result in [SortResult::Standard, SortResult::Special, SortResult::Rejected, custom("two-person-lift")] {
+            let json = serde_json::to_string(&result).unwrap();
+            assert_eq!(json, format!("\"{}\"", result));
+            assert_eq!(serde_json::from_str::<SortResult>(&json).unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn invalid_names_are_not_deserialized() {
+        let error = serde_json::from_str::<SortResult>("\"Two Person\"").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid stack name"));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::package_input::PackageInput;
+use crate::sort_decision::{SortDecision, SortOutcome};
+use crate::sort_error::SortError;
+use crate::sort_policy::SortPolicy;
+use crate::sort_result::SortResult;
+
+/**
+ * Divisibility
+ *
+ * Documents where the contents of a load can be cut, always across
+ * its longest side:
+ * - anywhere, in steps of a whole cm
+ * - only between the given number of identical units, laid out along
+ *   the longest side, such as the boxes or the pipes making up the load
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Divisibility {
+    Anywhere,
+    Units(usize)
+}
+
+/**
+ * SplitPlan
+ *
+ * The packages a load is split into, each with the decision it was
+ * validated with. None of them is rejected.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitPlan {
+    pieces: Vec<(PackageInput, SortDecision)>,
+    specials: usize
+}
+
+// how severe a decision is: standard, special, or unacceptable
+type Rank = u8;
+const STANDARD: Rank = 0;
+const SPECIAL: Rank = 1;
+const UNACCEPTABLE: Rank = 2;
+
+impl SplitPlan {
+    pub fn pieces(&self) -> &[(PackageInput, SortDecision)] {
+        &self.pieces
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /**
+     * The number of packages that aren't standard with certainty
+     */
+    pub fn specials(&self) -> usize {
+        self.specials
+    }
+}
+
+/**
+ * Plans how to split a load into the fewest packages that are each
+ * sorted as standard or special, with as few special ones as possible
+ *
+ * The load is cut across its longest side, sharing its mass in
+ * proportion, and rounding lengths and masses up. Packages whose
+ * stack is uncertain count as special, as long as they can't be
+ * rejected. Returns nothing when even the smallest possible package
+ * would be rejected.
+ */
+pub(crate) fn plan(load: &PackageInput, divisibility: &Divisibility, policy: &SortPolicy) -> Result<Option<SplitPlan>, SortError> {
+    // validates the load itself before planning
+    crate::sort_package(load, policy)?;
+
+    let units = match divisibility {
+        Divisibility::Anywhere => longest(load),
+        Divisibility::Units(0) => return Err(SortError::InvalidUnitCount(0)),
+        Divisibility::Units(count) => *count
+    };
+    let rank_of = |count| rank(&crate::sort_package(&piece(load, count, units), policy).expect("pieces hold valid measurements"), policy);
+
+    let most_special = most_units(units, SPECIAL, rank_of);
+    if most_special == 0 {
+        return Ok(None);
+    }
+    let most_standard = most_units(units, STANDARD, rank_of);
+
+    let packages = units.div_ceil(most_special);
+    let standards = match most_special - most_standard {
+        0 => packages,
+        difference => ((packages * most_special - units) / difference).min(packages)
+    };
+
+    let pieces = share(units, packages, standards, most_standard).into_iter()
+        .map(|count| {
+            let input = piece(load, count, units);
+            let decision = crate::sort_package(&input, policy)?;
+            Ok((input, decision))
+        })
+        .collect::<Result<Vec<_>, SortError>>()?;
+
+    // rules that aren't monotonous in the size of a package can still reject a piece
+    let ranks: Vec<Rank> = pieces.iter().map(|(_, decision)| rank(decision, policy)).collect();
+    match ranks.iter().all(|rank| *rank < UNACCEPTABLE) {
+        true => Ok(Some(SplitPlan { specials: ranks.iter().filter(|rank| **rank == SPECIAL).count(), pieces })),
+        false => Ok(None)
+    }
+}
+
+fn longest(load: &PackageInput) -> usize {
+    load.width().max(load.height()).max(load.length())
+}
+
+// the package holding the given number of units out of the load
+fn piece(load: &PackageInput, count: usize, units: usize) -> PackageInput {
+    let share = |value: usize| (value as u128 * count as u128).div_ceil(units as u128).max(1) as usize;
+
+    let longest = longest(load);
+    let (width, height, length) = match (load.width(), load.height()) {
+        (width, _) if width == longest => (share(width), load.height(), load.length()),
+        (_, height) if height == longest => (load.width(), share(height), load.length()),
+        _ => (load.width(), load.height(), share(load.length()))
+    };
+
+    load.resized(width, height, length, share(load.mass()))
+}
+
+fn rank(decision: &SortDecision, policy: &SortPolicy) -> Rank {
+    let result = match decision.outcome() {
+        SortOutcome::Sorted(result) => result,
+        SortOutcome::Uncertain { conservative, .. } => conservative,
+        SortOutcome::NeedsRemeasure(_) => return UNACCEPTABLE
+    };
+    match policy.rules().built_in(result) {
+        SortResult::Standard => match decision.outcome() {
+            SortOutcome::Sorted(_) => STANDARD,
+            _ => SPECIAL
+        },
+        SortResult::Special => SPECIAL,
+        _ => UNACCEPTABLE
+    }
+}
+
+// the most units a single package can hold without exceeding the given rank
+fn most_units(units: usize, at_most: Rank, rank: impl Fn(usize) -> Rank) -> usize {
+    let (mut fewest, mut most) = (0, units);
+    while fewest < most {
+        let count = most - (most - fewest) / 2;
+        match rank(count) <= at_most {
+            true => fewest = count,
+            false => most = count - 1
+        }
+    }
+    fewest
+}
+
+// shares the units over the packages, filling the standard ones first
+fn share(units: usize, packages: usize, standards: usize, most_standard: usize) -> Vec<usize> {
+    if standards == packages {
+        return (0..packages).map(|index| units / packages + usize::from(index < units % packages)).collect();
+    }
+
+    let remaining = units - standards * most_standard;
+    let specials = packages - standards;
+    std::iter::repeat_n(most_standard, standards)
+        .chain((0..specials).map(|index| remaining / specials + usize::from(index < remaining % specials)))
+        .collect()
+}
+
+impl std::fmt::Display for SplitPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let pieces: Vec<String> = self.pieces.iter().map(|(_, decision)| decision.to_string()).collect();
+        let noun = if self.pieces.len() == 1 { "package" } else { "packages" };
+        write!(f, "{} {}: {}", self.pieces.len(), noun, pieces.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::attributes::Attribute;
+    use crate::package::dimensions::test_dependencies::ParcelDimensions;
+    use crate::sort_policy::rules::{EvaluationMode, Predicate, Rule, RuleSet};
+    use quickcheck::{Arbitrary, Gen};
+
+    fn split(load: &PackageInput, divisibility: Divisibility) -> SplitPlan {
+        plan(load, &divisibility, &SortPolicy::default()).unwrap().unwrap()
+    }
+
+    fn measurements(plan: &SplitPlan) -> Vec<(usize, usize, usize, usize)> {
+        plan.pieces().iter().map(|(piece, _)| (piece.width(), piece.height(), piece.length(), piece.mass())).collect()
+    }
+
+    #[test]
+    fn rejected_loads_are_split_into_the_fewest_packages() {
+        // every cm of the load weighs 0.3 kg
+        let plan = split(&PackageInput::new(50, 200, 30, 60), Divisibility::Anywhere);
+
+        assert_eq!(measurements(&plan), vec![(50, 63, 30, 19), (50, 69, 30, 21), (50, 68, 30, 21)]);
+        let outcomes: Vec<_> = plan.pieces().iter().map(|(_, decision)| decision.outcome().clone()).collect();
+        assert_eq!(outcomes, vec![
+            SortOutcome::Sorted(SortResult::Standard),
+            SortOutcome::Sorted(SortResult::Special),
+            SortOutcome::Sorted(SortResult::Special)
+        ]);
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan.specials(), 2);
+    }
+
+    #[test]
+    fn units_are_never_cut() {
+        let plan = split(&PackageInput::new(50, 200, 30, 60), Divisibility::Units(4));
+        assert_eq!(measurements(&plan), vec![(50, 50, 30, 15); 4]);
+        assert_eq!(plan.specials(), 0);
+    }
+
+    #[test]
+    fn acceptable_loads_are_kept_whole() {
+        let plan = split(&PackageInput::new(30, 20, 10, 5), Divisibility::Anywhere);
+        assert_eq!(measurements(&plan), vec![(30, 20, 10, 5)]);
+        assert_eq!(plan.to_string(), "1 package: standard");
+    }
+
+    #[test]
+    fn loads_whose_smallest_part_is_rejected_cannot_be_split() {
+        let load = PackageInput::new(100, 100, 100, 50);
+        assert_eq!(plan(&load, &Divisibility::Units(1), &SortPolicy::default()), Ok(None));
+
+        let rules = RuleSet::new(EvaluationMode::FirstMatch, vec![
+            Rule::new("hazmat", SortResult::Rejected).when(Predicate::HasAttribute(Attribute::Hazmat)),
+            Rule::new("anything else", SortResult::Standard)
+        ]).unwrap();
+        let policy = SortPolicy::default().with_rules(rules);
+        assert_eq!(plan(&load.with_attribute(Attribute::Hazmat), &Divisibility::Anywhere, &policy), Ok(None));
+    }
+
+    #[test]
+    fn invalid_loads_are_refused() {
+        assert_eq!(plan(&PackageInput::new(0, 20, 10, 5), &Divisibility::Anywhere, &SortPolicy::default()), Err(SortError::InvalidWidth(0)));
+        assert_eq!(plan(&PackageInput::new(30, 20, 10, 5), &Divisibility::Units(0), &SortPolicy::default()), Err(SortError::InvalidUnitCount(0)));
+    }
+
+    #[test]
+    fn display_split_plans() {
+        assert_eq!(split(&PackageInput::new(50, 200, 30, 60), Divisibility::Anywhere).to_string(), "3 packages: standard, special, special");
+    }
+
+    #[quickcheck]
+    fn planned_packages_are_never_rejected_and_hold_the_whole_load(load: Load, units: usize) -> bool {
+        let divisibility = match units % 8 {
+            0 => Divisibility::Anywhere,
+            count => Divisibility::Units(count)
+        };
+        match plan(&load.0, &divisibility, &SortPolicy::default()).unwrap() {
+            None => true,
+            Some(plan) => plan.pieces().iter().all(|(_, decision)| rank(decision, &SortPolicy::default()) < UNACCEPTABLE)
+                && plan.pieces().iter().map(|(piece, _)| piece.mass()).sum::<usize>() >= load.0.mass()
+                && plan.pieces().iter().map(|(piece, _)| longest(piece)).sum::<usize>() >= longest(&load.0)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct Load(PackageInput);
+
+    impl Arbitrary for Load {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let dimensions = ParcelDimensions::arbitrary(g);
+            let mass = usize::arbitrary(g) % 500 + 1;
+            Load(PackageInput::new(dimensions.width().value(), dimensions.height().value(), dimensions.length().value(), mass))
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::upper_case_acronyms)]
+
 mod mass_class;
 mod dimension_class;
 mod classification;